@@ -4,25 +4,115 @@ use serde::{
   de::{self, Deserializer, Visitor},
   Deserialize, Serialize,
 };
-use strum::Display;
+use strum::{Display, EnumIter};
 
 use crate::{
-  database::{DbError, Rows},
+  database::{DbError, ObjectKind, Rows},
   focus::Focus,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+pub enum FilterOperator {
+  Eq,
+  NotEq,
+  Lt,
+  Gt,
+  Like,
+  In,
+  IsNull,
+}
+
+impl FilterOperator {
+  pub fn sql(&self) -> &'static str {
+    match self {
+      FilterOperator::Eq => "=",
+      FilterOperator::NotEq => "!=",
+      FilterOperator::Lt => "<",
+      FilterOperator::Gt => ">",
+      FilterOperator::Like => "like",
+      FilterOperator::In => "in",
+      FilterOperator::IsNull => "is null",
+    }
+  }
+}
+
+// a condition on a column of a rows preview. `in` takes a comma separated list of values, where
+// values containing commas are wrapped in single quotes, `is null` ignores the value, and `=` or
+// `!=` to `NULL` (the value of a null cell) check for null
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowFilter {
+  pub column: String,
+  pub operator: FilterOperator,
+  pub value: String,
+}
+
+// how the rows of a table or view are previewed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowsPreview {
+  pub primary_key: Vec<String>, // the rows are ordered by it, when the table has one
+  pub descending: bool,         // latest rows first
+  pub filters: Vec<RowFilter>,
+  pub after: Vec<String>, // primary key of the last row shown, to load the rows after it
+  pub offset: usize,      // rows shown, to load the rest of a table without a primary key
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
 pub enum MenuPreview {
-  Rows,
+  Rows(RowsPreview),
   Columns,
   Constraints,
   Indexes,
   Policies,
+  Triggers,
+  Grants,
+  Statistics,
+  Partitions,
+  ReferencingTables,
+  Definition(ObjectKind, Option<String>),   // (kind, trigger table)
+  Dependencies(ObjectKind, Option<String>), // (kind, trigger table)
+  DistinctValues(String),                   // column
+}
+
+// the actions that can be taken on a table from the menu. which ones are available depends on the
+// database, see `DatabaseQueries::table_actions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
+pub enum TableAction {
+  Count,
+  Truncate,
+  Drop,
+  Rename(String), // new name
+  Vacuum,
+  Analyze,
+  Optimize,
+  Reindex,
+}
+
+impl TableAction {
+  pub fn label(&self) -> &'static str {
+    match self {
+      TableAction::Count => "count rows",
+      TableAction::Truncate => "truncate",
+      TableAction::Drop => "drop",
+      TableAction::Rename(_) => "rename",
+      TableAction::Vacuum => "vacuum",
+      TableAction::Analyze => "analyze",
+      TableAction::Optimize => "optimize",
+      TableAction::Reindex => "reindex",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Display, Deserialize)]
+pub enum DefinitionTarget {
+  Editor,
+  Clipboard,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
 pub enum ExportFormat {
   CSV,
+  Mermaid,
+  Dot,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
@@ -36,8 +126,14 @@ pub enum Action {
   Error(String),
   Help,
   SubmitEditorQuery,
-  Query(Vec<String>, bool),                 // (query_lines, execution_confirmed)
-  MenuPreview(MenuPreview, String, String), // (preview, schema, table)
+  Query(Vec<String>, bool),                              // (query_lines, execution_confirmed)
+  MenuPreview(MenuPreview, String, String),              // (preview, schema, table)
+  LoadMoreRows(usize, Vec<(String, String, String)>),    // (rows shown, last row as (column, type, value))
+  RequestFilterRows(Vec<String>, usize, Option<String>), // (columns, selected column, selected value or null)
+  FollowForeignKey(String, Vec<(String, Option<String>)>, bool), /* (column, row as (column, value or null), to the rows referencing it) */
+  FollowBack,
+  RequestErDiagram(String, Vec<String>),          // (schema, tables)
+  TableAction(TableAction, String, String, bool), // (action, schema, table, run)
   HistoryToEditor(Vec<String>),
   ClearHistory,
   AbortQuery,
@@ -49,6 +145,13 @@ pub enum Action {
   CycleFocusForwards,
   CycleFocusBackwards,
   LoadMenu,
+  LoadMenuColumns(String, String), // (schema, table)
+  LoadAllColumns,
+  SwitchDatabase(String),
+  SavePinnedTables(Vec<(String, String)>), // (schema, table)
+  InsertIntoEditor(String),
+  RequestDefinition(ObjectKind, String, String, Option<String>, DefinitionTarget), /* (kind, schema, name, trigger table, target) */
+  DefinitionToEditor(Vec<String>),
   CopyData(String),
   RequestExportData(i64),
   ExportData(ExportFormat),
@@ -57,4 +160,9 @@ pub enum Action {
   SaveFavorite(String, Vec<String>),
   FavoriteToEditor(Vec<String>),
   DeleteFavorite(String),
+  RequestSwitchConnection,
+  RequestOpenConnection,
+  NextConnection,
+  PreviousConnection,
+  CloseConnection,
 }
@@ -19,14 +19,15 @@ use tui_textarea::{Input, Key};
 
 use super::{scroll_table::SelectionMode, Frame};
 use crate::{
-  action::Action,
+  action::{Action, ExportFormat},
   app::{App, AppState},
   components::{
+    er_diagram::ErDiagram,
     scroll_table::{ScrollDirection, ScrollTable},
     Component,
   },
   config::{Config, KeyBindings},
-  database::{get_headers, header_to_vec, row_to_json, row_to_vec, statement_type_string, DbError, Rows},
+  database::{get_headers, header_to_vec, row_to_json, row_to_vec, statement_type_string, DbError, Rows, Value},
   focus::Focus,
   tui::Event,
   utils::get_export_dir,
@@ -39,12 +40,13 @@ pub enum DataState<'a> {
   Blank,
   Loading,
   NoResults,
-  HasResults(Rows),
+  HasResults(Rows<Value>),
   Explain(Text<'a>),
+  Diagram(ErDiagram, Text<'a>), // the diagram and its drawing
   Error(DbError),
   Cancelled,
   RowsAffected(u64),
-  StatementCompleted(Statement),
+  StatementCompleted(String), // the statement's type
 }
 
 #[derive(Clone, Debug)]
@@ -53,10 +55,26 @@ pub struct ExplainOffsets {
   pub x_offset: u16,
 }
 
+// the values of a row as they're shown, with nulls as `NULL`
+fn strings(row: &[Value]) -> Vec<String> {
+  row.iter().map(|value| value.string.clone()).collect()
+}
+
+// a cell's value, or none when it's null
+fn cell_value(value: &Value) -> Option<String> {
+  (!value.is_null).then(|| value.string.clone())
+}
+
 pub trait SettableDataTable<'a> {
-  fn set_data_state(&mut self, data: Option<Result<Rows, DbError>>, statement_type: Option<Statement>);
+  fn set_data_state(&mut self, data: Option<Result<Rows<Value>, DbError>>, statement_type: Option<Statement>);
   fn set_loading(&mut self);
   fn set_cancelled(&mut self);
+  // for statements that were run without being parsed, like `vacuum`
+  fn set_statement_completed(&mut self, statement: String);
+  // adds more rows of the same query below the ones shown
+  fn append_rows(&mut self, data: Result<Rows<Value>, DbError>);
+  fn set_breadcrumbs(&mut self, breadcrumbs: Vec<String>);
+  fn set_er_diagram(&mut self, diagram: ErDiagram);
 }
 
 pub trait DataComponent<'a, DB: sqlx::Database>: Component<DB> + SettableDataTable<'a> {}
@@ -75,9 +93,18 @@ pub struct Data<'a> {
   explain_height: u16,
   explain_max_x_offset: u16,
   explain_max_y_offset: u16,
+  breadcrumbs: Vec<String>, // the tables followed through foreign keys, ending with the one shown
 }
 
 impl Data<'_> {
+  // redraws the diagram after it's changed, keeping the scroll offsets
+  fn draw_er_diagram(&mut self, diagram: ErDiagram) {
+    let lines = diagram.render();
+    self.explain_width = lines.iter().fold(0_u16, |acc, line| acc.max(line.chars().count() as u16));
+    self.explain_height = lines.len() as u16;
+    self.data_state = DataState::Diagram(diagram, Text::from_iter(lines));
+  }
+
   pub fn new() -> Self {
     Data {
       command_tx: None,
@@ -89,11 +116,12 @@ impl Data<'_> {
       explain_height: 0,
       explain_max_x_offset: 0,
       explain_max_y_offset: 0,
+      breadcrumbs: vec![],
     }
   }
 
   pub fn scroll(&mut self, direction: ScrollDirection) {
-    if let DataState::Explain(_) = self.data_state {
+    if let DataState::Explain(_) | DataState::Diagram(..) = self.data_state {
       if let Some(offsets) = self.explain_scroll.clone() {
         match direction {
           ScrollDirection::Up => {
@@ -120,7 +148,7 @@ impl Data<'_> {
   }
 
   pub fn top(&mut self) {
-    if let DataState::Explain(_) = self.data_state {
+    if let DataState::Explain(_) | DataState::Diagram(..) = self.data_state {
       match self.explain_scroll {
         Some(ExplainOffsets { x_offset, .. }) => {
           self.explain_scroll = Some(ExplainOffsets { y_offset: 0, x_offset });
@@ -135,7 +163,7 @@ impl Data<'_> {
   }
 
   pub fn bottom(&mut self) {
-    if let DataState::Explain(_) = self.data_state {
+    if let DataState::Explain(_) | DataState::Diagram(..) = self.data_state {
       match self.explain_scroll {
         Some(ExplainOffsets { x_offset, .. }) => {
           self.explain_scroll = Some(ExplainOffsets { y_offset: self.explain_max_y_offset, x_offset });
@@ -150,7 +178,7 @@ impl Data<'_> {
   }
 
   pub fn left(&mut self) {
-    if let DataState::Explain(_) = self.data_state {
+    if let DataState::Explain(_) | DataState::Diagram(..) = self.data_state {
       match self.explain_scroll {
         Some(ExplainOffsets { y_offset, .. }) => {
           self.explain_scroll = Some(ExplainOffsets { y_offset, x_offset: 0 });
@@ -165,7 +193,7 @@ impl Data<'_> {
   }
 
   pub fn right(&mut self) {
-    if let DataState::Explain(_) = self.data_state {
+    if let DataState::Explain(_) | DataState::Diagram(..) = self.data_state {
       match self.explain_scroll {
         Some(ExplainOffsets { y_offset, .. }) => {
           self.explain_scroll = Some(ExplainOffsets { y_offset, x_offset: self.explain_max_x_offset });
@@ -181,7 +209,7 @@ impl Data<'_> {
 }
 
 impl<'a> SettableDataTable<'a> for Data<'a> {
-  fn set_data_state(&mut self, data: Option<Result<Rows, DbError>>, statement_type: Option<Statement>) {
+  fn set_data_state(&mut self, data: Option<Result<Rows<Value>, DbError>>, statement_type: Option<Statement>) {
     self.explain_width = 0;
     self.explain_height = 0;
     self.explain_max_x_offset = 0;
@@ -196,26 +224,26 @@ impl<'a> SettableDataTable<'a> for Data<'a> {
           && statement_type.is_some()
           && !matches!(statement_type, Some(Statement::Query(_)))
         {
-          self.data_state = DataState::StatementCompleted(statement_type.unwrap());
+          self.data_state = DataState::StatementCompleted(statement_type_string(&statement_type.unwrap()));
         } else if rows.rows.is_empty() {
           self.data_state = DataState::NoResults;
         } else if matches!(statement_type, Some(Statement::Explain { .. })) {
-          self.explain_width = rows.rows.iter().fold(0_u16, |acc, r| acc.max(r.join(" ").len() as u16));
+          self.explain_width = rows.rows.iter().fold(0_u16, |acc, r| acc.max(strings(r).join(" ").len() as u16));
           self.explain_height = rows.rows.len() as u16;
           self.explain_scroll = Some(ExplainOffsets { y_offset: 0, x_offset: 0 });
-          self.data_state = DataState::Explain(Text::from_iter(rows.rows.iter().map(|r| r.join(" "))));
+          self.data_state = DataState::Explain(Text::from_iter(rows.rows.iter().map(|r| strings(r).join(" "))));
         } else {
           let header_row = Row::new(
             rows.headers.iter().map(|h| Cell::from(format!("{}\n{}", h.name, h.type_name))).collect::<Vec<Cell>>(),
           )
           .height(2)
           .bottom_margin(1);
-          let value_rows = rows.rows.iter().map(|r| Row::new(r.clone()).bottom_margin(1));
+          let value_rows = rows.rows.iter().map(|r| Row::new(strings(r)).bottom_margin(1));
 
           let mut max_widths: HashMap<u16, u16> = HashMap::new();
 
           for row in &rows.rows {
-            for (column_index, c) in row.iter().enumerate() {
+            for (column_index, Value { string: c, .. }) in row.iter().enumerate() {
               let len = c.len() as u16;
               log::info!("{column_index} - {len} - {c}");
               match max_widths.entry(column_index as u16) {
@@ -275,6 +303,39 @@ impl<'a> SettableDataTable<'a> for Data<'a> {
   fn set_cancelled(&mut self) {
     self.data_state = DataState::Cancelled;
   }
+
+  fn set_statement_completed(&mut self, statement: String) {
+    self.set_data_state(None, None);
+    self.data_state = DataState::StatementCompleted(statement);
+  }
+
+  fn append_rows(&mut self, data: Result<Rows<Value>, DbError>) {
+    let DataState::HasResults(shown) = &self.data_state else {
+      return self.set_data_state(Some(data), None);
+    };
+    match data {
+      Ok(more) if !more.rows.is_empty() => {
+        let mut rows = shown.clone();
+        rows.rows.extend(more.rows);
+        let previous = std::mem::take(&mut self.scrollable);
+        self.set_data_state(Some(Ok(rows)), None);
+        self.scrollable.restore_scroll(&previous);
+      },
+      // every row is already shown
+      Ok(_) => {},
+      Err(e) => self.set_data_state(Some(Err(e)), None),
+    }
+  }
+
+  fn set_breadcrumbs(&mut self, breadcrumbs: Vec<String>) {
+    self.breadcrumbs = breadcrumbs;
+  }
+
+  fn set_er_diagram(&mut self, diagram: ErDiagram) {
+    self.set_data_state(None, None);
+    self.explain_scroll = Some(ExplainOffsets { y_offset: 0, x_offset: 0 });
+    self.draw_er_diagram(diagram);
+  }
 }
 
 impl<DB: Database> Component<DB> for Data<'_> {
@@ -320,11 +381,64 @@ impl<DB: Database> Component<DB> for Data<'_> {
     }
     let input = Input::from(key);
     match input {
+      Input { key: key @ (Key::Char('+') | Key::Char('=') | Key::Char('-')), .. }
+        if matches!(self.data_state, DataState::Diagram(..)) =>
+      {
+        if let DataState::Diagram(mut diagram, _) = std::mem::take(&mut self.data_state) {
+          if key == Key::Char('-') {
+            diagram.zoom_out();
+          } else {
+            diagram.zoom_in();
+          }
+          self.draw_er_diagram(diagram);
+        }
+      },
+      Input { key: key @ (Key::Char('M') | Key::Char('D')), .. } => {
+        if let DataState::Diagram(..) = &self.data_state {
+          let format = if key == Key::Char('M') { ExportFormat::Mermaid } else { ExportFormat::Dot };
+          self.command_tx.clone().unwrap().send(Action::ExportData(format))?;
+        }
+      },
       Input { key: Key::Char('P'), .. } => {
         if let DataState::HasResults(rows) = &self.data_state {
           self.command_tx.clone().unwrap().send(Action::RequestExportData(rows.rows.len() as i64))?;
         }
       },
+      Input { key: Key::Char('f'), ctrl: false, .. } => {
+        if let DataState::HasResults(Rows { headers, rows, .. }) = &self.data_state {
+          let (x, y) = self.scrollable.get_cell_offsets();
+          let value = rows.get(y).and_then(|row| row.get(x as usize)).map_or(Some(String::new()), cell_value);
+          self.command_tx.clone().unwrap().send(Action::RequestFilterRows(
+            header_to_vec(headers),
+            x as usize,
+            value,
+          ))?;
+        } else if let DataState::NoResults = &self.data_state {
+          // there are no columns to pick from, but the filters can still be removed
+          self.command_tx.clone().unwrap().send(Action::RequestFilterRows(vec![], 0, Some(String::new())))?;
+        }
+      },
+      Input { key: Key::Char('m'), .. } => {
+        if let DataState::HasResults(rows) = &self.data_state {
+          let columns = rows.headers.iter().map(|h| (h.name.clone(), h.type_name.clone()));
+          let last_row = rows
+            .rows
+            .last()
+            .map(|row| columns.zip(strings(row)).map(|((name, type_name), value)| (name, type_name, value)).collect())
+            .unwrap_or_default();
+          self.command_tx.clone().unwrap().send(Action::LoadMoreRows(rows.rows.len(), last_row))?;
+        }
+      },
+      Input { key: key @ (Key::Char('r') | Key::Char('R')), .. } => {
+        if let DataState::HasResults(Rows { headers, rows, .. }) = &self.data_state {
+          let (x, y) = self.scrollable.get_cell_offsets();
+          if let (Some(header), Some(row)) = (headers.get(x as usize), rows.get(y)) {
+            let row = headers.iter().map(|h| h.name.clone()).zip(row.iter().map(cell_value)).collect();
+            let reverse = key == Key::Char('R');
+            self.command_tx.clone().unwrap().send(Action::FollowForeignKey(header.name.clone(), row, reverse))?;
+          }
+        }
+      },
       Input { key: Key::Right, .. } | Input { key: Key::Char('l'), .. } => {
         self.scroll(ScrollDirection::Right);
       },
@@ -390,6 +504,7 @@ impl<DB: Database> Component<DB> for Data<'_> {
           Some(SelectionMode::Cell) => {
             self.scrollable.transition_selection_mode(Some(SelectionMode::Row));
           },
+          None => self.command_tx.clone().unwrap().send(Action::FollowBack)?,
           _ => {},
         };
       },
@@ -399,18 +514,18 @@ impl<DB: Database> Component<DB> for Data<'_> {
           let row = &rows[y];
           match self.scrollable.get_selection_mode() {
             Some(SelectionMode::Row) => {
-              let row_string = row.join(", ");
+              let row_string = strings(row).join(", ");
               self.command_tx.clone().unwrap().send(Action::CopyData(row_string))?;
               self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
             },
             Some(SelectionMode::Cell) => {
-              let cell = row[x as usize].clone();
+              let cell = row[x as usize].string.clone();
               self.command_tx.clone().unwrap().send(Action::CopyData(cell))?;
               self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
             },
             _ => {},
           }
-        } else if let DataState::Explain(text) = &self.data_state {
+        } else if let DataState::Explain(text) | DataState::Diagram(_, text) = &self.data_state {
           self.command_tx.clone().unwrap().send(Action::CopyData(text.to_string()))?;
           self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
         } else if let DataState::Error(err) = &self.data_state {
@@ -430,16 +545,28 @@ impl<DB: Database> Component<DB> for Data<'_> {
     if let Action::Query(query, confirmed) = action {
       self.scrollable.reset_scroll();
     } else if let Action::ExportData(format) = action {
-      let DataState::HasResults(rows) = &self.data_state else {
-        return Ok(None);
-      };
-      let name = format!("rainfrog_export_{}_rows_{}.csv", rows.rows.len(), chrono::Utc::now().timestamp());
-      let mut writer = Writer::from_path(get_export_dir().join(name))?;
-      writer.write_record(header_to_vec(&rows.headers))?;
-      for row in &rows.rows {
-        writer.write_record(row)?;
+      match (&format, &mut self.data_state) {
+        (ExportFormat::CSV, DataState::HasResults(rows)) => {
+          let name = format!("rainfrog_export_{}_rows_{}.csv", rows.rows.len(), chrono::Utc::now().timestamp());
+          let mut writer = Writer::from_path(get_export_dir().join(name))?;
+          writer.write_record(header_to_vec(&rows.headers))?;
+          for row in &rows.rows {
+            writer.write_record(strings(row))?;
+          }
+          writer.flush()?;
+        },
+        (ExportFormat::Mermaid | ExportFormat::Dot, DataState::Diagram(diagram, _)) => {
+          let (extension, contents) = match format {
+            ExportFormat::Mermaid => ("mmd", diagram.to_mermaid()),
+            _ => ("dot", diagram.to_dot()),
+          };
+          let name = format!("rainfrog_export_{}_er_{}.{}", diagram.schema, chrono::Utc::now().timestamp(), extension);
+          let path = get_export_dir().join(name);
+          std::fs::write(&path, contents)?;
+          diagram.exported = Some(path.display().to_string());
+        },
+        _ => return Ok(None),
       }
-      writer.flush()?;
       self.command_tx.clone().unwrap().send(Action::ExportDataFinished)?;
     }
     Ok(None)
@@ -473,7 +600,7 @@ impl<DB: Database> Component<DB> for Data<'_> {
           format!(" 󰆼 results <alt+3> (row {} of {})", y.saturating_add(1), rows.len())
         },
         Some(SelectionMode::Cell) => {
-          format!(" 󰆼 results <alt+3> (row {} of {}) - {} ", y.saturating_add(1), rows.len(), row[x as usize].clone())
+          format!(" 󰆼 results <alt+3> (row {} of {}) - {} ", y.saturating_add(1), rows.len(), row[x as usize].string)
         },
         Some(SelectionMode::Copied) => {
           format!(" 󰆼 results <alt+3> ({} rows) - copied! ", rows.len())
@@ -481,6 +608,19 @@ impl<DB: Database> Component<DB> for Data<'_> {
         _ => format!(" 󰆼 results <alt+3> ({} rows)", rows.len()),
       };
       block = block.title(title_string);
+    } else if let DataState::Diagram(diagram, _) = &self.data_state {
+      let mut title_string = format!(
+        " 󰆼 er diagram of {} <alt+3> ({} tables, zoom: {})",
+        if diagram.schema.is_empty() { "main" } else { &diagram.schema },
+        diagram.table_count(),
+        diagram.zoom.label()
+      );
+      if let Some(file) = &diagram.exported {
+        title_string.push_str(&format!(" - exported to {} ", file));
+      } else if let Some(SelectionMode::Copied) = self.scrollable.get_selection_mode() {
+        title_string.push_str(" - copied! ");
+      }
+      block = block.title(title_string).title(Line::from(" [+|-] zoom [M] mermaid [D] dot ").right_aligned());
     } else {
       let title_string = match self.scrollable.get_selection_mode() {
         Some(SelectionMode::Copied) => " 󰆼 results <alt+3> - copied! ",
@@ -489,15 +629,17 @@ impl<DB: Database> Component<DB> for Data<'_> {
       block = block.title(title_string);
     }
 
+    if !self.breadcrumbs.is_empty() {
+      block = block.title(Line::from(format!(" {} ", self.breadcrumbs.join(" › "))).right_aligned());
+    }
+
     match &self.data_state {
       DataState::NoResults => {
         f.render_widget(Paragraph::new("no results").wrap(Wrap { trim: false }).block(block), area);
       },
       DataState::StatementCompleted(statement) => {
         f.render_widget(
-          Paragraph::new(format!("{} statement completed", statement_type_string(statement)))
-            .wrap(Wrap { trim: false })
-            .block(block),
+          Paragraph::new(format!("{} statement completed", statement)).wrap(Wrap { trim: false }).block(block),
           area,
         );
       },
@@ -512,7 +654,7 @@ impl<DB: Database> Component<DB> for Data<'_> {
       DataState::Blank => {
         f.render_widget(Paragraph::new("").wrap(Wrap { trim: false }).block(block), area);
       },
-      DataState::Explain(text) => {
+      DataState::Explain(text) | DataState::Diagram(_, text) => {
         let mut paragraph = Paragraph::new(text.clone()).block(block);
         if let Some(offsets) = self.explain_scroll.clone() {
           paragraph = paragraph.scroll((offsets.y_offset, offsets.x_offset));
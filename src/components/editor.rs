@@ -41,6 +41,16 @@ fn keyword_regex() -> String {
   format!("(?i)(^|[^a-zA-Z0-9\'\"`._]+)({})($|[^a-zA-Z0-9\'\"`._]+)", get_keywords().join("|"))
 }
 
+pub trait EditorLines {
+  fn get_lines(&self) -> Vec<String>;
+  fn set_lines(&mut self, lines: Vec<String>);
+}
+
+pub trait EditorComponent<DB: Database>: Component<DB> + EditorLines {}
+impl<T, DB: Database> EditorComponent<DB> for T where T: Component<DB> + EditorLines
+{
+}
+
 #[derive(Default)]
 pub struct Editor<'a> {
   command_tx: Option<UnboundedSender<Action>>,
@@ -125,6 +135,17 @@ impl Editor<'_> {
   }
 }
 
+impl EditorLines for Editor<'_> {
+  fn get_lines(&self) -> Vec<String> {
+    self.textarea.lines().to_vec()
+  }
+
+  fn set_lines(&mut self, lines: Vec<String>) {
+    self.textarea = TextArea::from(lines);
+    self.textarea.set_search_pattern(keyword_regex()).unwrap();
+  }
+}
+
 impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
   fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
     self.vim_state.register_action_handler(self.command_tx.clone())?;
@@ -186,11 +207,21 @@ impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
           return Ok(None);
         }
         let query = match preview_type {
-          MenuPreview::Rows => DB::preview_rows_query(&schema, &table),
+          MenuPreview::Rows(preview) => {
+            DB::preview_rows_query(&schema, &table, &preview, self.config.settings.preview_limit())
+          },
           MenuPreview::Columns => DB::preview_columns_query(&schema, &table),
           MenuPreview::Constraints => DB::preview_constraints_query(&schema, &table),
           MenuPreview::Indexes => DB::preview_indexes_query(&schema, &table),
           MenuPreview::Policies => DB::preview_policies_query(&schema, &table),
+          MenuPreview::Triggers => DB::preview_triggers_query(&schema, &table),
+          MenuPreview::Grants => DB::preview_grants_query(&schema, &table),
+          MenuPreview::Statistics => DB::preview_statistics_query(&schema, &table),
+          MenuPreview::Partitions => DB::preview_partitions_query(&schema, &table),
+          MenuPreview::ReferencingTables => DB::preview_referencing_tables_query(&schema, &table),
+          MenuPreview::Definition(kind, on) => DB::preview_definition_query(kind, &schema, &table, on.as_deref()),
+          MenuPreview::Dependencies(kind, on) => DB::preview_dependencies_query(kind, &schema, &table, on.as_deref()),
+          MenuPreview::DistinctValues(column) => DB::preview_distinct_values_query(&schema, &table, &column),
         };
         self.textarea = TextArea::from(vec![query.clone()]);
         self.textarea.set_search_pattern(keyword_regex()).unwrap();
@@ -199,6 +230,22 @@ impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
         self.command_tx.as_ref().unwrap().send(Action::FocusMenu)?;
         self.command_tx.as_ref().unwrap().send(Action::Query(vec![query.clone()], false))?;
       },
+      Action::TableAction(table_action, schema, table, run) => {
+        let Some(query) = DB::table_action_query(&table_action, &schema, &table) else {
+          return Ok(None);
+        };
+        if run && app_state.query_task.is_some() {
+          return Ok(None);
+        }
+        self.textarea = TextArea::from(vec![query.clone()]);
+        self.textarea.set_search_pattern(keyword_regex()).unwrap();
+        self.command_tx.as_ref().unwrap().send(Action::FocusEditor)?;
+        // destructive actions ask for confirmation like any other query
+        if run {
+          self.command_tx.as_ref().unwrap().send(Action::FocusMenu)?;
+          self.command_tx.as_ref().unwrap().send(Action::Query(vec![query], false))?;
+        }
+      },
       Action::SubmitEditorQuery => {
         if let Some(sender) = &self.command_tx {
           sender.send(Action::Query(self.textarea.lines().to_vec(), false))?;
@@ -208,6 +255,10 @@ impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
         self.textarea = TextArea::from(lines.clone());
         self.textarea.set_search_pattern(keyword_regex()).unwrap();
       },
+      Action::DefinitionToEditor(lines) => {
+        self.textarea = TextArea::from(lines.clone());
+        self.textarea.set_search_pattern(keyword_regex()).unwrap();
+      },
       Action::FavoriteToEditor(lines) => {
         self.textarea = TextArea::from(lines.clone());
         self.textarea.set_search_pattern(keyword_regex()).unwrap();
@@ -215,6 +266,9 @@ impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
       Action::CopyData(data) => {
         self.textarea.set_yank_text(data);
       },
+      Action::InsertIntoEditor(text) => {
+        self.textarea.insert_str(text);
+      },
       _ => {},
     }
     Ok(None)
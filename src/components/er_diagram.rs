@@ -0,0 +1,524 @@
+use crate::database::{ForeignKey, Rows};
+
+// how much of each table is drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zoom {
+  Names, // the table names only
+  Keys,  // and the columns of their foreign keys
+  #[default]
+  Columns, // and every column with its type
+}
+
+impl Zoom {
+  pub fn label(&self) -> &'static str {
+    match self {
+      Zoom::Names => "names",
+      Zoom::Keys => "keys",
+      Zoom::Columns => "columns",
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct Table {
+  name: String,
+  columns: Vec<(String, String)>, // (name, type)
+}
+
+// the tables of a schema and the foreign keys between them, drawn as boxes joined by lines.
+// tables are laid out in layers from left to right, each one right of the tables it references
+#[derive(Debug, Clone, Default)]
+pub struct ErDiagram {
+  pub schema: String,
+  tables: Vec<Table>,
+  keys: Vec<ForeignKey>,
+  pub zoom: Zoom,
+  pub exported: Option<String>, // the file the diagram was last exported to
+}
+
+// a table's box, with its top left corner at (x, y)
+struct Placed {
+  x: usize,
+  y: usize,
+  width: usize,
+  rows: Vec<String>, // the name, then the columns shown
+}
+
+impl Placed {
+  fn height(&self) -> usize {
+    match self.rows.len() {
+      1 => 3,
+      rows => rows + 3,
+    }
+  }
+
+  fn right(&self) -> usize {
+    self.x + self.width - 1
+  }
+
+  // the row a line to `column` attaches to, or the name's row when the column isn't shown
+  fn row_of(&self, column: &str) -> usize {
+    let shown = self.rows.iter().skip(1).position(|row| row.split(' ').next() == Some(column));
+    match shown {
+      Some(i) => self.y + 3 + i,
+      None => self.y + 1,
+    }
+  }
+}
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+// characters on a grid, where lines are kept as the directions they leave each cell in, so lines
+// meeting or crossing are joined
+struct Canvas {
+  lines: Vec<Vec<u8>>,
+  text: Vec<Vec<Option<char>>>,
+}
+
+impl Canvas {
+  fn new(width: usize, height: usize) -> Self {
+    Self { lines: vec![vec![0; width]; height], text: vec![vec![None; width]; height] }
+  }
+
+  fn horizontal(&mut self, y: usize, x1: usize, x2: usize) {
+    let (start, end) = (x1.min(x2), x1.max(x2));
+    for x in start..=end {
+      if x > start {
+        self.lines[y][x] |= LEFT;
+      }
+      if x < end {
+        self.lines[y][x] |= RIGHT;
+      }
+    }
+  }
+
+  fn vertical(&mut self, x: usize, y1: usize, y2: usize) {
+    let (start, end) = (y1.min(y2), y1.max(y2));
+    for y in start..=end {
+      if y > start {
+        self.lines[y][x] |= UP;
+      }
+      if y < end {
+        self.lines[y][x] |= DOWN;
+      }
+    }
+  }
+
+  fn put(&mut self, x: usize, y: usize, c: char) {
+    self.text[y][x] = Some(c);
+  }
+
+  fn write(&mut self, x: usize, y: usize, text: &str) {
+    for (i, c) in text.chars().enumerate() {
+      self.put(x + i, y, c);
+    }
+  }
+
+  fn draw_box(&mut self, placed: &Placed) {
+    let (left, right, bottom) = (placed.x, placed.right(), placed.y + placed.height() - 1);
+    let border = "─".repeat(placed.width - 2);
+    self.write(left, placed.y, &format!("┌{}┐", border));
+    self.write(left, bottom, &format!("└{}┘", border));
+    for y in placed.y + 1..bottom {
+      // lines end on the box's sides
+      self.put(left, y, if self.lines[y][left] & LEFT != 0 { '┤' } else { '│' });
+      self.put(right, y, if self.lines[y][right] & RIGHT != 0 { '├' } else { '│' });
+      self.write(left + 1, y, &" ".repeat(placed.width - 2));
+    }
+    self.write(left + 2, placed.y + 1, &placed.rows[0]);
+    if placed.rows.len() > 1 {
+      self.write(left, placed.y + 2, &format!("├{}┤", border));
+      for (i, row) in placed.rows.iter().skip(1).enumerate() {
+        self.write(left + 2, placed.y + 3 + i, row);
+      }
+    }
+  }
+
+  fn lines(self) -> Vec<String> {
+    self
+      .text
+      .iter()
+      .zip(self.lines)
+      .map(|(text, lines)| {
+        let line: String = text.iter().zip(lines).map(|(c, directions)| c.unwrap_or(line_char(directions))).collect();
+        line.trim_end().to_owned()
+      })
+      .collect()
+  }
+}
+
+fn line_char(directions: u8) -> char {
+  match directions {
+    0 => ' ',
+    d if d == UP | DOWN | LEFT | RIGHT => '┼',
+    d if d == UP | DOWN | RIGHT => '├',
+    d if d == UP | DOWN | LEFT => '┤',
+    d if d == LEFT | RIGHT | DOWN => '┬',
+    d if d == LEFT | RIGHT | UP => '┴',
+    d if d == DOWN | RIGHT => '┌',
+    d if d == DOWN | LEFT => '┐',
+    d if d == UP | RIGHT => '└',
+    d if d == UP | LEFT => '┘',
+    d if d & (UP | DOWN) != 0 => '│',
+    _ => '─',
+  }
+}
+
+// a name mermaid accepts unquoted
+fn mermaid_name(name: &str) -> String {
+  let name: String = name.chars().map(|c| if c.is_alphanumeric() || c == '_' || c == '-' { c } else { '_' }).collect();
+  match name.chars().next() {
+    Some(c) if c.is_alphabetic() || c == '_' => name,
+    _ => format!("_{}", name),
+  }
+}
+
+fn dot_escape(text: &str) -> String {
+  text.chars().fold(String::new(), |mut escaped, c| {
+    if matches!(c, '\\' | '"' | '{' | '}' | '|' | '<' | '>') {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+    escaped
+  })
+}
+
+impl ErDiagram {
+  // `columns` are the rows of `preview_all_columns_query`, and `keys` the rows of
+  // `preview_schema_foreign_keys_query`. keys to tables outside of the diagram are left out
+  pub fn new(schema: String, tables: Vec<String>, columns: &Rows, keys: &Rows) -> Self {
+    let tables: Vec<Table> = tables
+      .into_iter()
+      .map(|name| {
+        let columns = columns
+          .rows
+          .iter()
+          .filter(|row| row.first() == Some(&schema) && row.get(1) == Some(&name))
+          .filter_map(|row| Some((row.get(2)?.clone(), row.get(3).cloned().unwrap_or_default())))
+          .collect();
+        Table { name, columns }
+      })
+      .collect();
+    let has_table = |name: &str| tables.iter().any(|table| table.name == name);
+    let keys = ForeignKey::from_rows(keys)
+      .into_iter()
+      .filter(|key| key.schema == schema && key.referenced_schema == schema)
+      .filter(|key| has_table(&key.table) && has_table(&key.referenced_table))
+      .collect();
+    Self { schema, tables, keys, zoom: Zoom::default(), exported: None }
+  }
+
+  pub fn table_count(&self) -> usize {
+    self.tables.len()
+  }
+
+  pub fn zoom_in(&mut self) {
+    self.zoom = match self.zoom {
+      Zoom::Names => Zoom::Keys,
+      _ => Zoom::Columns,
+    };
+  }
+
+  pub fn zoom_out(&mut self) {
+    self.zoom = match self.zoom {
+      Zoom::Columns => Zoom::Keys,
+      _ => Zoom::Names,
+    };
+  }
+
+  fn index(&self, table: &str) -> Option<usize> {
+    self.tables.iter().position(|t| t.name == table)
+  }
+
+  // (key, referencing table, referenced table) for every key
+  fn edges(&self) -> Vec<(&ForeignKey, usize, usize)> {
+    self
+      .keys
+      .iter()
+      .filter_map(|key| Some((key, self.index(&key.table)?, self.index(&key.referenced_table)?)))
+      .collect()
+  }
+
+  // the layer of each table, one more than the layers of the tables it references. a cycle of
+  // references stops growing after as many rounds as there are tables
+  fn layers(&self) -> Vec<usize> {
+    let edges = self.edges();
+    let mut layers = vec![0; self.tables.len()];
+    for _ in 0..self.tables.len() {
+      let mut changed = false;
+      for (_, child, parent) in &edges {
+        if child != parent && layers[*child] <= layers[*parent] {
+          layers[*child] = layers[*parent] + 1;
+          changed = true;
+        }
+      }
+      if !changed {
+        break;
+      }
+    }
+    // closes the gaps a cycle leaves
+    let mut used = layers.clone();
+    used.sort_unstable();
+    used.dedup();
+    layers.iter().map(|layer| used.binary_search(layer).unwrap_or_else(|i| i)).collect()
+  }
+
+  fn rows(&self, table: usize) -> Vec<String> {
+    let Table { name, columns } = &self.tables[table];
+    let in_key = |column: &str| {
+      self.keys.iter().any(|key| {
+        (key.table == *name && key.columns.iter().any(|c| c == column))
+          || (key.referenced_table == *name && key.referenced_columns.iter().any(|c| c == column))
+      })
+    };
+    let name_width = columns.iter().map(|(column, _)| column.chars().count()).max().unwrap_or(0);
+    let columns = columns.iter().filter_map(|(column, type_name)| {
+      match self.zoom {
+        Zoom::Names => None,
+        Zoom::Keys => in_key(column).then(|| column.clone()),
+        Zoom::Columns => Some(format!("{:width$} {}", column, type_name, width = name_width)),
+      }
+    });
+    std::iter::once(name.clone()).chain(columns).collect()
+  }
+
+  // the diagram at its zoom, as lines of text
+  pub fn render(&self) -> Vec<String> {
+    if self.tables.is_empty() {
+      return vec![format!("{} has no tables", self.schema)];
+    }
+    let layers = self.layers();
+    let edges = self.edges();
+    let layer_count = layers.iter().max().map_or(0, |layer| layer + 1);
+
+    // tables are ordered in their layer by where the tables they reference are, to keep lines
+    // short. the first layer has the tables without any keys last
+    let mut order: Vec<Vec<usize>> = vec![vec![]; layer_count];
+    let mut position = vec![0.0; self.tables.len()];
+    for (layer, members) in order.iter_mut().enumerate() {
+      *members = (0..self.tables.len()).filter(|t| layers[*t] == layer).collect();
+      let weight = |table: usize| {
+        let parents: Vec<f64> = edges
+          .iter()
+          .filter(|(_, child, parent)| *child == table && child != parent)
+          .map(|(.., p)| position[*p])
+          .collect();
+        match parents.is_empty() {
+          true => f64::MAX,
+          false => parents.iter().sum::<f64>() / parents.len() as f64,
+        }
+      };
+      match layer {
+        0 => members.sort_by_key(|t| !edges.iter().any(|(_, child, parent)| child == t || parent == t)),
+        _ => members.sort_by(|a, b| weight(*a).total_cmp(&weight(*b))),
+      }
+      for (i, table) in members.iter().enumerate() {
+        position[*table] = i as f64;
+      }
+    }
+
+    // each line between layers runs down its own channel of the gap it crosses last
+    let gap_of = |child: usize, parent: usize| {
+      if layers[child] > layers[parent] {
+        layers[child] - 1
+      } else {
+        layers[parent]
+      }
+    };
+    let mut channels = vec![0; layer_count];
+    let mut edge_channels = vec![0; edges.len()];
+    for (i, (_, child, parent)) in edges.iter().enumerate() {
+      if child != parent {
+        let gap = gap_of(*child, *parent);
+        edge_channels[i] = channels[gap];
+        channels[gap] += 1;
+      }
+    }
+
+    let mut placed: Vec<Option<Placed>> = (0..self.tables.len()).map(|_| None).collect();
+    let mut gap_starts = vec![0; layer_count];
+    let (mut x, mut height) = (0, 0);
+    for (layer, members) in order.iter().enumerate() {
+      let rows: Vec<Vec<String>> = members.iter().map(|t| self.rows(*t)).collect();
+      let width = rows.iter().flatten().map(|row| row.chars().count()).max().unwrap_or(0) + 4;
+      let mut y = 0;
+      for (table, rows) in members.iter().zip(rows) {
+        let table_box = Placed { x, y, width, rows };
+        y += table_box.height() + 1;
+        placed[*table] = Some(table_box);
+      }
+      height = height.max(y - 1);
+      gap_starts[layer] = x + width;
+      x += width + 4 + 2 * channels[layer];
+    }
+    let placed: Vec<Placed> = placed.into_iter().flatten().collect();
+
+    let mut canvas = Canvas::new(x + 3, height);
+    let mut arrows = vec![];
+    for (i, (key, child, parent)) in edges.iter().enumerate() {
+      let (from, to) = (&placed[*child], &placed[*parent]);
+      let (from_y, to_y) = (from.row_of(&key.columns[0]), to.row_of(&key.referenced_columns[0]));
+      arrows.push((to.right() + 1, to_y));
+      if child == parent {
+        canvas.horizontal(from_y, to.right(), to.right() + 2);
+        canvas.vertical(to.right() + 2, from_y, to_y);
+        canvas.horizontal(to_y, to.right(), to.right() + 2);
+        continue;
+      }
+      let channel = gap_starts[gap_of(*child, *parent)] + 2 + 2 * edge_channels[i];
+      // a table in a later layer is joined on its left, and any other on its right
+      let end = if layers[*child] > layers[*parent] { from.x } else { from.right() };
+      canvas.horizontal(to_y, to.right(), channel);
+      canvas.vertical(channel, to_y, from_y);
+      canvas.horizontal(from_y, channel, end);
+    }
+    for table_box in &placed {
+      canvas.draw_box(table_box);
+    }
+    for (x, y) in arrows {
+      canvas.put(x, y, '◀');
+    }
+    canvas.lines()
+  }
+
+  pub fn to_mermaid(&self) -> String {
+    let mut lines = vec!["erDiagram".to_owned()];
+    for Table { name, columns } in &self.tables {
+      lines.push(format!("  {} {{", mermaid_name(name)));
+      for (column, type_name) in columns {
+        let is_key = self.keys.iter().any(|key| key.table == *name && key.columns.contains(column));
+        lines.push(format!(
+          "    {} {}{}",
+          mermaid_name(if type_name.is_empty() { "unknown" } else { type_name }),
+          mermaid_name(column),
+          if is_key { " FK" } else { "" }
+        ));
+      }
+      lines.push("  }".to_owned());
+    }
+    for key in &self.keys {
+      lines.push(format!(
+        "  {} ||--o{{ {} : \"{}\"",
+        mermaid_name(&key.referenced_table),
+        mermaid_name(&key.table),
+        key.name.replace('"', "'")
+      ));
+    }
+    lines.join("\n") + "\n"
+  }
+
+  pub fn to_dot(&self) -> String {
+    let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut lines = vec![
+      format!("digraph {} {{", quote(&self.schema)),
+      "  rankdir=LR;".to_owned(),
+      "  node [shape=record, fontname=\"monospace\"];".to_owned(),
+    ];
+    for Table { name, columns } in &self.tables {
+      let columns: String = columns
+        .iter()
+        .map(|(column, type_name)| format!("{}: {}\\l", dot_escape(column), dot_escape(type_name)))
+        .collect();
+      let label = match columns.is_empty() {
+        true => dot_escape(name),
+        false => format!("{}|{}", dot_escape(name), columns),
+      };
+      lines.push(format!("  {} [label=\"{}\"];", quote(name), label));
+    }
+    for key in &self.keys {
+      lines.push(format!(
+        "  {} -> {} [label={}];",
+        quote(&key.table),
+        quote(&key.referenced_table),
+        quote(&key.columns.join(", "))
+      ));
+    }
+    lines.push("}".to_owned());
+    lines.join("\n") + "\n"
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::Header;
+
+  fn rows(rows: &[&[&str]]) -> Rows {
+    Rows {
+      headers: vec![Header { name: "column".to_owned(), type_name: "TEXT".to_owned() }],
+      rows: rows.iter().map(|row| row.iter().map(|s| s.to_string()).collect()).collect(),
+      rows_affected: None,
+    }
+  }
+
+  fn diagram() -> ErDiagram {
+    let columns = rows(&[
+      &["app", "users", "id", "int4"],
+      &["app", "users", "name", "text"],
+      &["app", "orders", "id", "int8"],
+      &["app", "orders", "user_id", "int4"],
+      &["app", "items", "order_id", "int8"],
+      &["app", "tags", "name", "text"],
+      &["other", "users", "id", "uuid"],
+    ]);
+    let keys = rows(&[
+      &["orders_user_fk", "app", "orders", "user_id", "app", "users", "id"],
+      &["items_order_fk", "app", "items", "order_id", "app", "orders", "id"],
+      &["orders_audit_fk", "app", "orders", "user_id", "audit", "users", "id"],
+    ]);
+    let tables = ["items", "orders", "tags", "users"].map(String::from).to_vec();
+    ErDiagram::new("app".to_owned(), tables, &columns, &keys)
+  }
+
+  #[test]
+  fn test_er_diagram_layout() {
+    let mut diagram = diagram();
+    // each table is right of the one it references, and the key to another schema is left out
+    assert_eq!(diagram.layers(), vec![2, 1, 0, 0]);
+    assert_eq!(diagram.edges().len(), 2);
+
+    diagram.zoom = Zoom::Names;
+    assert_eq!(diagram.render(), vec![
+      "┌───────┐      ┌────────┐      ┌───────┐",
+      "│ users ├◀─────┤ orders ├◀─────┤ items │",
+      "└───────┘      └────────┘      └───────┘",
+      "",
+      "┌───────┐",
+      "│ tags  │",
+      "└───────┘",
+    ]);
+
+    diagram.zoom_in();
+    let lines = diagram.render();
+    assert_eq!(lines[1], "│ users │      │ orders  │      │ items    │");
+    // the line from orders.user_id runs down its channel to users.id
+    assert_eq!(lines[3], "│ id    ├◀─┐   │ id      ├◀─────┤ order_id │");
+    assert_eq!(lines[4], "└───────┘  └───┤ user_id │      └──────────┘");
+
+    diagram.zoom_in();
+    assert!(diagram.render().iter().any(|line| line.contains("┤ user_id int4 │")));
+    diagram.zoom_out();
+    assert_eq!(diagram.zoom, Zoom::Keys);
+  }
+
+  #[test]
+  fn test_er_diagram_exports() {
+    let diagram = diagram();
+    let mermaid = diagram.to_mermaid();
+    assert!(mermaid.starts_with("erDiagram\n  items {\n    int8 order_id FK\n  }\n"));
+    assert!(mermaid.contains("  tags {\n    text name\n  }\n"));
+    assert!(
+      mermaid.ends_with("  users ||--o{ orders : \"orders_user_fk\"\n  orders ||--o{ items : \"items_order_fk\"\n")
+    );
+
+    let dot = diagram.to_dot();
+    assert!(dot.starts_with("digraph \"app\" {\n  rankdir=LR;\n"));
+    assert!(dot.contains("  \"orders\" [label=\"orders|id: int8\\luser_id: int4\\l\"];\n"));
+    assert!(dot.contains("  \"orders\" -> \"users\" [label=\"user_id\"];\n"));
+    assert_eq!(dot_escape("a|b{c}"), "a\\|b\\{c\\}");
+    assert_eq!(mermaid_name("numeric(10,2)"), "numeric_10_2_");
+  }
+}
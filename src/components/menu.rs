@@ -1,12 +1,13 @@
 use std::{
   borrow::BorrowMut,
-  collections::HashMap,
+  collections::{BTreeMap, HashMap, HashSet},
+  path::{Path, PathBuf},
   sync::{Arc, Mutex},
   time::Duration,
 };
 
 use color_eyre::eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, MouseEventKind};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEventKind};
 use indexmap::IndexMap;
 use ratatui::{prelude::*, widgets::*};
 use serde::{Deserialize, Serialize};
@@ -16,23 +17,249 @@ use tokio::sync::mpsc::UnboundedSender;
 
 use super::{Component, Frame};
 use crate::{
-  action::{Action, MenuPreview},
-  app::{App, AppState},
+  action::{Action, DefinitionTarget, MenuPreview, RowsPreview, TableAction},
+  app::{App, AppState, MetadataTask},
   config::{Config, KeyBindings},
-  database::{get_headers, row_to_json, row_to_vec, DbError, Rows},
+  database::{get_headers, row_to_json, row_to_vec, DbError, ObjectKind, Rows},
   focus::Focus,
+  fuzzy::fuzzy_match,
   tui::Event,
 };
 
 #[derive(Debug, Clone, Default, Eq, PartialEq)]
 pub enum MenuFocus {
+  Database,
   #[default]
   Schema,
   Tables,
 }
 
+// a row in the object tree of the selected schema
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TreeItem {
+  Pins(usize),             // (number of pinned tables)
+  Pinned(String, String),  // (schema, table)
+  Kind(ObjectKind, usize), // (kind, number of objects)
+  Object(ObjectKind, String),
+  Column(String, Column), // (table, column)
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Column {
+  pub name: String,
+  pub data_type: String,
+  pub nullable: bool,
+  pub default: String,
+  pub primary_key: bool,
+  pub foreign_key: bool,
+}
+
+impl Column {
+  // parses a row returned by `preview_column_details_query`
+  fn from_row(row: &[String]) -> Self {
+    let keys: Vec<&str> = row[4].split(',').map(str::trim).collect();
+    Column {
+      name: row[0].clone(),
+      data_type: row[1].clone(),
+      nullable: row[2].eq_ignore_ascii_case("yes"),
+      default: row[3].clone(),
+      primary_key: keys.contains(&"PK"),
+      foreign_key: keys.contains(&"FK"),
+    }
+  }
+
+  fn line(&self) -> Line<'static> {
+    let mut spans =
+      vec![Span::raw(format!("    {} ", self.name)), Span::styled(self.data_type.clone(), Style::new().dim())];
+    if self.primary_key {
+      spans.push(Span::styled(" [PK]", Style::new().yellow()));
+    }
+    if self.foreign_key {
+      spans.push(Span::styled(" [FK]", Style::new().cyan()));
+    }
+    if !self.nullable {
+      spans.push(Span::styled(" not null", Style::new().dim()));
+    }
+    if !self.default.is_empty() {
+      spans.push(Span::styled(format!(" = {}", self.default), Style::new().dim()));
+    }
+    Line::from(spans)
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct TableSize {
+  pub rows: Option<u64>,
+  pub bytes: Option<u64>,
+}
+
+impl TableSize {
+  fn label(&self) -> String {
+    let rows = self.rows.map(|rows| format!("~{} rows", format_count(rows)));
+    let bytes = self.bytes.map(format_bytes);
+    [rows, bytes].into_iter().flatten().collect::<Vec<_>>().join(" ")
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum TableSort {
+  #[default]
+  Name,
+  Size,
+}
+
+// 1234 -> 1.2k
+fn format_count(count: u64) -> String {
+  let units = [(1_000_000_000, "B"), (1_000_000, "M"), (1_000, "k")];
+  match units.iter().find(|(size, _)| count >= *size) {
+    Some((size, unit)) if count < size * 10 => format!("{:.1}{}", count as f64 / *size as f64, unit),
+    Some((size, unit)) => format!("{}{}", count / size, unit),
+    None => count.to_string(),
+  }
+}
+
+// 49152 -> 48 kB
+fn format_bytes(bytes: u64) -> String {
+  let mut size = bytes as f64;
+  for unit in ["B", "kB", "MB", "GB"] {
+    if size < 1024.0 {
+      return format!("{:.0} {}", size, unit);
+    }
+    size /= 1024.0;
+  }
+  format!("{:.0} TB", size)
+}
+
+// what was selected before the menu was reloaded
+#[derive(Debug, Clone)]
+struct Selection {
+  schema: String,
+  focus: MenuFocus,
+  item: Option<TreeItem>,
+  index: Option<usize>,
+}
+
+// what the finder can jump to
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum FinderTarget {
+  Schema(String),
+  Object(String, ObjectKind, String), // (schema, kind, name)
+  Column(String, String, String),     // (schema, table, column)
+}
+
+impl FinderTarget {
+  // the text that gets matched and shown, like `schema.table.column`
+  fn text(&self) -> String {
+    let qualified = |schema: &str, name: &str| {
+      match schema {
+        "" => name.to_owned(),
+        schema => format!("{}.{}", schema, name),
+      }
+    };
+    match self {
+      FinderTarget::Schema(schema) => schema.clone(),
+      FinderTarget::Object(schema, _, name) => qualified(schema, name),
+      FinderTarget::Column(schema, table, column) => format!("{}.{}", qualified(schema, table), column),
+    }
+  }
+
+  fn label(&self) -> &'static str {
+    match self {
+      FinderTarget::Schema(_) => "schema",
+      FinderTarget::Object(_, kind, _) => kind.label().trim_end_matches('s'),
+      FinderTarget::Column(..) => "column",
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct FinderMatch {
+  target: FinderTarget,
+  text: String,
+  positions: Vec<usize>, // of the matched characters in `text`
+}
+
+// fuzzy search across every schema, and optionally the columns of every table
+#[derive(Debug, Clone, Default)]
+struct Finder {
+  query: String,
+  columns: bool,
+  matches: Vec<FinderMatch>,
+  list_state: ListState,
+}
+
+// the actions list opened on a table
+#[derive(Debug, Clone, Default)]
+struct TableActions {
+  schema: String,
+  table: String,
+  list_state: ListState,
+  renaming: Option<(String, bool)>, // (new name, run), while the new name is typed
+}
+
+// the pinned tables of every connection, by connection name and then database, saved in the data dir
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PinnedTables(BTreeMap<String, BTreeMap<String, Vec<(String, String)>>>);
+
+impl PinnedTables {
+  fn path(data_dir: &Path) -> PathBuf {
+    data_dir.join("pinned_tables.json")
+  }
+
+  pub fn load(data_dir: &Path) -> Result<Self> {
+    match std::fs::read_to_string(Self::path(data_dir)) {
+      Ok(json) => Ok(serde_json::from_str(&json)?),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(e) => Err(e.into()),
+    }
+  }
+
+  pub fn save(&self, data_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(data_dir)?;
+    std::fs::write(Self::path(data_dir), serde_json::to_string_pretty(self)?)?;
+    Ok(())
+  }
+
+  // sqlite connections have no database name, so their pins are kept under an empty one
+  pub fn get(&self, connection: &str, database: Option<&str>) -> Vec<(String, String)> {
+    self
+      .0
+      .get(connection)
+      .and_then(|databases| databases.get(database.unwrap_or_default()))
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  pub fn set(&mut self, connection: &str, database: Option<&str>, pinned: Vec<(String, String)>) {
+    let databases = self.0.entry(connection.to_owned()).or_default();
+    if pinned.is_empty() {
+      databases.remove(database.unwrap_or_default());
+    } else {
+      databases.insert(database.unwrap_or_default().to_owned(), pinned);
+    }
+    if databases.is_empty() {
+      self.0.remove(connection);
+    }
+  }
+
+  pub fn rename(&mut self, from: &str, to: &str) {
+    if let Some(databases) = self.0.remove(from) {
+      self.0.insert(to.to_owned(), databases);
+    }
+  }
+}
+
 pub trait SettableTableList<'a> {
   fn set_table_list(&mut self, data: Option<Result<Rows, DbError>>);
+  fn set_schema_objects(&mut self, data: Option<Result<Rows, DbError>>);
+  fn set_columns(&mut self, schema: &str, table: &str, data: Option<Result<Rows, DbError>>);
+  fn set_all_columns(&mut self, data: Option<Result<Rows, DbError>>);
+  fn set_table_sizes(&mut self, data: Option<Result<Rows, DbError>>);
+  fn set_databases(&mut self, data: Option<Result<Rows, DbError>>);
+  fn set_pinned_tables(&mut self, pinned: Vec<(String, String)>);
+  fn set_table_actions(&mut self, actions: Vec<TableAction>);
+  // whether keys are going into the search or the finder
+  fn is_typing(&self) -> bool;
 }
 
 pub trait MenuComponent<'a, DB: Database>: Component<DB> + SettableTableList<'a> {}
@@ -45,12 +272,85 @@ impl<'a, T, DB: Database> MenuComponent<'a, DB> for T where T: Component<DB> + S
 pub struct Menu {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
-  table_map: IndexMap<String, Vec<String>>,
+  object_map: IndexMap<String, BTreeMap<ObjectKind, Vec<String>>>,
+  expanded: HashSet<ObjectKind>,
+  columns: HashMap<(String, String), Vec<Column>>, // keyed by (schema, table)
+  expanded_tables: HashSet<(String, String)>,
+  sizes: HashMap<(String, String), TableSize>, // keyed by (schema, table)
+  // (trigger, table) keyed by (schema, listed name), for triggers whose names are only unique per table
+  triggers: HashMap<(String, String), (String, String)>,
+  table_sort: TableSort,
+  reloading: Option<Selection>,
   schema_index: usize,
   list_state: ListState,
   menu_focus: MenuFocus,
   search: Option<String>,
   search_focused: bool,
+  all_columns: Option<Vec<(String, String, String)>>, // (schema, table, column), loaded for the finder
+  finder: Option<Finder>,
+  jumping_to_column: Option<(String, String, String)>, // (schema, table, column), selected once loaded
+  pinned: Vec<(String, String)>,                       // (schema, table), shown above the schema's objects
+  databases: Vec<String>,
+  database: Option<String>, // the one connected to
+  database_index: usize,
+  table_actions: Vec<TableAction>, // the ones the database supports
+  actions: Option<TableActions>,
+  previewing_rows: Option<(String, String, RowsPreview)>, // (schema, table, preview), sent once the columns load
+}
+
+// the previews available for each kind of object, as (key, label, preview). the first one is
+// also bound to enter
+fn previews(kind: ObjectKind) -> Vec<(&'static str, &'static str, MenuPreview)> {
+  match kind {
+    ObjectKind::Table => {
+      vec![
+        ("<enter>", "rows", MenuPreview::Rows(RowsPreview::default())),
+        ("L", "latest rows", MenuPreview::Rows(RowsPreview { descending: true, ..RowsPreview::default() })),
+        ("1", "columns", MenuPreview::Columns),
+        ("2", "constraints", MenuPreview::Constraints),
+        ("3", "indexes", MenuPreview::Indexes),
+        ("4", "rls policies", MenuPreview::Policies),
+        ("5", "dependencies", MenuPreview::Dependencies(kind, None)),
+        ("6", "definition", MenuPreview::Definition(kind, None)),
+        ("7", "triggers", MenuPreview::Triggers),
+        ("8", "grants", MenuPreview::Grants),
+        ("9", "statistics", MenuPreview::Statistics),
+        ("0", "partitions", MenuPreview::Partitions),
+        ("f", "referencing tables", MenuPreview::ReferencingTables),
+      ]
+    },
+    kind if kind.has_rows() => {
+      vec![
+        ("<enter>", "rows", MenuPreview::Rows(RowsPreview::default())),
+        ("1", "definition", MenuPreview::Definition(kind, None)),
+        ("2", "dependencies", MenuPreview::Dependencies(kind, None)),
+      ]
+    },
+    kind => {
+      vec![
+        ("1", "definition", MenuPreview::Definition(kind, None)),
+        ("2", "dependencies", MenuPreview::Dependencies(kind, None)),
+      ]
+    },
+  }
+}
+
+// the lines listing the keys available for the selected item
+fn hint_lines(indent: &str, hints: Vec<(&str, &str)>, running_query: bool) -> Vec<Line<'static>> {
+  let last = hints.len().saturating_sub(1);
+  hints
+    .into_iter()
+    .enumerate()
+    .map(|(i, (key, label))| {
+      Line::from(format!(
+        "{}{}[{}] {}",
+        indent,
+        if i == last { "└" } else { "├" },
+        if running_query { "..." } else { key },
+        label
+      ))
+    })
+    .collect()
 }
 
 impl Menu {
@@ -58,17 +358,339 @@ impl Menu {
     Menu {
       command_tx: None,
       config: Config::default(),
-      table_map: IndexMap::new(),
+      object_map: IndexMap::new(),
+      expanded: HashSet::from([ObjectKind::Table]),
+      columns: HashMap::new(),
+      expanded_tables: HashSet::new(),
+      sizes: HashMap::new(),
+      triggers: HashMap::new(),
+      table_sort: TableSort::default(),
+      reloading: None,
       schema_index: 0,
       list_state: ListState::default(),
       menu_focus: MenuFocus::default(),
       search: None,
       search_focused: false,
+      all_columns: None,
+      finder: None,
+      jumping_to_column: None,
+      pinned: vec![],
+      databases: vec![],
+      database: None,
+      database_index: 0,
+      table_actions: vec![],
+      actions: None,
+      previewing_rows: None,
+    }
+  }
+
+  fn matches_search(&self, name: &str) -> bool {
+    match self.search.as_ref() {
+      Some(search) => name.to_lowercase().contains(search.to_lowercase().trim()),
+      None => true,
     }
   }
 
+  // the rows shown for the selected schema. kinds without any matches are hidden while
+  // searching, and every kind is expanded so the matches are visible
+  pub fn visible_items(&self) -> Vec<TreeItem> {
+    let searching = self.search.as_ref().is_some_and(|s| !s.trim().is_empty());
+    let Some((schema, objects)) = self.object_map.get_index(self.schema_index) else {
+      return vec![];
+    };
+    let mut items = vec![];
+    let pinned: Vec<&(String, String)> = self
+      .pinned
+      .iter()
+      .filter(|(schema, table)| {
+        self
+          .object_map
+          .get(schema)
+          .and_then(|objects| objects.get(&ObjectKind::Table))
+          .is_some_and(|t| t.contains(table))
+          && self.matches_search(table)
+      })
+      .collect();
+    if !pinned.is_empty() {
+      items.push(TreeItem::Pins(pinned.len()));
+      items.extend(pinned.into_iter().map(|(schema, table)| TreeItem::Pinned(schema.clone(), table.clone())));
+    }
+    for (kind, names) in objects {
+      let mut matches: Vec<&String> = names.iter().filter(|n| self.matches_search(n)).collect();
+      if *kind == ObjectKind::Table && self.table_sort == TableSort::Size {
+        // biggest first, and tables without a size last
+        matches.sort_by_key(|name| {
+          std::cmp::Reverse(self.sizes.get(&(schema.clone(), name.to_string())).and_then(|size| size.bytes))
+        });
+      }
+      if matches.is_empty() && searching {
+        continue;
+      }
+      items.push(TreeItem::Kind(*kind, matches.len()));
+      if searching || self.expanded.contains(kind) {
+        for name in matches {
+          items.push(TreeItem::Object(*kind, name.clone()));
+          let key = (schema.clone(), name.clone());
+          if let (true, Some(columns)) = (self.expanded_tables.contains(&key), self.columns.get(&key)) {
+            items.extend(columns.iter().map(|c| TreeItem::Column(name.clone(), c.clone())));
+          }
+        }
+      }
+    }
+    items
+  }
+
+  fn selected_item(&self) -> Option<TreeItem> {
+    self.list_state.selected().and_then(|i| self.visible_items().get(i).cloned())
+  }
+
+  fn schema(&self) -> String {
+    self.object_map.get_index(self.schema_index).map(|(schema, _)| schema.clone()).unwrap_or_default()
+  }
+
+  fn select_item(&mut self, is_item: impl Fn(&TreeItem) -> bool) {
+    let index = self.visible_items().iter().position(is_item);
+    self.list_state = ListState::default().with_selected(index.or(Some(0)));
+  }
+
+  // expands or collapses a kind, keeping its header selected
+  fn set_expanded(&mut self, kind: ObjectKind, expanded: bool) {
+    if expanded {
+      self.expanded.insert(kind);
+    } else {
+      self.expanded.remove(&kind);
+    }
+    self.select_item(|item| matches!(item, TreeItem::Kind(k, _) if *k == kind));
+  }
+
+  // shows or hides the columns of a table, loading them the first time
+  fn set_table_expanded(&mut self, table: String, expanded: bool) -> Result<()> {
+    let key = (self.schema(), table.clone());
+    if expanded {
+      if !self.columns.contains_key(&key) {
+        self.command_tx.as_ref().unwrap().send(Action::LoadMenuColumns(key.0.clone(), key.1.clone()))?;
+      }
+      self.expanded_tables.insert(key);
+    } else {
+      self.expanded_tables.remove(&key);
+    }
+    self.select_item(|item| matches!(item, TreeItem::Object(ObjectKind::Table, t) if *t == table));
+    Ok(())
+  }
+
+  fn expand_selected(&mut self) -> Result<()> {
+    match self.selected_item() {
+      Some(TreeItem::Kind(kind, _)) => self.set_expanded(kind, true),
+      Some(TreeItem::Object(ObjectKind::Table, table)) => self.set_table_expanded(table, true)?,
+      Some(TreeItem::Pinned(schema, table)) => self.jump_to(FinderTarget::Object(schema, ObjectKind::Table, table))?,
+      _ => {},
+    }
+    Ok(())
+  }
+
+  // collapses the selected table or kind (or the one the selected item is in), and moves to the
+  // schemas once there's nothing left to collapse
+  fn collapse_selected(&mut self) -> Result<()> {
+    match self.selected_item() {
+      Some(TreeItem::Column(table, _)) => self.set_table_expanded(table, false)?,
+      Some(TreeItem::Object(ObjectKind::Table, table))
+        if self.expanded_tables.contains(&(self.schema(), table.clone())) =>
+      {
+        self.set_table_expanded(table, false)?
+      },
+      Some(TreeItem::Kind(kind, _)) if self.expanded.contains(&kind) => self.set_expanded(kind, false),
+      Some(TreeItem::Object(kind, _)) => self.set_expanded(kind, false),
+      _ => self.change_focus(MenuFocus::Schema),
+    }
+    Ok(())
+  }
+
+  // the selected object as (kind, schema, name), whether it's in the tree or pinned
+  fn selected_object(&self) -> Option<(ObjectKind, String, String)> {
+    match self.selected_item() {
+      Some(TreeItem::Object(kind, name)) => Some((kind, self.schema(), name)),
+      Some(TreeItem::Pinned(schema, table)) => Some((ObjectKind::Table, schema, table)),
+      _ => None,
+    }
+  }
+
+  fn is_table(&self, schema: &str, name: &str) -> bool {
+    self
+      .object_map
+      .get(schema)
+      .and_then(|objects| objects.get(&ObjectKind::Table))
+      .is_some_and(|tables| tables.iter().any(|table| table == name))
+  }
+
+  // the name an object is queried by, along with the table it's on if it's a trigger listed with it
+  fn object_name(&self, kind: ObjectKind, schema: &str, name: String) -> (String, Option<String>) {
+    match self.triggers.get(&(schema.to_owned(), name.clone())) {
+      Some((trigger, table)) if kind == ObjectKind::Trigger => (trigger.clone(), Some(table.clone())),
+      _ => (name, None),
+    }
+  }
+
+  // the rows of a table are ordered by its primary key, so its columns are loaded first
+  fn send_preview(&mut self, preview: MenuPreview, schema: String, name: String) -> Result<()> {
+    let (preview, name) = match preview {
+      MenuPreview::Definition(kind, _) => {
+        let (name, table) = self.object_name(kind, &schema, name);
+        (MenuPreview::Definition(kind, table), name)
+      },
+      MenuPreview::Dependencies(kind, _) => {
+        let (name, table) = self.object_name(kind, &schema, name);
+        (MenuPreview::Dependencies(kind, table), name)
+      },
+      preview => (preview, name),
+    };
+    let preview = match preview {
+      MenuPreview::Rows(mut rows) if self.is_table(&schema, &name) => {
+        let Some(columns) = self.columns.get(&(schema.clone(), name.clone())) else {
+          self.previewing_rows = Some((schema.clone(), name.clone(), rows));
+          self.command_tx.as_ref().unwrap().send(Action::LoadMenuColumns(schema, name))?;
+          return Ok(());
+        };
+        rows.primary_key = columns.iter().filter(|c| c.primary_key).map(|c| c.name.clone()).collect();
+        MenuPreview::Rows(rows)
+      },
+      preview => preview,
+    };
+    self.command_tx.as_ref().unwrap().send(Action::MenuPreview(preview, schema, name))?;
+    Ok(())
+  }
+
+  // pins the selected table, or unpins it, and saves the pins
+  fn toggle_pinned(&mut self) -> Result<()> {
+    let Some((ObjectKind::Table, schema, table)) = self.selected_object() else {
+      return Ok(());
+    };
+    let selected = self.selected_item();
+    let index = self.list_state.selected();
+    let pin = (schema, table);
+    match self.pinned.iter().position(|p| *p == pin) {
+      Some(i) => {
+        self.pinned.remove(i);
+      },
+      None => self.pinned.push(pin),
+    }
+    let items = self.visible_items();
+    let index =
+      selected.and_then(|s| items.iter().position(|i| *i == s)).or(index.map(|i| i.min(items.len().saturating_sub(1))));
+    self.list_state = ListState::default().with_selected(index);
+    self.command_tx.as_ref().unwrap().send(Action::SavePinnedTables(self.pinned.clone()))?;
+    Ok(())
+  }
+
+  // switches between sorting tables by name and by size, keeping the selected item selected
+  fn toggle_table_sort(&mut self) {
+    let selected = self.selected_item();
+    self.table_sort = match self.table_sort {
+      TableSort::Name => TableSort::Size,
+      TableSort::Size => TableSort::Name,
+    };
+    if let Some(selected) = selected {
+      self.select_item(|item| *item == selected);
+    }
+  }
+
+  fn request_definition(&self, target: DefinitionTarget) -> Result<()> {
+    if let Some((kind, schema, name)) = self.selected_object() {
+      let (name, table) = self.object_name(kind, &schema, name);
+      self.command_tx.as_ref().unwrap().send(Action::RequestDefinition(kind, schema, name, table, target))?;
+    }
+    Ok(())
+  }
+
+  // draws the tables of the selected schema
+  fn request_er_diagram(&self) -> Result<()> {
+    let schema = self.schema();
+    let tables = self.object_map.get(&schema).and_then(|objects| objects.get(&ObjectKind::Table)).cloned();
+    if let Some(tables) = tables {
+      self.command_tx.as_ref().unwrap().send(Action::RequestErDiagram(schema, tables))?;
+    }
+    Ok(())
+  }
+
+  // sends the preview bound to `key` for the selected object
+  fn preview_selected(&mut self, key: &str) -> Result<()> {
+    match (self.selected_object(), self.selected_item()) {
+      (Some((kind, schema, name)), _) => {
+        if let Some((_, _, preview)) = previews(kind).into_iter().find(|(k, _, _)| *k == key) {
+          self.send_preview(preview, schema, name)?;
+        }
+      },
+      (None, Some(TreeItem::Column(table, column))) if key == "1" => {
+        self.send_preview(MenuPreview::DistinctValues(column.name), self.schema(), table)?;
+      },
+      _ => {},
+    }
+    Ok(())
+  }
+
+  fn selection(&self) -> Option<Selection> {
+    self.object_map.get_index(self.schema_index).map(|(schema, _)| {
+      Selection {
+        schema: schema.clone(),
+        focus: self.menu_focus.clone(),
+        item: self.selected_item(),
+        index: self.list_state.selected(),
+      }
+    })
+  }
+
+  // selects the same schema and item as before the reload, or the closest thing to it when
+  // they're gone
+  fn restore_selection(&mut self, selection: &Selection) {
+    let Some(schema_index) = self.object_map.get_index_of(&selection.schema) else {
+      self.schema_index = 0;
+      self.reset_focus();
+      return;
+    };
+    self.schema_index = schema_index;
+    self.menu_focus = match selection.focus {
+      MenuFocus::Database => MenuFocus::Database,
+      _ if self.object_map.keys().len() == 1 => MenuFocus::Tables,
+      _ => selection.focus.clone(),
+    };
+    self.list_state = match self.menu_focus {
+      MenuFocus::Tables => {
+        let items = self.visible_items();
+        let index = selection
+          .item
+          .as_ref()
+          .and_then(|item| items.iter().position(|i| i == item))
+          .or(selection.index.map(|i| i.min(items.len().saturating_sub(1))))
+          .or(Some(0));
+        ListState::default().with_selected(index)
+      },
+      MenuFocus::Schema | MenuFocus::Database => ListState::default(),
+    };
+  }
+
+  fn reset_focus(&mut self) {
+    if self.object_map.keys().len() == 1 {
+      self.menu_focus = MenuFocus::Tables;
+      self.list_state = ListState::default().with_selected(Some(0));
+    } else {
+      self.menu_focus = MenuFocus::Schema;
+      self.list_state = ListState::default();
+    }
+  }
+
+  // the databases are above the schemas, or above the tables when there's only one schema
   pub fn change_focus(&mut self, new_focus: MenuFocus) {
-    if self.menu_focus != new_focus && self.table_map.keys().len() > 1 {
+    let single_schema = self.object_map.keys().len() == 1;
+    if self.menu_focus == MenuFocus::Database {
+      if new_focus != MenuFocus::Database {
+        self.reset_focus();
+      }
+    } else if new_focus == MenuFocus::Database || (new_focus == MenuFocus::Schema && single_schema) {
+      if !self.databases.is_empty() {
+        self.database_index = self.databases.iter().position(|d| Some(d) == self.database.as_ref()).unwrap_or(0);
+        self.menu_focus = MenuFocus::Database;
+        self.list_state = ListState::default();
+      }
+    } else if self.menu_focus != new_focus && !single_schema {
       match new_focus {
         MenuFocus::Schema => {
           self.list_state = ListState::default();
@@ -76,6 +698,7 @@ impl Menu {
         MenuFocus::Tables => {
           self.list_state = ListState::default().with_selected(Some(0));
         },
+        MenuFocus::Database => {},
       }
       self.menu_focus = new_focus;
     }
@@ -85,23 +708,16 @@ impl Menu {
     match self.menu_focus {
       MenuFocus::Tables => {
         if let Some(i) = self.list_state.selected() {
-          let tables = self.table_map.get_index(self.schema_index).unwrap().1.to_owned();
-          let filtered_tables: Vec<String> = tables
-            .into_iter()
-            .filter(|t| {
-              if let Some(search) = self.search.as_ref() {
-                t.to_lowercase().contains(search.to_lowercase().trim())
-              } else {
-                true
-              }
-            })
-            .collect();
-          self.list_state = ListState::default()
-            .with_selected(Some(i.saturating_add(1).clamp(0, filtered_tables.len().saturating_sub(1))));
+          let item_count = self.visible_items().len();
+          self.list_state =
+            ListState::default().with_selected(Some(i.saturating_add(1).clamp(0, item_count.saturating_sub(1))));
         }
       },
       MenuFocus::Schema => {
-        self.schema_index = self.schema_index.saturating_add(1).clamp(0, self.table_map.keys().len().saturating_sub(1))
+        self.schema_index = self.schema_index.saturating_add(1).clamp(0, self.object_map.keys().len().saturating_sub(1))
+      },
+      MenuFocus::Database => {
+        self.database_index = self.database_index.saturating_add(1).min(self.databases.len().saturating_sub(1))
       },
     }
   }
@@ -114,6 +730,7 @@ impl Menu {
         }
       },
       MenuFocus::Schema => self.schema_index = self.schema_index.saturating_sub(1),
+      MenuFocus::Database => self.database_index = self.database_index.saturating_sub(1),
     }
   }
 
@@ -121,23 +738,13 @@ impl Menu {
     match self.menu_focus {
       MenuFocus::Tables => {
         if let Some(i) = self.list_state.selected() {
-          let tables = self.table_map.get_index(self.schema_index).unwrap().1.to_owned();
-          let filtered_tables: Vec<String> = tables
-            .into_iter()
-            .filter(|t| {
-              if let Some(search) = self.search.as_ref() {
-                t.to_lowercase().contains(search.to_lowercase().trim())
-              } else {
-                true
-              }
-            })
-            .collect();
-          self.list_state = ListState::default().with_selected(Some(filtered_tables.len().saturating_sub(1)));
+          self.list_state = ListState::default().with_selected(Some(self.visible_items().len().saturating_sub(1)));
         }
       },
       MenuFocus::Schema => {
-        self.schema_index = self.table_map.keys().len().saturating_sub(1);
+        self.schema_index = self.object_map.keys().len().saturating_sub(1);
       },
+      MenuFocus::Database => self.database_index = self.databases.len().saturating_sub(1),
     }
   }
 
@@ -149,6 +756,7 @@ impl Menu {
         }
       },
       MenuFocus::Schema => self.schema_index = 0,
+      MenuFocus::Database => self.database_index = 0,
     }
   }
 
@@ -157,28 +765,298 @@ impl Menu {
     self.search_focused = false;
     self.list_state = ListState::default().with_selected(Some(0));
   }
+
+  fn switch_to_selected_database(&mut self) -> Result<()> {
+    match self.databases.get(self.database_index) {
+      Some(database) if Some(database) != self.database.as_ref() => {
+        self.command_tx.as_ref().unwrap().send(Action::SwitchDatabase(database.clone()))?;
+      },
+      _ => self.change_focus(MenuFocus::Schema),
+    }
+    Ok(())
+  }
+
+  fn open_finder(&mut self) {
+    self.finder = Some(Finder::default());
+    self.update_finder();
+  }
+
+  fn finder_targets(&self, columns: bool) -> Vec<FinderTarget> {
+    let mut targets = vec![];
+    for (schema, objects) in self.object_map.iter() {
+      if !schema.is_empty() {
+        targets.push(FinderTarget::Schema(schema.clone()));
+      }
+      for (kind, names) in objects {
+        targets.extend(names.iter().map(|name| FinderTarget::Object(schema.clone(), *kind, name.clone())));
+      }
+    }
+    if let (true, Some(all_columns)) = (columns, &self.all_columns) {
+      targets.extend(
+        all_columns
+          .iter()
+          .map(|(schema, table, column)| FinderTarget::Column(schema.clone(), table.clone(), column.clone())),
+      );
+    }
+    targets
+  }
+
+  // matches the finder's query again, best matches first
+  fn update_finder(&mut self) {
+    let Some(finder) = self.finder.as_ref() else {
+      return;
+    };
+    let query = finder.query.clone();
+    let mut matches: Vec<(i32, FinderMatch)> = self
+      .finder_targets(finder.columns)
+      .into_iter()
+      .filter_map(|target| {
+        let text = target.text();
+        fuzzy_match(&query, &text).map(|(score, positions)| (score, FinderMatch { target, text, positions }))
+      })
+      .collect();
+    if !query.trim().is_empty() {
+      matches.sort_by_key(|(score, m)| (std::cmp::Reverse(*score), m.text.chars().count()));
+    }
+    if let Some(finder) = self.finder.as_mut() {
+      finder.matches = matches.into_iter().map(|(_, m)| m).collect();
+      finder.list_state = ListState::default().with_selected(Some(0));
+    }
+  }
+
+  // closes the finder and selects what it found in the tree
+  fn jump_to(&mut self, target: FinderTarget) -> Result<()> {
+    self.finder = None;
+    self.search = None;
+    self.search_focused = false;
+    let schema = match &target {
+      FinderTarget::Schema(schema) | FinderTarget::Object(schema, ..) | FinderTarget::Column(schema, ..) => schema,
+    };
+    let Some(schema_index) = self.object_map.get_index_of(schema) else {
+      return Ok(());
+    };
+    self.schema_index = schema_index;
+    self.menu_focus = MenuFocus::Tables;
+    match target {
+      FinderTarget::Schema(_) => self.list_state = ListState::default().with_selected(Some(0)),
+      FinderTarget::Object(_, kind, name) => {
+        self.expanded.insert(kind);
+        self.select_item(|item| *item == TreeItem::Object(kind, name.clone()));
+      },
+      FinderTarget::Column(_, table, column) => {
+        self.expanded.insert(ObjectKind::Table);
+        self.set_table_expanded(table.clone(), true)?;
+        let is_column = |item: &TreeItem| matches!(item, TreeItem::Column(t, c) if *t == table && c.name == column);
+        // until the columns are loaded, the table stays selected
+        if self.visible_items().iter().any(is_column) {
+          self.select_item(is_column);
+        } else {
+          self.jumping_to_column = Some((self.schema(), table, column));
+        }
+      },
+    }
+    Ok(())
+  }
+
+  fn open_table_actions(&mut self) {
+    if let (Some((ObjectKind::Table, schema, table)), false) = (self.selected_object(), self.table_actions.is_empty()) {
+      self.actions =
+        Some(TableActions { schema, table, list_state: ListState::default().with_selected(Some(0)), renaming: None });
+    }
+  }
+
+  // closes the actions list, and runs the action or sends it to the editor
+  fn send_table_action(&mut self, action: TableAction, run: bool) -> Result<()> {
+    if let Some(actions) = self.actions.take() {
+      self.command_tx.as_ref().unwrap().send(Action::TableAction(action, actions.schema, actions.table, run))?;
+    }
+    Ok(())
+  }
+
+  fn handle_table_actions_key(&mut self, key: KeyEvent) -> Result<()> {
+    let Some(actions) = self.actions.as_mut() else {
+      return Ok(());
+    };
+    if let Some((new_name, run)) = actions.renaming.as_mut() {
+      match key.code {
+        KeyCode::Esc => actions.renaming = None,
+        KeyCode::Enter if !new_name.trim().is_empty() => {
+          let (action, run) = (TableAction::Rename(new_name.trim().to_owned()), *run);
+          self.send_table_action(action, run)?;
+        },
+        KeyCode::Backspace => {
+          new_name.pop();
+        },
+        KeyCode::Char(c) if (key.modifiers - KeyModifiers::SHIFT).is_empty() => new_name.push(c),
+        _ => {},
+      }
+      return Ok(());
+    }
+    let selected = actions.list_state.selected().unwrap_or(0);
+    match key.code {
+      KeyCode::Char('j') | KeyCode::Down => {
+        actions.list_state.select(Some(selected.saturating_add(1).min(self.table_actions.len().saturating_sub(1))));
+      },
+      KeyCode::Char('k') | KeyCode::Up => actions.list_state.select(Some(selected.saturating_sub(1))),
+      KeyCode::Char('h') | KeyCode::Left | KeyCode::Esc => self.actions = None,
+      KeyCode::Enter | KeyCode::Char('e') => {
+        let run = key.code == KeyCode::Enter;
+        match self.table_actions.get(selected).cloned() {
+          // the new name is asked for first, starting from the current one
+          Some(TableAction::Rename(_)) => actions.renaming = Some((actions.table.clone(), run)),
+          Some(action) => self.send_table_action(action, run)?,
+          None => {},
+        }
+      },
+      _ => {},
+    }
+    Ok(())
+  }
+
+  fn handle_finder_key(&mut self, key: KeyEvent) -> Result<()> {
+    let Some(finder) = self.finder.as_mut() else {
+      return Ok(());
+    };
+    let selected = finder.list_state.selected().unwrap_or(0);
+    match (key.code, key.modifiers) {
+      (KeyCode::Esc, _) => self.finder = None,
+      (KeyCode::Enter, _) => {
+        if let Some(found) = finder.matches.get(selected) {
+          let target = found.target.clone();
+          self.jump_to(target)?;
+        }
+      },
+      (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+        finder.list_state.select(Some(selected.saturating_add(1).min(finder.matches.len().saturating_sub(1))));
+      },
+      (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+        finder.list_state.select(Some(selected.saturating_sub(1)));
+      },
+      (KeyCode::Char('t'), KeyModifiers::CONTROL) => {
+        finder.columns = !finder.columns;
+        if finder.columns && self.all_columns.is_none() {
+          self.command_tx.as_ref().unwrap().send(Action::LoadAllColumns)?;
+        }
+        self.update_finder();
+      },
+      (KeyCode::Backspace, _) => {
+        finder.query.pop();
+        self.update_finder();
+      },
+      (KeyCode::Char(c), modifiers) if (modifiers - KeyModifiers::SHIFT).is_empty() => {
+        finder.query.push(c);
+        self.update_finder();
+      },
+      _ => {},
+    }
+    Ok(())
+  }
 }
 
 impl SettableTableList<'_> for Menu {
   fn set_table_list(&mut self, data: Option<Result<Rows, DbError>>) {
     log::info!("setting menu table list");
-    self.table_map = IndexMap::new();
+    self.reloading = self.selection();
+    self.object_map = IndexMap::new();
+    self.columns = HashMap::new();
+    self.sizes = HashMap::new();
+    self.triggers = HashMap::new();
+    self.all_columns = None;
+    if let (Some(command_tx), Some(Finder { columns: true, .. })) = (&self.command_tx, &self.finder) {
+      command_tx.send(Action::LoadAllColumns).ok();
+    }
     match data {
       Some(Ok(rows)) => {
         rows.rows.iter().for_each(|row| {
           let schema = row[0].clone();
           let table = row[1].clone();
-          if !self.table_map.contains_key(&schema) {
-            self.table_map.insert(schema.clone(), vec![]);
+          self.object_map.entry(schema).or_default().entry(ObjectKind::Table).or_default().push(table);
+        });
+        // tables that are still there stay expanded, with their columns loaded again
+        self.expanded_tables.retain(|(schema, table)| {
+          self
+            .object_map
+            .get(schema)
+            .and_then(|objects| objects.get(&ObjectKind::Table))
+            .is_some_and(|t| t.contains(table))
+        });
+        if let Some(command_tx) = &self.command_tx {
+          for (schema, table) in self.expanded_tables.iter() {
+            command_tx.send(Action::LoadMenuColumns(schema.clone(), table.clone())).ok();
           }
-          self.table_map.get_mut(&schema).unwrap().push(table.clone());
+        }
+        match self.reloading.clone() {
+          Some(selection) => self.restore_selection(&selection),
+          None => self.reset_focus(),
+        }
+      },
+      Some(Err(e)) => {
+        log::error!("{}", e);
+      },
+      None => {},
+    }
+  }
+
+  // adds the rest of the objects to the schemas listed by `set_table_list`
+  fn set_schema_objects(&mut self, data: Option<Result<Rows, DbError>>) {
+    log::info!("setting menu schema objects");
+    match data {
+      Some(Ok(rows)) => {
+        let schema_count = self.object_map.keys().len();
+        rows.rows.iter().for_each(|row| {
+          let Some(kind) = ObjectKind::from_query(&row[1]) else {
+            log::warn!("unknown object kind {}", row[1]);
+            return;
+          };
+          let name = match row.get(3) {
+            Some(table) if kind == ObjectKind::Trigger => {
+              let name = format!("{} on {}", row[2], table);
+              self.triggers.insert((row[0].clone(), name.clone()), (row[2].clone(), table.clone()));
+              name
+            },
+            _ => row[2].clone(),
+          };
+          self.object_map.entry(row[0].clone()).or_default().entry(kind).or_default().push(name);
         });
-        if self.table_map.keys().len() == 1 {
-          self.menu_focus = MenuFocus::Tables;
-          self.list_state = ListState::default().with_selected(Some(0));
-        } else {
-          self.menu_focus = MenuFocus::Schema;
-          self.list_state = ListState::default();
+        match self.reloading.take() {
+          Some(selection) => self.restore_selection(&selection),
+          None if self.object_map.keys().len() != schema_count => self.reset_focus(),
+          None => {},
+        }
+        self.update_finder();
+      },
+      Some(Err(e)) => {
+        log::error!("{}", e);
+      },
+      None => {},
+    }
+  }
+
+  fn is_typing(&self) -> bool {
+    self.finder.is_some()
+      || (self.search.is_some() && self.search_focused)
+      || self.actions.as_ref().is_some_and(|actions| actions.renaming.is_some())
+  }
+
+  fn set_pinned_tables(&mut self, pinned: Vec<(String, String)>) {
+    self.pinned = pinned;
+  }
+
+  fn set_table_actions(&mut self, actions: Vec<TableAction>) {
+    self.table_actions = actions;
+  }
+
+  fn set_databases(&mut self, data: Option<Result<Rows, DbError>>) {
+    match data {
+      Some(Ok(rows)) => {
+        let previous = self.database.take();
+        self.databases = rows.rows.iter().map(|row| row[0].clone()).collect();
+        self.database =
+          rows.rows.iter().find(|row| matches!(row[1].as_str(), "t" | "true" | "1")).map(|row| row[0].clone());
+        self.database_index = self.database_index.min(self.databases.len().saturating_sub(1));
+        // leave the databases once the switch to another one went through
+        if previous.is_some() && previous != self.database && self.menu_focus == MenuFocus::Database {
+          self.reset_focus();
         }
       },
       Some(Err(e)) => {
@@ -187,6 +1065,85 @@ impl SettableTableList<'_> for Menu {
       None => {},
     }
   }
+
+  // only the columns of tables in the menu are kept, since the finder jumps to their table
+  fn set_all_columns(&mut self, data: Option<Result<Rows, DbError>>) {
+    match data {
+      Some(Ok(rows)) => {
+        let is_table = |schema: &String, table: &String| {
+          self
+            .object_map
+            .get(schema)
+            .and_then(|objects| objects.get(&ObjectKind::Table))
+            .is_some_and(|tables| tables.contains(table))
+        };
+        self.all_columns = Some(
+          rows
+            .rows
+            .iter()
+            .filter(|row| is_table(&row[0], &row[1]))
+            .map(|row| (row[0].clone(), row[1].clone(), row[2].clone()))
+            .collect(),
+        );
+        self.update_finder();
+      },
+      Some(Err(e)) => {
+        log::error!("{}", e);
+      },
+      None => {},
+    }
+  }
+
+  fn set_table_sizes(&mut self, data: Option<Result<Rows, DbError>>) {
+    match data {
+      Some(Ok(rows)) => {
+        self.sizes = rows
+          .rows
+          .iter()
+          .map(|row| {
+            ((row[0].clone(), row[1].clone()), TableSize { rows: row[2].parse().ok(), bytes: row[3].parse().ok() })
+          })
+          .collect();
+      },
+      Some(Err(e)) => {
+        log::error!("{}", e);
+      },
+      None => {},
+    }
+  }
+
+  fn set_columns(&mut self, schema: &str, table: &str, data: Option<Result<Rows, DbError>>) {
+    let key = (schema.to_owned(), table.to_owned());
+    match data {
+      Some(Ok(rows)) => {
+        // columns showing up shouldn't move the selection
+        let selected = self.selected_item();
+        self.columns.insert(key.clone(), rows.rows.iter().map(|row| Column::from_row(row)).collect());
+        let jumping = self.jumping_to_column.take_if(|(s, t, _)| *s == key.0 && *t == key.1);
+        if let Some((schema, table, preview)) = self.previewing_rows.take_if(|(s, t, _)| *s == key.0 && *t == key.1) {
+          self.send_preview(MenuPreview::Rows(preview), schema, table).ok();
+        }
+        match (jumping, selected) {
+          (Some((_, table, column)), _) => {
+            self.select_item(|item| matches!(item, TreeItem::Column(t, c) if *t == table && c.name == column));
+          },
+          (None, Some(selected)) if self.menu_focus == MenuFocus::Tables => self.select_item(|item| *item == selected),
+          _ => {},
+        }
+      },
+      Some(Err(e)) => {
+        log::error!("{}", e);
+        self.expanded_tables.remove(&key);
+        // without the primary key, the rows aren't ordered
+        if let Some((schema, table, preview)) = self.previewing_rows.take_if(|(s, t, _)| *s == key.0 && *t == key.1) {
+          if let Some(command_tx) = &self.command_tx {
+            command_tx.send(Action::MenuPreview(MenuPreview::Rows(preview), schema, table)).ok();
+          }
+        }
+      },
+      None => {},
+    }
+  }
 }
 
 impl<DB: Database> Component<DB> for Menu {
@@ -220,9 +1177,33 @@ impl<DB: Database> Component<DB> for Menu {
     if app_state.focus != Focus::Menu {
       return Ok(None);
     }
+    if self.finder.is_some() {
+      self.handle_finder_key(key)?;
+      return Ok(None);
+    }
+    if self.actions.is_some() {
+      self.handle_table_actions_key(key)?;
+      return Ok(None);
+    }
+    if self.menu_focus == MenuFocus::Database {
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.scroll_down(),
+        KeyCode::Char('k') | KeyCode::Up => self.scroll_up(),
+        KeyCode::Char('g') => self.scroll_top(),
+        KeyCode::Char('G') => self.scroll_bottom(),
+        KeyCode::Char('l') | KeyCode::Right | KeyCode::Esc => self.change_focus(MenuFocus::Schema),
+        KeyCode::Enter => self.switch_to_selected_database()?,
+        KeyCode::Char('R') => self.command_tx.as_ref().unwrap().send(Action::LoadMenu)?,
+        KeyCode::Char('F') => self.open_finder(),
+        _ => {},
+      }
+      return Ok(None);
+    }
     match key.code {
+      KeyCode::Right if self.menu_focus == MenuFocus::Tables => self.expand_selected()?,
       KeyCode::Right => self.change_focus(MenuFocus::Tables),
-      KeyCode::Left => self.change_focus(MenuFocus::Schema),
+      KeyCode::Left if self.menu_focus == MenuFocus::Tables => self.collapse_selected()?,
+      KeyCode::Left => self.change_focus(MenuFocus::Database),
       KeyCode::Down => self.scroll_down(),
       KeyCode::Up => self.scroll_up(),
       KeyCode::Char(c) => {
@@ -239,40 +1220,23 @@ impl<DB: Database> Component<DB> for Menu {
                 self.search = Some("".to_owned())
               }
             },
+            KeyCode::Char('l') if self.menu_focus == MenuFocus::Tables => self.expand_selected()?,
             KeyCode::Char('l') => self.change_focus(MenuFocus::Tables),
-            KeyCode::Char('h') => self.change_focus(MenuFocus::Schema),
+            KeyCode::Char('h') if self.menu_focus == MenuFocus::Tables => self.collapse_selected()?,
+            KeyCode::Char('h') => self.change_focus(MenuFocus::Database),
             KeyCode::Char('j') => self.scroll_down(),
             KeyCode::Char('k') => self.scroll_up(),
             KeyCode::Char('g') => self.scroll_top(),
             KeyCode::Char('G') => self.scroll_bottom(),
             KeyCode::Char('R') => self.command_tx.as_ref().unwrap().send(Action::LoadMenu)?,
-            KeyCode::Char('1') | KeyCode::Char('2') | KeyCode::Char('3') | KeyCode::Char('4') => {
-              if let Some(selected) = self.list_state.selected() {
-                let (schema, tables) = self.table_map.get_index(self.schema_index).unwrap();
-                let filtered_tables: Vec<String> = tables
-                  .iter()
-                  .filter(|t| {
-                    if let Some(search) = self.search.as_ref() {
-                      t.to_lowercase().contains(search.to_lowercase().trim())
-                    } else {
-                      true
-                    }
-                  })
-                  .cloned()
-                  .collect();
-                self.command_tx.as_ref().unwrap().send(Action::MenuPreview(
-                  match key.code {
-                    KeyCode::Char('1') => MenuPreview::Columns,
-                    KeyCode::Char('2') => MenuPreview::Constraints,
-                    KeyCode::Char('3') => MenuPreview::Indexes,
-                    KeyCode::Char('4') => MenuPreview::Policies,
-                    _ => MenuPreview::Rows,
-                  },
-                  schema.clone(),
-                  filtered_tables[selected].clone(),
-                ))?;
-              }
-            },
+            KeyCode::Char('F') => self.open_finder(),
+            KeyCode::Char('p') => self.toggle_pinned()?,
+            KeyCode::Char('a') => self.open_table_actions(),
+            KeyCode::Char('s') => self.toggle_table_sort(),
+            KeyCode::Char('e') => self.request_definition(DefinitionTarget::Editor)?,
+            KeyCode::Char('y') => self.request_definition(DefinitionTarget::Clipboard)?,
+            KeyCode::Char('E') => self.request_er_diagram()?,
+            KeyCode::Char(c) => self.preview_selected(&c.to_string())?,
             _ => {},
           }
         }
@@ -282,24 +1246,21 @@ impl<DB: Database> Component<DB> for Menu {
           self.search_focused = false;
         } else if self.menu_focus == MenuFocus::Schema {
           self.change_focus(MenuFocus::Tables);
-        } else if let Some(selected) = self.list_state.selected() {
-          let (schema, tables) = self.table_map.get_index(self.schema_index).unwrap();
-          let filtered_tables: Vec<String> = tables
-            .iter()
-            .filter(|t| {
-              if let Some(search) = self.search.as_ref() {
-                t.to_lowercase().contains(search.to_lowercase().trim())
-              } else {
-                true
+        } else {
+          match self.selected_item() {
+            Some(TreeItem::Kind(kind, _)) => self.set_expanded(kind, !self.expanded.contains(&kind)),
+            Some(TreeItem::Object(..)) | Some(TreeItem::Pinned(..)) => {
+              if let Some((kind, schema, name)) = self.selected_object() {
+                let (_, _, preview) = previews(kind).remove(0);
+                self.send_preview(preview, schema, name)?;
               }
-            })
-            .cloned()
-            .collect();
-          self.command_tx.as_ref().unwrap().send(Action::MenuPreview(
-            MenuPreview::Rows,
-            schema.clone(),
-            filtered_tables[selected].clone(),
-          ))?;
+            },
+            Some(TreeItem::Column(_, column)) => {
+              self.command_tx.as_ref().unwrap().send(Action::InsertIntoEditor(column.name))?;
+              self.command_tx.as_ref().unwrap().send(Action::FocusEditor)?;
+            },
+            Some(TreeItem::Pins(_)) | None => {},
+          }
         }
       },
       KeyCode::Esc => self.reset_search(),
@@ -315,6 +1276,8 @@ impl<DB: Database> Component<DB> for Menu {
           }
         } else if self.menu_focus == MenuFocus::Tables {
           self.change_focus(MenuFocus::Schema);
+        } else {
+          self.change_focus(MenuFocus::Database);
         }
       },
       _ => {},
@@ -325,7 +1288,151 @@ impl<DB: Database> Component<DB> for Menu {
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, app_state: &AppState<'_, DB>) -> Result<()> {
     let focused = app_state.focus == Focus::Menu;
     let parent_block = Block::default();
-    let stable_keys = self.table_map.keys().enumerate();
+    let visible_items = self.visible_items();
+    let loading_menu = app_state.metadata_tasks.iter().any(|task| matches!(task, MetadataTask::Menu(_)));
+    // the database connected to stays on top of the menu
+    let area = match &self.database {
+      Some(database) => {
+        let [header, area] = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        f.render_widget(
+          Line::from(format!("󰆼 {}", database)).style(if !focused {
+            Style::new().dim()
+          } else if self.menu_focus == MenuFocus::Database {
+            Style::default().fg(Color::Green)
+          } else {
+            Style::default()
+          }),
+          header,
+        );
+        area
+      },
+      None => area,
+    };
+    if self.menu_focus == MenuFocus::Database {
+      let block = Block::default()
+        .title(" 󰆼  databases <alt+1> ")
+        .title_bottom(Line::from(" [enter] switch [l] schemas ").dim().right_aligned())
+        .borders(Borders::ALL)
+        .border_style(if focused { Style::default().fg(Color::Green) } else { Style::new().dim() });
+      let list_items: Vec<ListItem> = self
+        .databases
+        .iter()
+        .map(|database| {
+          let marker = if Some(database) == self.database.as_ref() { "●" } else { " " };
+          ListItem::new(format!("{} {}", marker, database))
+        })
+        .collect();
+      let list = List::default().items(list_items).block(block).highlight_style(
+        Style::default().fg(if focused { Color::Green } else { Color::Gray }).add_modifier(if focused {
+          Modifier::BOLD
+        } else {
+          Modifier::REVERSED
+        }),
+      );
+      f.render_stateful_widget(list, area, &mut ListState::default().with_selected(Some(self.database_index)));
+      return Ok(());
+    }
+    if self.object_map.is_empty() {
+      let block = Block::default().title(" 󰦄  <alt+1> (schema) ").borders(Borders::ALL).border_style(if focused {
+        Style::default()
+      } else {
+        Style::new().dim()
+      });
+      let message = if loading_menu { "loading… [q] cancel" } else { "" };
+      f.render_widget(Paragraph::new(message).dim().block(block), area);
+      return Ok(());
+    }
+    if let Some(finder) = self.finder.as_mut() {
+      let block = Block::default()
+        .title(format!(" 󰍉  find <alt+1> ({}) ", finder.matches.len()))
+        .title_bottom(Line::from(" [ctrl+t] columns [enter] jump [esc] close ").dim().right_aligned())
+        .borders(Borders::ALL)
+        .border_style(if focused { Style::default().fg(Color::Green) } else { Style::new().dim() })
+        .padding(Padding { left: 0, right: 1, top: 0, bottom: 0 });
+      let layout = Layout::default()
+        .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Fill(1)])
+        .direction(Direction::Vertical)
+        .split(block.inner(area));
+      f.render_widget(block, area);
+      f.render_widget(
+        Line::from(vec![Span::styled("> ", Style::default().fg(Color::Yellow)), Span::raw(finder.query.clone())]),
+        layout[0],
+      );
+      let columns = match (finder.columns, &self.all_columns) {
+        (false, _) => "columns: off",
+        (true, None) => "columns: loading…",
+        (true, Some(_)) => "columns: on",
+      };
+      f.render_widget(Line::from(columns).dim(), layout[1]);
+      // only the matches up to the bottom of the scrolled list are drawn
+      let width = layout[2].width as usize;
+      let shown = finder.list_state.selected().unwrap_or(0) + layout[2].height as usize;
+      let list_items: Vec<ListItem> = finder
+        .matches
+        .iter()
+        .take(shown)
+        .map(|found| {
+          let mut spans: Vec<Span> = found
+            .text
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+              match found.positions.contains(&i) {
+                true => Span::styled(c.to_string(), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                false => Span::raw(c.to_string()),
+              }
+            })
+            .collect();
+          let label = found.target.label();
+          let padding = width.saturating_sub(found.text.chars().count() + label.chars().count()).max(1);
+          spans.push(Span::raw(" ".repeat(padding)));
+          spans.push(Span::styled(label, Style::new().dim()));
+          ListItem::new(Line::from(spans))
+        })
+        .collect();
+      let list = List::default().items(list_items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+      f.render_stateful_widget(list, layout[2], &mut finder.list_state);
+      return Ok(());
+    }
+    if let Some(actions) = self.actions.as_mut() {
+      let name =
+        if actions.schema.is_empty() { actions.table.clone() } else { format!("{}.{}", actions.schema, actions.table) };
+      let hints = match actions.renaming {
+        Some((_, true)) => " [enter] rename [esc] back ",
+        Some((_, false)) => " [enter] to editor [esc] back ",
+        None => " [enter] run [e] to editor [esc] back ",
+      };
+      let block = Block::default()
+        .title(format!(" 󰓫  {} <alt+1> ", name))
+        .title_bottom(Line::from(hints).dim().right_aligned())
+        .borders(Borders::ALL)
+        .border_style(if focused { Style::default().fg(Color::Green) } else { Style::new().dim() });
+      let [list_area, input_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(if actions.renaming.is_some() { 1 } else { 0 })])
+          .areas(block.inner(area));
+      f.render_widget(block, area);
+      let list_items: Vec<ListItem> =
+        self.table_actions.iter().map(|action| ListItem::new(action.label().to_owned())).collect();
+      let list = List::default().items(list_items).highlight_style(
+        Style::default().fg(if focused { Color::Green } else { Color::Gray }).add_modifier(if focused {
+          Modifier::BOLD
+        } else {
+          Modifier::REVERSED
+        }),
+      );
+      f.render_stateful_widget(list, list_area, &mut actions.list_state);
+      if let Some((new_name, _)) = &actions.renaming {
+        f.render_widget(
+          Line::from(vec![
+            Span::styled("rename to > ", Style::default().fg(Color::Yellow)),
+            Span::raw(new_name.clone()),
+          ]),
+          input_area,
+        );
+      }
+      return Ok(());
+    }
+    let stable_keys = self.object_map.keys().enumerate();
     let mut constraints: Vec<Constraint> = stable_keys
       .clone()
       .map(|(i, k)| {
@@ -370,49 +1477,107 @@ impl<DB: Database> Component<DB> for Menu {
               Style::new().dim()
             })
             .padding(Padding { left: 0, right: 1, top: 0, bottom: 0 });
+          let block = match loading_menu {
+            true => block.title_bottom(Line::from(" loading… [q] cancel ").dim().right_aligned()),
+            false => block,
+          };
           let block_margin = layout[layout_index].inner(Margin { vertical: 1, horizontal: 0 });
-          let tables = self.table_map.get_key_value(k).unwrap().1.clone();
-          let filtered_tables: Vec<String> = tables
-            .into_iter()
-            .filter(|t| {
-              if let Some(search) = self.search.as_ref() {
-                t.to_lowercase().contains(search.to_lowercase().trim())
-              } else {
-                true
-              }
-            })
-            .collect();
-          let table_length = filtered_tables.len();
+          let items = visible_items.clone();
+          let item_count = items.len();
           let available_height = block.inner(parent_block.inner(area)).height as usize;
-          let selected_table_index = self.list_state.selected();
-          let filtered_tables_items: Vec<ListItem> = filtered_tables
+          let selected_index = self.list_state.selected();
+          let width = block.inner(layout[layout_index]).width as usize;
+          let list_items: Vec<ListItem> = items
             .into_iter()
             .enumerate()
-            .map(|(i, t)| {
-              let is_selected = selected_table_index == Some(i);
-              if is_selected && focused && !self.search_focused {
-                ListItem::new(Text::from(vec![
-                  Line::from(t),
-                  Line::from(if app_state.query_task.is_some() { "├[...] rows" } else { "├[<enter>] rows" }),
-                  Line::from(if app_state.query_task.is_some() { "├[...] columns" } else { "├[1] columns" }),
-                  Line::from(if app_state.query_task.is_some() {
-                    "├[...] constraints"
-                  } else {
-                    "├[2] constraints"
-                  }),
-                  Line::from(if app_state.query_task.is_some() { "├[...] indexes" } else { "├[3] indexes" }),
-                  Line::from(if app_state.query_task.is_some() {
-                    "└[...] rls policies"
-                  } else {
-                    "└[4] rls policies"
-                  }),
-                ]))
-              } else {
-                ListItem::new(t)
+            .map(|(i, item)| {
+              // the size of tables is right aligned, as long as it fits
+              let object_line = |name: &str, size: Option<String>| {
+                match size {
+                  Some(size) => {
+                    let padding = width.saturating_sub(name.chars().count() + size.chars().count() + 2).max(1);
+                    Line::from(vec![
+                      Span::raw(format!("  {}{}", name, " ".repeat(padding))),
+                      Span::styled(size, Style::new().dim()),
+                    ])
+                  },
+                  None => Line::from(format!("  {}", name)),
+                }
+              };
+              let show_hints = selected_index == Some(i) && focused && !self.search_focused;
+              match item {
+                TreeItem::Pins(count) => ListItem::new(format!("★ pinned ({})", count)),
+                TreeItem::Pinned(schema, table) => {
+                  let name =
+                    if schema == *k || schema.is_empty() { table.clone() } else { format!("{}.{}", schema, table) };
+                  let mut lines = vec![object_line(&name, self.sizes.get(&(schema, table)).map(TableSize::label))];
+                  if show_hints {
+                    let mut hints: Vec<(&str, &str)> =
+                      previews(ObjectKind::Table).into_iter().map(|(key, label, _)| (key, label)).collect();
+                    hints.extend([
+                      ("e", "definition to editor"),
+                      ("y", "copy definition"),
+                      ("l", "go to table"),
+                      ("p", "unpin"),
+                    ]);
+                    if !self.table_actions.is_empty() {
+                      hints.push(("a", "actions"));
+                    }
+                    lines.extend(hint_lines("  ", hints, app_state.query_task.is_some()));
+                  }
+                  ListItem::new(Text::from(lines))
+                },
+                TreeItem::Kind(kind, count) => {
+                  let marker =
+                    if self.search.as_ref().is_some_and(|s| !s.trim().is_empty()) || self.expanded.contains(&kind) {
+                      "▾"
+                    } else {
+                      "▸"
+                    };
+                  let sort =
+                    if kind == ObjectKind::Table && self.table_sort == TableSort::Size { " by size" } else { "" };
+                  ListItem::new(format!("{} {} ({}){}", marker, kind.label(), count, sort))
+                },
+                TreeItem::Object(kind, name) => {
+                  let size = match kind {
+                    ObjectKind::Table => self.sizes.get(&(k.clone(), name.clone())).map(TableSize::label),
+                    _ => None,
+                  };
+                  let mut lines = vec![object_line(&name, size)];
+                  let loading_columns = app_state
+                    .metadata_tasks
+                    .iter()
+                    .any(|task| matches!(task, MetadataTask::Columns(s, t, _) if s == k && t == &name));
+                  if loading_columns && self.expanded_tables.contains(&(k.clone(), name.clone())) {
+                    lines.push(Line::from("    loading columns…").dim());
+                  }
+                  if show_hints {
+                    let mut hints: Vec<(&str, &str)> =
+                      previews(kind).into_iter().map(|(key, label, _)| (key, label)).collect();
+                    hints.extend([("e", "definition to editor"), ("y", "copy definition")]);
+                    if kind == ObjectKind::Table {
+                      let pinned = self.pinned.contains(&(k.clone(), name.clone()));
+                      hints.push(("p", if pinned { "unpin" } else { "pin" }));
+                      if !self.table_actions.is_empty() {
+                        hints.push(("a", "actions"));
+                      }
+                    }
+                    lines.extend(hint_lines("  ", hints, app_state.query_task.is_some()));
+                  }
+                  ListItem::new(Text::from(lines))
+                },
+                TreeItem::Column(_, column) => {
+                  let mut lines = vec![column.line()];
+                  if show_hints {
+                    let hints = vec![("<enter>", "insert name"), ("1", "distinct values")];
+                    lines.extend(hint_lines("    ", hints, app_state.query_task.is_some()));
+                  }
+                  ListItem::new(Text::from(lines))
+                },
               }
             })
             .collect();
-          let list = List::default().items(filtered_tables_items).block(block).highlight_style(
+          let list = List::default().items(list_items).block(block).highlight_style(
             Style::default()
               .fg(if focused && !self.search_focused && self.menu_focus == MenuFocus::Tables {
                 Color::Green
@@ -430,10 +1595,10 @@ impl<DB: Database> Component<DB> for Menu {
               Style::default()
             });
           let mut vertical_scrollbar_state =
-            ScrollbarState::new(table_length.saturating_sub(available_height)).position(self.list_state.offset());
+            ScrollbarState::new(item_count.saturating_sub(available_height)).position(self.list_state.offset());
           f.render_stateful_widget(vertical_scrollbar, block_margin, &mut vertical_scrollbar_state);
         },
-        x if x == self.table_map.keys().len().saturating_sub(1) => {
+        x if x == self.object_map.keys().len().saturating_sub(1) => {
           f.render_widget(
             Text::styled(
               "└ ".to_owned() + k.to_owned().as_str(),
@@ -467,3 +1632,323 @@ impl<DB: Database> Component<DB> for Menu {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use strum::IntoEnumIterator;
+
+  use super::*;
+
+  fn rows(rows: &[&[&str]]) -> Option<Result<Rows, DbError>> {
+    Some(Ok(Rows {
+      headers: vec![],
+      rows: rows.iter().map(|row| row.iter().map(|s| s.to_string()).collect()).collect(),
+      rows_affected: None,
+    }))
+  }
+
+  fn menu() -> Menu {
+    let mut menu = Menu::new();
+    menu.set_table_list(rows(&[&["public", "orders"], &["public", "users"]]));
+    menu.set_schema_objects(rows(&[
+      &["public", "view", "user_orders"],
+      &["public", "function", "add(integer, integer)"],
+      &["public", "procedure", "touch_users()"],
+      &["extensions", "extension", "pgcrypto"],
+    ]));
+    menu
+  }
+
+  #[test]
+  fn test_visible_items_only_expand_tables() {
+    let menu = menu();
+    assert_eq!(menu.object_map.keys().collect::<Vec<_>>(), vec!["public", "extensions"]);
+    assert_eq!(menu.visible_items(), vec![
+      TreeItem::Kind(ObjectKind::Table, 2),
+      TreeItem::Object(ObjectKind::Table, "orders".to_owned()),
+      TreeItem::Object(ObjectKind::Table, "users".to_owned()),
+      TreeItem::Kind(ObjectKind::View, 1),
+      TreeItem::Kind(ObjectKind::Function, 2),
+    ]);
+  }
+
+  #[test]
+  fn test_collapse_keeps_kind_selected() {
+    let mut menu = menu();
+    menu.menu_focus = MenuFocus::Tables;
+    menu.list_state = ListState::default().with_selected(Some(2));
+    menu.collapse_selected().unwrap();
+    assert_eq!(menu.list_state.selected(), Some(0));
+    assert_eq!(menu.visible_items().len(), 3);
+    menu.list_state = ListState::default().with_selected(Some(2));
+    menu.expand_selected().unwrap();
+    assert_eq!(menu.visible_items()[3], TreeItem::Object(ObjectKind::Function, "add(integer, integer)".to_owned()));
+  }
+
+  #[test]
+  fn test_search_expands_matching_kinds() {
+    let mut menu = menu();
+    menu.search = Some("user".to_owned());
+    assert_eq!(menu.visible_items(), vec![
+      TreeItem::Kind(ObjectKind::Table, 1),
+      TreeItem::Object(ObjectKind::Table, "users".to_owned()),
+      TreeItem::Kind(ObjectKind::View, 1),
+      TreeItem::Object(ObjectKind::View, "user_orders".to_owned()),
+      TreeItem::Kind(ObjectKind::Function, 1),
+      TreeItem::Object(ObjectKind::Function, "touch_users()".to_owned()),
+    ]);
+  }
+
+  #[test]
+  fn test_expanded_table_lists_columns() {
+    let mut menu = menu();
+    menu.expanded_tables.insert(("public".to_owned(), "users".to_owned()));
+    menu.set_columns(
+      "public",
+      "users",
+      rows(&[&["id", "integer", "NO", "", "PK,FK"], &["name", "text", "YES", "'anon'::text", ""]]),
+    );
+    let items = menu.visible_items();
+    assert_eq!(
+      items[3],
+      TreeItem::Column("users".to_owned(), Column {
+        name: "id".to_owned(),
+        data_type: "integer".to_owned(),
+        nullable: false,
+        default: "".to_owned(),
+        primary_key: true,
+        foreign_key: true,
+      })
+    );
+    assert!(matches!(&items[4], TreeItem::Column(_, c) if c.nullable && c.default == "'anon'::text" && !c.primary_key));
+    assert_eq!(items[5], TreeItem::Kind(ObjectKind::View, 1));
+  }
+
+  #[test]
+  fn test_sort_tables_by_size() {
+    let mut menu = menu();
+    menu.set_table_sizes(rows(&[&["public", "orders", "10", "8192"], &["public", "users", "NULL", "16384"]]));
+    menu.menu_focus = MenuFocus::Tables;
+    menu.list_state = ListState::default().with_selected(Some(1));
+    menu.toggle_table_sort();
+    assert_eq!(&menu.visible_items()[1..3], &[
+      TreeItem::Object(ObjectKind::Table, "users".to_owned()),
+      TreeItem::Object(ObjectKind::Table, "orders".to_owned()),
+    ]);
+    assert_eq!(menu.list_state.selected(), Some(2));
+    assert_eq!(menu.sizes[&("public".to_owned(), "users".to_owned())].label(), "16 kB");
+  }
+
+  #[test]
+  fn test_format_sizes() {
+    assert_eq!(format_count(999), "999");
+    assert_eq!(format_count(1234), "1.2k");
+    assert_eq!(format_count(56_789_000), "56M");
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(49152), "48 kB");
+    assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3 GB");
+  }
+
+  #[test]
+  fn test_preview_keys_are_unique() {
+    // the keys handled by the menu itself, which a preview would shadow
+    let reserved = ["/", "h", "j", "k", "l", "g", "G", "R", "F", "p", "a", "s", "e", "y", "E"];
+    for kind in ObjectKind::iter() {
+      let keys: Vec<&str> = previews(kind).iter().map(|(key, _, _)| *key).collect();
+      assert_eq!(keys.iter().collect::<HashSet<_>>().len(), keys.len(), "{kind}");
+      assert!(!keys.iter().any(|key| reserved.contains(key)), "{kind}");
+    }
+  }
+
+  #[test]
+  fn test_reload_keeps_selection() {
+    let mut menu = menu();
+    menu.schema_index = 0;
+    menu.change_focus(MenuFocus::Tables);
+    menu.list_state = ListState::default().with_selected(Some(2));
+    menu.set_table_list(rows(&[&["new", "t"], &["public", "accounts"], &["public", "users"]]));
+    menu.set_schema_objects(rows(&[&["public", "view", "user_orders"]]));
+    assert_eq!(menu.object_map.get_index(menu.schema_index).unwrap().0, "public");
+    assert_eq!(menu.menu_focus, MenuFocus::Tables);
+    assert_eq!(menu.selected_item(), Some(TreeItem::Object(ObjectKind::Table, "users".to_owned())));
+
+    // the selected table was dropped, so the one closest to it is selected
+    menu.set_table_list(rows(&[&["public", "accounts"]]));
+    menu.set_schema_objects(rows(&[]));
+    assert_eq!(menu.selected_item(), Some(TreeItem::Object(ObjectKind::Table, "accounts".to_owned())));
+  }
+
+  #[test]
+  fn test_finder_ranks_matches_across_schemas() {
+    let mut menu = menu();
+    menu.open_finder();
+    "ord".chars().for_each(|c| menu.handle_finder_key(KeyEvent::from(KeyCode::Char(c))).unwrap());
+    let finder = menu.finder.as_ref().unwrap();
+    assert_eq!(finder.matches.iter().map(|m| m.text.as_str()).collect::<Vec<_>>(), vec![
+      "public.orders",
+      "public.user_orders"
+    ]);
+    assert_eq!(finder.matches[0].positions, vec![7, 8, 9]);
+  }
+
+  #[test]
+  fn test_finder_jumps_to_column_once_loaded() {
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut menu = menu();
+    menu.command_tx = Some(tx);
+    menu.set_all_columns(rows(&[&["public", "users", "id"], &["public", "users", "email"], &[
+      "public",
+      "user_orders",
+      "email",
+    ]]));
+    menu.open_finder();
+    menu.handle_finder_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL)).unwrap();
+    "email".chars().for_each(|c| menu.handle_finder_key(KeyEvent::from(KeyCode::Char(c))).unwrap());
+    // only columns of tables are searched, since views can't be expanded
+    assert_eq!(menu.finder.as_ref().unwrap().matches.len(), 1);
+    menu.handle_finder_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+    assert!(menu.finder.is_none());
+    assert_eq!(menu.selected_item(), Some(TreeItem::Object(ObjectKind::Table, "users".to_owned())));
+    menu.set_columns("public", "users", rows(&[&["id", "integer", "NO", "", "PK"], &["email", "text", "YES", "", ""]]));
+    assert!(
+      matches!(menu.selected_item(), Some(TreeItem::Column(table, column)) if table == "users" && column.name == "email")
+    );
+  }
+
+  #[test]
+  fn test_databases_are_above_schemas() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut menu = menu();
+    menu.command_tx = Some(tx);
+    menu.set_databases(rows(&[&["postgres", "true"], &["shop", "false"]]));
+    assert_eq!(menu.database.as_deref(), Some("postgres"));
+    menu.change_focus(MenuFocus::Database);
+    assert_eq!((&menu.menu_focus, menu.database_index), (&MenuFocus::Database, 0));
+    menu.scroll_down();
+    menu.switch_to_selected_database().unwrap();
+    assert!(matches!(rx.try_recv(), Ok(Action::SwitchDatabase(database)) if database == "shop"));
+    // once switched, the menu goes back to the schemas
+    menu.set_databases(rows(&[&["postgres", "false"], &["shop", "true"]]));
+    assert_eq!(menu.database.as_deref(), Some("shop"));
+    assert_eq!(menu.menu_focus, MenuFocus::Schema);
+  }
+
+  #[test]
+  fn test_pinned_tables_show_above_every_schema() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut menu = menu();
+    menu.command_tx = Some(tx);
+    menu.set_pinned_tables(vec![("public".to_owned(), "gone".to_owned())]);
+    assert_eq!(menu.visible_items()[0], TreeItem::Kind(ObjectKind::Table, 2));
+    menu.menu_focus = MenuFocus::Tables;
+    menu.list_state = ListState::default().with_selected(Some(2));
+    menu.toggle_pinned().unwrap();
+    assert!(matches!(rx.try_recv(), Ok(Action::SavePinnedTables(pinned)) if pinned.len() == 2));
+    assert_eq!(&menu.visible_items()[..2], &[
+      TreeItem::Pins(1),
+      TreeItem::Pinned("public".to_owned(), "users".to_owned())
+    ]);
+    assert_eq!(menu.selected_item(), Some(TreeItem::Object(ObjectKind::Table, "users".to_owned())));
+    menu.schema_index = 1;
+    assert_eq!(menu.visible_items()[1], TreeItem::Pinned("public".to_owned(), "users".to_owned()));
+  }
+
+  #[test]
+  fn test_triggers_are_requested_by_name_and_table() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut menu = menu();
+    menu.command_tx = Some(tx);
+    menu
+      .set_schema_objects(rows(&[&["public", "trigger", "audit", "users"], &["public", "trigger", "audit", "orders"]]));
+    menu.menu_focus = MenuFocus::Tables;
+    menu.search = Some("audit on users".to_owned());
+    menu.list_state = ListState::default().with_selected(Some(1));
+    assert_eq!(menu.selected_item(), Some(TreeItem::Object(ObjectKind::Trigger, "audit on users".to_owned())));
+    menu.request_definition(DefinitionTarget::Editor).unwrap();
+    assert!(matches!(
+      rx.try_recv(),
+      Ok(Action::RequestDefinition(ObjectKind::Trigger, _, name, Some(table), _)) if name == "audit" && table == "users"
+    ));
+    menu.preview_selected("2").unwrap();
+    assert!(matches!(
+      rx.try_recv(),
+      Ok(Action::MenuPreview(MenuPreview::Dependencies(ObjectKind::Trigger, Some(table)), _, name))
+        if name == "audit" && table == "users"
+    ));
+  }
+
+  #[test]
+  fn test_table_actions_run_or_go_to_editor() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut menu = menu();
+    menu.command_tx = Some(tx);
+    menu.set_table_actions(vec![TableAction::Count, TableAction::Rename(String::new()), TableAction::Drop]);
+    menu.menu_focus = MenuFocus::Tables;
+    menu.list_state = ListState::default().with_selected(Some(2));
+    menu.open_table_actions();
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    menu.handle_table_actions_key(key(KeyCode::Char('e'))).unwrap();
+    assert!(matches!(
+      rx.try_recv(),
+      Ok(Action::TableAction(TableAction::Count, schema, table, false)) if schema == "public" && table == "users"
+    ));
+    assert!(menu.actions.is_none());
+    menu.open_table_actions();
+    menu.handle_table_actions_key(key(KeyCode::Char('j'))).unwrap();
+    menu.handle_table_actions_key(key(KeyCode::Enter)).unwrap();
+    assert!(menu.is_typing());
+    for code in [KeyCode::Backspace, KeyCode::Char('z'), KeyCode::Enter] {
+      menu.handle_table_actions_key(key(code)).unwrap();
+    }
+    assert!(matches!(
+      rx.try_recv(),
+      Ok(Action::TableAction(TableAction::Rename(name), _, _, true)) if name == "userz"
+    ));
+    assert!(!menu.is_typing());
+  }
+
+  #[test]
+  fn test_rows_preview_waits_for_the_primary_key() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut menu = menu();
+    menu.command_tx = Some(tx);
+    menu.menu_focus = MenuFocus::Tables;
+    menu.list_state = ListState::default().with_selected(Some(2));
+    menu.preview_selected("L").unwrap();
+    assert!(matches!(rx.try_recv(), Ok(Action::LoadMenuColumns(_, table)) if table == "users"));
+    assert!(rx.try_recv().is_err());
+    menu.set_columns("public", "users", rows(&[&["id", "integer", "NO", "", "PK"], &["email", "text", "NO", "", ""]]));
+    assert!(matches!(
+      rx.try_recv(),
+      Ok(Action::MenuPreview(MenuPreview::Rows(RowsPreview { primary_key, descending: true, .. }), _, table))
+        if primary_key == vec!["id".to_owned()] && table == "users"
+    ));
+    menu.preview_selected("<enter>").unwrap();
+    assert!(matches!(
+      rx.try_recv(),
+      Ok(Action::MenuPreview(MenuPreview::Rows(RowsPreview { descending: false, .. }), ..))
+    ));
+  }
+
+  #[test]
+  fn test_pinned_tables_are_saved_per_connection_and_database() {
+    let dir = std::env::temp_dir().join(format!("rainfrog-pins-{}", std::process::id()));
+    let users = vec![("public".to_owned(), "users".to_owned())];
+    let mut pinned = PinnedTables::load(&dir).unwrap();
+    pinned.set("local", Some("app"), users.clone());
+    pinned.set("local", Some("other"), vec![("public".to_owned(), "orders".to_owned())]);
+    pinned.set("file", None, users.clone());
+    pinned.set("prod", Some("app"), vec![]);
+    pinned.save(&dir).unwrap();
+    let mut pinned = PinnedTables::load(&dir).unwrap();
+    assert_eq!(pinned.get("local", Some("app")), users);
+    assert_eq!(pinned.get("local", Some("other")), vec![("public".to_owned(), "orders".to_owned())]);
+    assert!(pinned.get("local", Some("postgres")).is_empty());
+    assert_eq!(pinned.get("file", None), users);
+    assert!(pinned.get("prod", Some("app")).is_empty());
+    pinned.rename("local", "dev");
+    assert!(pinned.get("local", Some("app")).is_empty());
+    assert_eq!(pinned.get("dev", Some("app")), users);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
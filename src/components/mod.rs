@@ -22,6 +22,7 @@ pub enum ComponentImpls {
 
 pub mod data;
 pub mod editor;
+pub mod er_diagram;
 pub mod favorites;
 pub mod history;
 pub mod menu;
@@ -201,6 +201,15 @@ impl<'a> ScrollTable<'a> {
     self
   }
 
+  // keeps the position and selection of the table this one replaces
+  pub fn restore_scroll(&mut self, previous: &ScrollTable) -> &mut Self {
+    self.x_offset = previous.x_offset;
+    self.y_offset = previous.y_offset.min(self.max_y_offset);
+    self.current_column_index = previous.current_column_index;
+    self.selection_mode = previous.selection_mode.clone();
+    self
+  }
+
   pub fn get_cell_offsets(&self) -> (u16, usize) {
     let column_count = self.requested_width.saturating_div(self.column_width);
     let col_index = (self.x_offset.saturating_sub(self.x_offset % self.column_width)).saturating_div(self.column_width);
@@ -1,7 +1,11 @@
-use std::{collections::HashMap, fmt, path::PathBuf};
+use std::{
+  collections::HashMap,
+  fmt,
+  path::{Path, PathBuf},
+};
 
 use color_eyre::eyre::{self, Result};
-use config::Value;
+use config::{builder::DefaultState, ConfigBuilder, Value, ValueKind};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use derive_deref::{Deref, DerefMut};
 use ratatui::style::{Color, Modifier, Style};
@@ -40,6 +44,7 @@ pub enum ConnectionString {
 #[derive(Clone, Debug, Deserialize)]
 pub struct StructuredConnection {
   pub host: String,
+  #[serde(deserialize_with = "deserialize_port")]
   pub port: u32,
   pub database: String,
   pub username: String,
@@ -52,6 +57,63 @@ pub struct DatabaseConnection {
   pub connection: ConnectionString,
   #[serde(default)]
   pub default: bool,
+  #[serde(default)]
+  pub tunnel: Option<TunnelConfig>,
+  // shown on the connection's tab, e.g. red for production
+  #[serde(default)]
+  pub color: Option<Color>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TunnelConfig {
+  pub host: String,
+  #[serde(default = "default_ssh_port")]
+  pub port: u16,
+  pub user: String,
+  pub key_path: PathBuf,
+  // for encrypted keys; there is no prompt, since tunnels are also opened from inside the tui
+  #[serde(default)]
+  pub key_passphrase: Option<String>,
+  #[serde(default)]
+  pub jump_host: Option<JumpHost>,
+  // defaults to the user's ~/.ssh/known_hosts
+  #[serde(default)]
+  pub known_hosts_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct JumpHost {
+  pub host: String,
+  #[serde(default = "default_ssh_port")]
+  pub port: u16,
+  // falls back to the tunnel's user and key when not set
+  #[serde(default)]
+  pub user: Option<String>,
+  #[serde(default)]
+  pub key_path: Option<PathBuf>,
+  #[serde(default)]
+  pub key_passphrase: Option<String>,
+}
+
+fn default_ssh_port() -> u16 {
+  22
+}
+
+// interpolated values are always strings, so `port = "${PGPORT:-5432}"` has to be accepted too
+fn deserialize_port<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Port {
+    Number(u32),
+    String(String),
+  }
+  match Port::deserialize(deserializer)? {
+    Port::Number(port) => Ok(port),
+    Port::String(port) => port.trim().parse().map_err(|_| de::Error::custom(format!("invalid port `{port}`"))),
+  }
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -90,35 +152,15 @@ impl StructuredConnection {
 }
 
 impl Config {
-  pub fn new() -> Result<Self, config::ConfigError> {
+  pub fn new() -> Result<Self> {
     let default_config: Config = toml::from_str(CONFIG).unwrap();
-    let data_dir = crate::utils::get_data_dir();
-    let config_dir = crate::utils::get_config_dir();
-    let favorites_dir = crate::utils::get_favorites_dir();
-    let mut builder = config::Config::builder()
-      .set_default("_data_dir", data_dir.to_str().unwrap())?
-      .set_default("_config_dir", config_dir.to_str().unwrap())?
-      .set_default("_favorites_dir", favorites_dir.to_str().unwrap())?;
-
-    let config_files = [
-      ("rainfrog_config.json5", config::FileFormat::Json5),
-      ("rainfrog_config.json", config::FileFormat::Json),
-      ("rainfrog_config.yaml", config::FileFormat::Yaml),
-      ("rainfrog_config.toml", config::FileFormat::Toml),
-      ("rainfrog_config.ini", config::FileFormat::Ini),
-    ];
-    let mut found_config = false;
-    for (file, format) in &config_files {
-      builder = builder.add_source(config::File::from(config_dir.join(file)).format(*format).required(false));
-      if config_dir.join(file).exists() {
-        found_config = true
-      }
-    }
-    if !found_config {
-      log::error!("No configuration file found. Application may not behave as expected");
+    let (value, mut problems) = Self::load()?;
+    problems.extend(validate(&value));
+    problems.sort();
+    if !problems.is_empty() {
+      return Err(ConfigErrors(problems).into());
     }
-
-    let mut cfg: Self = builder.build()?.try_deserialize()?;
+    let mut cfg: Self = value.try_deserialize()?;
 
     for (focus, default_bindings) in default_config.keybindings.iter() {
       let user_bindings = cfg.keybindings.entry(*focus).or_default();
@@ -141,6 +183,329 @@ impl Config {
 
     Ok(cfg)
   }
+
+  /// Loads the user's config files the same way as `Config::new`, but returns
+  /// every invalid entry instead of failing on the first one.
+  pub fn check() -> Result<Vec<ConfigProblem>> {
+    let (value, mut problems) = Self::load()?;
+    problems.extend(validate(&value));
+    problems.sort();
+    Ok(problems)
+  }
+
+  // the config files in use, in the order they're merged, including any that are included
+  pub fn files() -> Vec<PathBuf> {
+    let mut files = Self::main_files();
+    if let Ok(main) = Self::builder().and_then(|builder| builder.build()) {
+      files.extend(include_paths(&main.cache).unwrap_or_default().into_iter().filter(|path| path.exists()));
+    }
+    files
+  }
+
+  // the included file that defines `[db.<name>]`, if any. when several do, the last one merged wins
+  pub fn included_entry_file(name: &str) -> Option<PathBuf> {
+    let main = Self::builder().and_then(|builder| builder.build()).ok()?;
+    let includes = include_paths(&main.cache).unwrap_or_default();
+    includes.into_iter().rev().filter(|path| path.exists()).find(|path| {
+      config::Config::builder()
+        .add_source(config::File::from(path.as_path()))
+        .build()
+        .and_then(|file| file.get_table("db"))
+        .is_ok_and(|db| db.contains_key(name))
+    })
+  }
+
+  fn main_files() -> Vec<PathBuf> {
+    let config_dir = crate::utils::get_config_dir();
+    CONFIG_FILES.iter().map(|(file, _)| config_dir.join(file)).filter(|path| path.exists()).collect()
+  }
+
+  fn builder() -> Result<ConfigBuilder<DefaultState>, config::ConfigError> {
+    let data_dir = crate::utils::get_data_dir();
+    let config_dir = crate::utils::get_config_dir();
+    let favorites_dir = crate::utils::get_favorites_dir();
+    let mut builder = config::Config::builder()
+      .set_default("_data_dir", data_dir.to_str().unwrap())?
+      .set_default("_config_dir", config_dir.to_str().unwrap())?
+      .set_default("_favorites_dir", favorites_dir.to_str().unwrap())?;
+
+    for (file, format) in &CONFIG_FILES {
+      builder = builder.add_source(config::File::from(config_dir.join(file)).format(*format).required(false));
+    }
+    Ok(builder)
+  }
+
+  // merges the main config files with the files they include, and fills in environment variables.
+  // problems with either are returned alongside the config so they can be reported with the rest
+  fn load() -> Result<(Value, Vec<ConfigProblem>), config::ConfigError> {
+    if Self::main_files().is_empty() {
+      log::error!("No configuration file found. Application may not behave as expected");
+    }
+    let mut problems = vec![];
+    let mut builder = Self::builder()?;
+    let main = builder.build_cloned()?.cache;
+    let includes = include_paths(&main).unwrap_or_else(|e| {
+      problems.push(ConfigProblem::new(&main, "include".to_string(), e));
+      vec![]
+    });
+    // included files are merged last, so machine specific settings override shared ones
+    for path in includes {
+      if path.exists() {
+        builder = builder.add_source(config::File::from(path));
+      } else {
+        log::warn!("included config file {} does not exist", path.display());
+      }
+    }
+
+    let mut value = builder.build()?.cache;
+    if let ValueKind::Table(table) = &mut value.kind {
+      if let Some(db) = table.get_mut("db") {
+        interpolate_env(db, "db".to_string(), &|name| std::env::var(name).ok(), &mut problems);
+      }
+    }
+    Ok((value, problems))
+  }
+}
+
+// paths in `include` may start with `~`, and are relative to the config dir otherwise
+fn include_paths(value: &Value) -> Result<Vec<PathBuf>, String> {
+  let include = match &value.kind {
+    ValueKind::Table(table) => table.get("include"),
+    _ => None,
+  };
+  let Some(include) = include else {
+    return Ok(vec![]);
+  };
+  let config_dir = crate::utils::get_config_dir();
+  include
+    .clone()
+    .into_array()
+    .map_err(|_| "expected a list of file paths".to_string())?
+    .into_iter()
+    .map(|path| {
+      let path = path.into_string().map_err(|_| "expected a list of file paths".to_string())?;
+      Ok(config_dir.join(crate::utils::expand_home(Path::new(&path))))
+    })
+    .collect()
+}
+
+// replaces `${VAR}` and `${VAR:-default}` in every string under `value`
+fn interpolate_env(
+  value: &mut Value,
+  location: String,
+  lookup: &dyn Fn(&str) -> Option<String>,
+  problems: &mut Vec<ConfigProblem>,
+) {
+  let origin = origin(value);
+  match &mut value.kind {
+    ValueKind::String(s) => {
+      match interpolate(s, lookup) {
+        Ok(interpolated) => *s = interpolated,
+        Err(message) => problems.push(ConfigProblem { file: origin, location, message }),
+      }
+    },
+    ValueKind::Table(table) => {
+      for (key, value) in table.iter_mut() {
+        interpolate_env(value, format!("{location}.{key}"), lookup, problems);
+      }
+    },
+    ValueKind::Array(array) => {
+      for (i, value) in array.iter_mut().enumerate() {
+        interpolate_env(value, format!("{location}[{i}]"), lookup, problems);
+      }
+    },
+    _ => {},
+  }
+}
+
+/// Expands `${VAR}` and `${VAR:-default}` using the process environment.
+pub fn expand_env(s: &str) -> Result<String, String> {
+  interpolate(s, &|name| std::env::var(name).ok())
+}
+
+fn interpolate(s: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+  let mut result = String::with_capacity(s.len());
+  let mut rest = s;
+  while let Some(start) = rest.find("${") {
+    result.push_str(&rest[..start]);
+    let end = rest[start..].find('}').ok_or_else(|| format!("unterminated `${{` in `{s}`"))? + start;
+    let expression = &rest[start + 2..end];
+    let (name, default) = match expression.split_once(":-") {
+      Some((name, default)) => (name, Some(default)),
+      None => (expression, None),
+    };
+    // like the shell, an empty variable falls back to the default too
+    match (lookup(name).filter(|value| !value.is_empty() || default.is_none()), default) {
+      (Some(value), _) => result.push_str(&value),
+      (None, Some(default)) => result.push_str(default),
+      (None, None) => return Err(format!("environment variable `{name}` is not set")),
+    }
+    rest = &rest[end + 1..];
+  }
+  result.push_str(rest);
+  Ok(result)
+}
+
+const CONFIG_FILES: [(&str, config::FileFormat); 5] = [
+  ("rainfrog_config.json5", config::FileFormat::Json5),
+  ("rainfrog_config.json", config::FileFormat::Json),
+  ("rainfrog_config.yaml", config::FileFormat::Yaml),
+  ("rainfrog_config.toml", config::FileFormat::Toml),
+  ("rainfrog_config.ini", config::FileFormat::Ini),
+];
+
+/// An invalid entry in the user's config, e.g. an unknown action for a keybinding.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ConfigProblem {
+  // the file the entry came from, when known
+  pub file: Option<String>,
+  // the entry's path in the config, e.g. `keybindings.Menu.<q>`
+  pub location: String,
+  pub message: String,
+}
+
+impl ConfigProblem {
+  fn new(value: &Value, location: String, message: impl fmt::Display) -> Self {
+    Self { file: origin(value), location, message: message.to_string() }
+  }
+}
+
+// tables don't always keep their origin, but their entries do
+fn origin(value: &Value) -> Option<String> {
+  let file = value.origin().map(|file| {
+    // file origins are relative to the working directory
+    std::fs::canonicalize(file).map_or_else(|_| file.to_string(), |path| path.display().to_string())
+  });
+  file.or_else(|| {
+    match &value.kind {
+      ValueKind::Table(table) => table.values().find_map(origin),
+      ValueKind::Array(array) => array.iter().find_map(origin),
+      _ => None,
+    }
+  })
+}
+
+impl fmt::Display for ConfigProblem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.file {
+      Some(file) => write!(f, "{file}: {}: {}", self.location, self.message),
+      None => write!(f, "{}: {}", self.location, self.message),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct ConfigErrors(pub Vec<ConfigProblem>);
+
+impl fmt::Display for ConfigErrors {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "invalid config:")?;
+    for problem in self.0.iter() {
+      writeln!(f, "  {problem}")?;
+    }
+    write!(f, "run `rainfrog config check` after fixing them to validate the config")
+  }
+}
+
+impl std::error::Error for ConfigErrors {
+}
+
+fn validate(value: &Value) -> Vec<ConfigProblem> {
+  let mut problems = vec![];
+  let table = match value.clone().into_table() {
+    Ok(table) => table,
+    Err(e) => return vec![ConfigProblem::new(value, String::new(), e)],
+  };
+  if let Some(keybindings) = table.get("keybindings") {
+    validate_focus_section(keybindings, "keybindings", &mut problems, |key, value| {
+      parse_key_sequence(key).map_err(|e| format!("invalid key `{key}`: {e}"))?;
+      let action = value.clone().into_string().map_err(|_| "expected an action name".to_string())?;
+      value.clone().try_deserialize::<Action>().map(|_| ()).map_err(|_| format!("unknown action `{action}`"))
+    });
+  }
+  if let Some(styles) = table.get("styles") {
+    validate_focus_section(styles, "styles", &mut problems, |_, value| {
+      let style = value.clone().into_string().map_err(|_| "expected a style string".to_string())?;
+      validate_style(&style)
+    });
+  }
+  if let Some(db) = table.get("db") {
+    match db.clone().into_table() {
+      Ok(connections) => {
+        for (name, connection) in connections {
+          if let Err(e) = validate_connection(&connection) {
+            problems.push(ConfigProblem::new(&connection, format!("db.{name}"), e));
+          }
+        }
+      },
+      Err(e) => problems.push(ConfigProblem::new(db, "db".to_string(), e)),
+    }
+  }
+  problems.sort();
+  problems
+}
+
+// keybindings and styles are both tables of focus -> entries
+fn validate_focus_section(
+  section: &Value,
+  name: &str,
+  problems: &mut Vec<ConfigProblem>,
+  validate_entry: impl Fn(&str, &Value) -> Result<(), String>,
+) {
+  let focuses = match section.clone().into_table() {
+    Ok(focuses) => focuses,
+    Err(e) => return problems.push(ConfigProblem::new(section, name.to_string(), e)),
+  };
+  for (focus, entries) in focuses {
+    let location = format!("{name}.{focus}");
+    // goes through the config crate so focus names are matched the same way as when loading
+    if Value::new(None, focus.as_str()).try_deserialize::<Focus>().is_err() {
+      problems.push(ConfigProblem::new(&entries, location, format!("unknown focus `{focus}`")));
+      continue;
+    }
+    match entries.clone().into_table() {
+      Ok(entries) => {
+        for (key, value) in entries {
+          if let Err(e) = validate_entry(&key, &value) {
+            problems.push(ConfigProblem::new(&value, format!("{location}.{key}"), e));
+          }
+        }
+      },
+      Err(e) => problems.push(ConfigProblem::new(&entries, location, e)),
+    }
+  }
+}
+
+// `parse_style` silently ignores anything it doesn't understand, so colors are checked here
+fn validate_style(style: &str) -> Result<(), String> {
+  let (foreground, background) = style.split_at(style.to_lowercase().find("on ").unwrap_or(style.len()));
+  for color in [foreground.to_string(), background.replacen("on ", "", 1)] {
+    let (color, _) = process_color_string(&color.to_lowercase());
+    if !color.trim().is_empty() && parse_color(&color).is_none() {
+      return Err(format!("unknown color `{}` in style `{style}`", color.trim()));
+    }
+  }
+  Ok(())
+}
+
+fn validate_connection(connection: &Value) -> Result<(), String> {
+  if let Some(driver) = connection.clone().into_table().ok().and_then(|table| table.get("driver").cloned()) {
+    if driver.clone().try_deserialize::<Driver>().is_err() {
+      return Err(format!("unknown driver `{driver}`, expected one of postgres, mysql or sqlite"));
+    }
+  }
+  let connection: DatabaseConnection = connection.clone().try_deserialize().map_err(|e| {
+    let e = e.to_string();
+    if e.contains("untagged enum ConnectionString") {
+      "expected either `connection_string` or `host`, `port`, `database` and `username`".to_string()
+    } else {
+      e
+    }
+  })?;
+  if connection.driver == Driver::Sqlite && matches!(connection.connection, ConnectionString::Structured { .. }) {
+    return Err("sqlite only supports `connection_string`".to_string());
+  }
+  Ok(())
 }
 
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
@@ -156,11 +521,13 @@ impl<'de> Deserialize<'de> for KeyBindings {
     let keybindings = parsed_map
       .into_iter()
       .map(|(focus, inner_map)| {
-        let converted_inner_map =
-          inner_map.into_iter().map(|(key_str, cmd)| (parse_key_sequence(&key_str).unwrap(), cmd)).collect();
-        (focus, converted_inner_map)
+        let converted_inner_map = inner_map
+          .into_iter()
+          .map(|(key_str, cmd)| Ok((parse_key_sequence(&key_str).map_err(de::Error::custom)?, cmd)))
+          .collect::<Result<_, D::Error>>()?;
+        Ok((focus, converted_inner_map))
       })
-      .collect();
+      .collect::<Result<_, D::Error>>()?;
 
     Ok(KeyBindings(keybindings))
   }
@@ -334,9 +701,20 @@ pub fn parse_key_sequence(raw: &str) -> Result<Vec<KeyEvent>, String> {
   sequences.into_iter().map(parse_key_event).collect()
 }
 
-#[derive(Clone, Debug, Default, Deref, DerefMut, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct Settings {
   pub mouse_mode: Option<bool>,
+  // re-run a query once the connection recovers, if it failed because the connection dropped
+  #[serde(default)]
+  pub retry_failed_query: bool,
+  // how many rows a preview loads at a time
+  pub preview_limit: Option<usize>,
+}
+
+impl Settings {
+  pub fn preview_limit(&self) -> usize {
+    self.preview_limit.unwrap_or(100).max(1)
+  }
 }
 
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
@@ -519,6 +897,76 @@ mod tests {
     Ok(())
   }
 
+  fn problems(toml: &str) -> Vec<String> {
+    let value = config::Config::builder()
+      .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+      .build()
+      .unwrap()
+      .cache;
+    validate(&value).iter().map(|problem| problem.to_string()).collect()
+  }
+
+  #[test]
+  fn test_validate_reports_every_problem() {
+    let toml = r#"
+      [keybindings.Menu]
+      "<ctrl-blah>" = "Quit"
+      "<q>" = "Quitt"
+      "<j>" = "Quit"
+
+      [keybindings.Nope]
+      "<j>" = "Quit"
+
+      [styles.Data]
+      selected = "bold redd on blue"
+
+      [db.missing]
+      driver = "postgres"
+      host = "localhost"
+
+      [db.oracle]
+      driver = "oracle"
+      connection_string = "x"
+    "#;
+    assert_eq!(problems(toml), vec![
+      "db.missing: expected either `connection_string` or `host`, `port`, `database` and `username`",
+      "db.oracle: unknown driver `oracle`, expected one of postgres, mysql or sqlite",
+      "keybindings.menu.<ctrl-blah>: invalid key `<ctrl-blah>`: Unable to parse blah",
+      "keybindings.menu.<q>: unknown action `Quitt`",
+      "keybindings.nope: unknown focus `nope`",
+      "styles.data.selected: unknown color `redd` in style `bold redd on blue`",
+    ]);
+  }
+
+  #[test]
+  fn test_validate_default_config() {
+    assert_eq!(problems(CONFIG), Vec::<String>::new());
+  }
+
+  #[test]
+  fn test_interpolate() {
+    let lookup = |name: &str| {
+      match name {
+        "HOST" => Some("db.internal".to_string()),
+        "EMPTY" => Some(String::new()),
+        _ => None,
+      }
+    };
+    assert_eq!(interpolate("${HOST}:5432", &lookup), Ok("db.internal:5432".to_string()));
+    assert_eq!(interpolate("${PORT:-5432}", &lookup), Ok("5432".to_string()));
+    assert_eq!(interpolate("${EMPTY:-fallback}", &lookup), Ok("fallback".to_string()));
+    assert_eq!(interpolate("${EMPTY}", &lookup), Ok(String::new()));
+    assert_eq!(interpolate("no variables", &lookup), Ok("no variables".to_string()));
+    assert_eq!(interpolate("${USER_NAME}", &lookup), Err("environment variable `USER_NAME` is not set".to_string()));
+    assert_eq!(interpolate("${HOST", &lookup), Err("unterminated `${` in `${HOST`".to_string()));
+  }
+
+  #[test]
+  fn test_invalid_keybinding_does_not_panic() {
+    let result = toml::from_str::<Config>("[keybindings.Menu]\n\"<ctrl-blah>\" = \"Quit\"\n");
+    assert!(result.is_err());
+  }
+
   #[test]
   fn test_simple_keys() {
     assert_eq!(parse_key_event("a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()));
@@ -0,0 +1,898 @@
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{self, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  layout::{Constraint, Direction, Layout},
+  style::{Color, Style, Stylize},
+  text::{Line, Span},
+  widgets::{Block, Borders, Padding, Paragraph},
+  Frame,
+};
+use toml_edit::{value, DocumentMut, Item, Table};
+
+use crate::{
+  cli::{Cli, Driver},
+  components::menu::PinnedTables,
+  config::{expand_env, Config, ConnectionString, DatabaseConnection, StructuredConnection},
+  keyring::{delete_password, get_saved_password, move_password, save_password, Password},
+  session::{self, ConnectionRequest},
+  tui::{self, Tui},
+  utils::get_config_dir,
+};
+
+const CONFIG_FILE: &str = "rainfrog_config.toml";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+  Name,
+  Driver,
+  ConnectionString,
+  Host,
+  Port,
+  Database,
+  Username,
+  Password,
+  Default,
+}
+
+const FIELDS: [Field; 9] = [
+  Field::Name,
+  Field::Driver,
+  Field::ConnectionString,
+  Field::Host,
+  Field::Port,
+  Field::Database,
+  Field::Username,
+  Field::Password,
+  Field::Default,
+];
+
+impl Field {
+  fn label(&self) -> &'static str {
+    match self {
+      Field::Name => "name",
+      Field::Driver => "driver",
+      Field::ConnectionString => "connection string",
+      Field::Host => "host",
+      Field::Port => "port",
+      Field::Database => "database",
+      Field::Username => "username",
+      Field::Password => "password",
+      Field::Default => "default",
+    }
+  }
+}
+
+struct Form {
+  // the entry being edited, if any
+  original_name: Option<String>,
+  selected: usize,
+  name: String,
+  driver: Driver,
+  connection_string: String,
+  host: String,
+  port: String,
+  database: String,
+  username: String,
+  password: String,
+  default: bool,
+}
+
+impl Form {
+  fn new() -> Self {
+    Self {
+      original_name: None,
+      selected: 0,
+      name: String::new(),
+      driver: Driver::Postgres,
+      connection_string: String::new(),
+      host: "localhost".to_string(),
+      port: "5432".to_string(),
+      database: String::new(),
+      username: String::new(),
+      password: String::new(),
+      default: false,
+    }
+  }
+
+  fn from_args(args: &Cli) -> Self {
+    let mut form = Self::new();
+    if let Some(driver) = args.driver {
+      form.set_driver(driver);
+    }
+    form.host = args.host.clone().unwrap_or(form.host);
+    form.port = args.port.map(|p| p.to_string()).unwrap_or(form.port);
+    form.database = args.database.clone().unwrap_or_default();
+    form.username = args.user.clone().unwrap_or_default();
+    form.password = args.password.clone().unwrap_or_default();
+    if let (Driver::Sqlite, Some(database)) = (form.driver, &args.database) {
+      form.connection_string = format!("sqlite://{database}");
+    }
+    form
+  }
+
+  fn from_entry(name: &str, conn: &DatabaseConnection) -> Self {
+    let mut form = Self::new();
+    form.original_name = Some(name.to_string());
+    form.name = name.to_string();
+    form.driver = conn.driver;
+    form.default = conn.default;
+    match &conn.connection {
+      ConnectionString::Raw { connection_string } => {
+        form.connection_string = connection_string.clone();
+        form.host = String::new();
+        form.port = String::new();
+      },
+      ConnectionString::Structured { details } => {
+        form.host = details.host.clone();
+        form.port = details.port.to_string();
+        form.database = details.database.clone();
+        form.username = details.username.clone();
+      },
+    }
+    form
+  }
+
+  fn field(&self) -> Field {
+    FIELDS[self.selected]
+  }
+
+  fn value_mut(&mut self, field: Field) -> Option<&mut String> {
+    match field {
+      Field::Name => Some(&mut self.name),
+      Field::ConnectionString => Some(&mut self.connection_string),
+      Field::Host => Some(&mut self.host),
+      Field::Port => Some(&mut self.port),
+      Field::Database => Some(&mut self.database),
+      Field::Username => Some(&mut self.username),
+      Field::Password => Some(&mut self.password),
+      Field::Driver | Field::Default => None,
+    }
+  }
+
+  fn display_value(&self, field: Field) -> String {
+    match field {
+      Field::Name => self.name.clone(),
+      Field::Driver => format!("< {} >", format!("{:?}", self.driver).to_lowercase()),
+      Field::ConnectionString => self.connection_string.clone(),
+      Field::Host => self.host.clone(),
+      Field::Port => self.port.clone(),
+      Field::Database => self.database.clone(),
+      Field::Username => self.username.clone(),
+      Field::Password => "*".repeat(self.password.chars().count()),
+      Field::Default => if self.default { "[x]" } else { "[ ]" }.to_string(),
+    }
+  }
+
+  fn set_driver(&mut self, driver: Driver) {
+    // only swap the port if it is still the previous driver's default
+    if self.port == default_port(self.driver) {
+      self.port = default_port(driver).to_string();
+    }
+    self.driver = driver;
+  }
+
+  fn cycle(&mut self, forwards: bool) {
+    match self.field() {
+      Field::Driver => {
+        let drivers = [Driver::Postgres, Driver::Mysql, Driver::Sqlite];
+        let index = drivers.iter().position(|d| *d == self.driver).unwrap_or(0);
+        let next = if forwards { (index + 1) % drivers.len() } else { (index + drivers.len() - 1) % drivers.len() };
+        self.set_driver(drivers[next]);
+      },
+      Field::Default => self.default = !self.default,
+      _ => {},
+    }
+  }
+
+  fn is_raw(&self) -> bool {
+    !self.connection_string.trim().is_empty()
+  }
+
+  fn structured(&self) -> Result<StructuredConnection> {
+    let required = |value: &str, label: &str| {
+      if value.trim().is_empty() {
+        Err(eyre::Report::msg(format!("{label} is required")))
+      } else {
+        Ok(value.trim().to_string())
+      }
+    };
+    Ok(StructuredConnection {
+      host: required(&self.host, "host")?,
+      port: required(&self.port, "port")?.parse().map_err(|_| eyre::Report::msg("port must be a number"))?,
+      database: required(&self.database, "database")?,
+      username: required(&self.username, "username")?,
+    })
+  }
+
+  fn validate(&self, existing: &Config) -> Result<()> {
+    let name = self.name.trim();
+    if name.is_empty() {
+      return Err(eyre::Report::msg("name is required"));
+    }
+    if self.original_name.as_deref() != Some(name) && existing.db.contains_key(name) {
+      return Err(eyre::Report::msg(format!("a connection named {name} already exists")));
+    }
+    if self.is_raw() {
+      return Ok(());
+    }
+    if self.driver == Driver::Sqlite {
+      return Err(eyre::Report::msg("sqlite connections need a connection string"));
+    }
+    self.structured().map(|_| ())
+  }
+
+  fn to_request(&self, config: &Config) -> Result<ConnectionRequest> {
+    let name = self.name.trim().to_string();
+    let url = if self.is_raw() {
+      self.connection_string.trim().to_string()
+    } else {
+      let details = self.structured()?;
+      let password = if self.password.is_empty() {
+        get_saved_password(&name, &details.username)?.unwrap_or_else(|| Password::from(String::new()))
+      } else {
+        Password::from(self.password.clone())
+      };
+      details.connection_string(self.driver, password)?
+    };
+    // keep the parts of an existing entry that the form doesn't edit
+    let existing = self.original_name.as_ref().and_then(|n| config.db.get(n));
+    Ok(ConnectionRequest {
+      name,
+      driver: self.driver,
+      url: Some(url),
+      tunnel: existing.and_then(|c| c.tunnel.clone()),
+      color: existing.and_then(|c| c.color),
+    })
+  }
+}
+
+fn default_port(driver: Driver) -> &'static str {
+  match driver {
+    Driver::Postgres => "5432",
+    Driver::Mysql => "3306",
+    Driver::Sqlite => "",
+  }
+}
+
+enum Mode {
+  List,
+  Form(Form),
+  ConfirmDelete(String),
+  // the entry has no password in the keyring yet
+  Password { name: String, password: String, launch: bool },
+}
+
+enum Status {
+  Info(String),
+  Success(String),
+  Error(String),
+}
+
+/// Full-screen manager for the `[db.*]` entries of the config file,
+/// shown at startup instead of prompting on stdin.
+pub struct ConnectionManager {
+  config: Config,
+  names: Vec<String>,
+  selected: usize,
+  mode: Mode,
+  status: Option<Status>,
+}
+
+impl ConnectionManager {
+  pub fn new(config: Config) -> Self {
+    let mut manager = Self { config, names: vec![], selected: 0, mode: Mode::List, status: None };
+    manager.refresh_names();
+    if let Some(index) = manager.names.iter().position(|n| manager.config.db[n].default) {
+      manager.selected = index;
+    }
+    manager
+  }
+
+  // starts on an add form filled in from whatever was passed on the command line
+  pub fn with_args(config: Config, args: &Cli) -> Self {
+    let mut manager = Self::new(config);
+    manager.mode = Mode::Form(Form::from_args(args));
+    manager
+  }
+
+  /// Runs the manager until a connection is chosen and tested, or the user quits.
+  pub async fn run(mut self) -> Result<Option<ConnectionRequest>> {
+    let mut tui = Tui::new()?.mouse(Some(false));
+    tui.enter()?;
+    let result = loop {
+      tui.draw(|f| self.draw(f))?;
+      match tui.next().await {
+        Some(tui::Event::Key(key)) => {
+          match self.handle_key(key) {
+            Some(Outcome::Quit) => break None,
+            Some(Outcome::Test(request, launch)) => {
+              self.status = Some(Status::Info(format!("testing connection to {}...", request.name)));
+              tui.draw(|f| self.draw(f))?;
+              match session::test_connection(request.clone()).await {
+                Ok(latency) => {
+                  self.status =
+                    Some(Status::Success(format!("connected to {} in {}ms", request.name, latency.as_millis())));
+                  if launch {
+                    tui.draw(|f| self.draw(f))?;
+                    break Some(request);
+                  }
+                },
+                Err(e) => self.status = Some(Status::Error(format!("failed to connect to {}: {e}", request.name))),
+              }
+            },
+            None => {},
+          }
+        },
+        Some(tui::Event::Quit) | None => break None,
+        _ => {},
+      }
+    };
+    tui.exit()?;
+    Ok(result)
+  }
+
+  fn refresh_names(&mut self) {
+    self.names = self.config.db.keys().cloned().collect();
+    self.names.sort();
+    self.selected = self.selected.min(self.names.len().saturating_sub(1));
+  }
+
+  fn reload(&mut self) {
+    match Config::new() {
+      Ok(config) => {
+        self.config = config;
+        self.refresh_names();
+      },
+      Err(e) => self.status = Some(Status::Error(format!("failed to reload config: {e}"))),
+    }
+  }
+
+  fn selected_name(&self) -> Option<String> {
+    self.names.get(self.selected).cloned()
+  }
+
+  fn request_for(&mut self, name: &str, password: Option<String>, launch: bool) -> Option<Outcome> {
+    let conn = self.config.db.get(name)?.clone();
+    let url = match &conn.connection {
+      ConnectionString::Raw { connection_string } => Ok(connection_string.clone()),
+      ConnectionString::Structured { details } => {
+        let password = match password {
+          Some(password) => Password::from(password),
+          None => {
+            match get_saved_password(name, &details.username) {
+              Ok(Some(password)) => password,
+              Ok(None) => {
+                self.mode = Mode::Password { name: name.to_string(), password: String::new(), launch };
+                return None;
+              },
+              Err(e) => {
+                self.status = Some(Status::Error(e.to_string()));
+                return None;
+              },
+            }
+          },
+        };
+        details.connection_string(conn.driver, password)
+      },
+    };
+    match url {
+      Ok(url) => {
+        Some(Outcome::Test(
+          ConnectionRequest {
+            name: name.to_string(),
+            driver: conn.driver,
+            url: Some(url),
+            tunnel: conn.tunnel.clone(),
+            color: conn.color,
+          },
+          launch,
+        ))
+      },
+      Err(e) => {
+        self.status = Some(Status::Error(e.to_string()));
+        None
+      },
+    }
+  }
+
+  fn handle_key(&mut self, key: KeyEvent) -> Option<Outcome> {
+    if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('c') {
+      return Some(Outcome::Quit);
+    }
+    match std::mem::replace(&mut self.mode, Mode::List) {
+      Mode::List => self.handle_list_key(key),
+      Mode::Form(form) => self.handle_form_key(form, key),
+      Mode::ConfirmDelete(name) => {
+        if key.code == KeyCode::Char('y') {
+          match entry_file(&name).and_then(|path| delete_entry(&path, &name)) {
+            Ok(()) => {
+              self.status = Some(Status::Success(format!("deleted {name}")));
+              self.reload();
+            },
+            Err(e) => self.status = Some(Status::Error(e.to_string())),
+          }
+        }
+        None
+      },
+      Mode::Password { name, mut password, launch } => {
+        match key.code {
+          KeyCode::Enter => {
+            let save = key.modifiers.contains(KeyModifiers::ALT);
+            if save {
+              if let Some(ConnectionString::Structured { details }) =
+                self.config.db.get(&name).map(|c| c.connection.clone())
+              {
+                if let Err(e) = save_password(&name, &details.username, &password) {
+                  self.status = Some(Status::Error(e.to_string()));
+                }
+              }
+            }
+            return self.request_for(&name, Some(password), launch);
+          },
+          KeyCode::Esc => return None,
+          KeyCode::Backspace => {
+            password.pop();
+          },
+          KeyCode::Char(c) => password.push(c),
+          _ => {},
+        }
+        self.mode = Mode::Password { name, password, launch };
+        None
+      },
+    }
+  }
+
+  fn handle_list_key(&mut self, key: KeyEvent) -> Option<Outcome> {
+    match key.code {
+      KeyCode::Char('q') | KeyCode::Esc => return Some(Outcome::Quit),
+      KeyCode::Char('j') | KeyCode::Down => {
+        if !self.names.is_empty() {
+          self.selected = (self.selected + 1) % self.names.len();
+        }
+      },
+      KeyCode::Char('k') | KeyCode::Up => {
+        if !self.names.is_empty() {
+          self.selected = (self.selected + self.names.len() - 1) % self.names.len();
+        }
+      },
+      KeyCode::Char('a') => self.mode = Mode::Form(Form::new()),
+      KeyCode::Char('e') => {
+        if let Some(name) = self.selected_name() {
+          self.mode = Mode::Form(Form::from_entry(&name, &self.config.db[&name]));
+        }
+      },
+      KeyCode::Char('d') => {
+        if let Some(name) = self.selected_name() {
+          self.mode = Mode::ConfirmDelete(name);
+        }
+      },
+      KeyCode::Char('t') => {
+        if let Some(name) = self.selected_name() {
+          return self.request_for(&name, None, false);
+        }
+      },
+      KeyCode::Enter => {
+        if let Some(name) = self.selected_name() {
+          return self.request_for(&name, None, true);
+        }
+      },
+      _ => {},
+    }
+    None
+  }
+
+  fn handle_form_key(&mut self, mut form: Form, key: KeyEvent) -> Option<Outcome> {
+    let field = form.field();
+    match key.code {
+      KeyCode::Esc => return None,
+      // ctrl+o connects with what's in the form without saving it
+      KeyCode::Char(c @ ('t' | 'o')) if key.modifiers == KeyModifiers::CONTROL => {
+        let outcome = form.to_request(&self.config).map(|request| Outcome::Test(request, c == 'o'));
+        if let Err(e) = &outcome {
+          self.status = Some(Status::Error(e.to_string()));
+        }
+        self.mode = Mode::Form(form);
+        return outcome.ok();
+      },
+      KeyCode::Enter => {
+        let file = || form.original_name.as_deref().map_or_else(config_file, entry_file);
+        let saved = form.validate(&self.config).and_then(|_| file()).and_then(|path| {
+          // checked before saving, so an entry that can't lose its default doesn't leave two of them
+          let defaults = if form.default { other_defaults(&self.config, &form)? } else { vec![] };
+          save_entry(&path, &form, &self.config.config._data_dir)?;
+          defaults.iter().filter(|(_, file)| *file != path).try_for_each(|(name, file)| clear_default(file, name))
+        });
+        match saved {
+          Ok(()) => {
+            self.status = Some(Status::Success(format!("saved {}", form.name.trim())));
+            self.reload();
+            if let Some(index) = self.names.iter().position(|n| n == form.name.trim()) {
+              self.selected = index;
+            }
+            return None;
+          },
+          Err(e) => self.status = Some(Status::Error(e.to_string())),
+        }
+      },
+      KeyCode::Tab | KeyCode::Down => form.selected = (form.selected + 1) % FIELDS.len(),
+      KeyCode::BackTab | KeyCode::Up => form.selected = (form.selected + FIELDS.len() - 1) % FIELDS.len(),
+      KeyCode::Left => form.cycle(false),
+      KeyCode::Right | KeyCode::Char(' ') if form.value_mut(field).is_none() => form.cycle(true),
+      KeyCode::Backspace => {
+        if let Some(value) = form.value_mut(field) {
+          value.pop();
+        }
+      },
+      KeyCode::Char(c) => {
+        if let Some(value) = form.value_mut(field) {
+          value.push(c);
+        }
+      },
+      _ => {},
+    }
+    self.mode = Mode::Form(form);
+    None
+  }
+
+  fn draw(&self, f: &mut Frame) {
+    let block = Block::default()
+      .borders(Borders::ALL)
+      .border_style(Style::default().fg(Color::Green))
+      .title(Line::from(" rainfrog connections ").centered())
+      .padding(Padding::uniform(1));
+    let layout = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Fill(1), Constraint::Length(2), Constraint::Length(1)])
+      .split(block.inner(f.area()));
+    f.render_widget(block, f.area());
+
+    let (lines, hints) = match &self.mode {
+      Mode::List => {
+        (
+          self.list_lines(),
+          "[j|↓] down [k|↑] up [Enter] test and connect [t] test [a] add [e] edit [d] delete [q] quit",
+        )
+      },
+      Mode::Form(form) => {
+        (
+          FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+              let line = Line::from(format!("{:>18}: {}", field.label(), form.display_value(*field)));
+              if i == form.selected {
+                line.reversed()
+              } else {
+                line
+              }
+            })
+            .chain([
+              Line::default(),
+              Line::from("leave the connection string empty to use host, port, database and username.").dim(),
+              Line::from("a password entered here is saved in the keyring, never in the config file.").dim(),
+            ])
+            .collect(),
+          "[Tab|↓] next field [Shift+Tab|↑] previous field [←|→] change [Enter] save [Ctrl+t] test [Ctrl+o] connect without saving [Esc] cancel",
+        )
+      },
+      Mode::ConfirmDelete(name) => {
+        (vec![Line::from(format!("Delete the connection {name}? This can't be undone."))], "[y] delete [n|Esc] cancel")
+      },
+      Mode::Password { name, password, .. } => {
+        (
+          vec![Line::from(format!("Password for {name}:")), Line::from("*".repeat(password.chars().count()))],
+          "[Enter] connect [Alt+Enter] connect and save to keyring [Esc] cancel",
+        )
+      },
+    };
+    f.render_widget(Paragraph::new(lines), layout[0]);
+    if let Some(status) = &self.status {
+      let status = match status {
+        Status::Info(message) => Line::from(message.as_str()).fg(Color::Yellow),
+        Status::Success(message) => Line::from(format!("✔ {message}")).fg(Color::Green),
+        Status::Error(message) => Line::from(format!("✖ {message}")).fg(Color::Red),
+      };
+      f.render_widget(Paragraph::new(status), layout[1]);
+    }
+    f.render_widget(Paragraph::new(Line::from(hints).centered()).fg(Color::Blue), layout[2]);
+  }
+
+  fn list_lines(&self) -> Vec<Line<'static>> {
+    if self.names.is_empty() {
+      return vec![Line::from("No connections yet. Press [a] to add one.")];
+    }
+    self
+      .names
+      .iter()
+      .enumerate()
+      .map(|(i, name)| {
+        let conn = &self.config.db[name];
+        let target = match &conn.connection {
+          ConnectionString::Raw { connection_string } => crate::cli::redact_url(connection_string),
+          ConnectionString::Structured { details } => {
+            format!("{}@{}:{}/{}", details.username, details.host, details.port, details.database)
+          },
+        };
+        let line = Line::from(vec![
+          Span::styled(format!("{name:<24}"), Style::default().fg(conn.color.unwrap_or(Color::Reset))),
+          Span::raw(format!("{:<10}", format!("{:?}", conn.driver).to_lowercase())),
+          Span::raw(target),
+          Span::raw(if conn.default { " (default)" } else { "" }),
+        ]);
+        if i == self.selected {
+          line.reversed()
+        } else {
+          line
+        }
+      })
+      .collect()
+  }
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Outcome {
+  Quit,
+  // (request, whether to launch it once the test succeeds)
+  Test(ConnectionRequest, bool),
+}
+
+fn config_file() -> Result<PathBuf> {
+  let dir = get_config_dir();
+  let path = dir.join(CONFIG_FILE);
+  if !path.exists() {
+    let other = ["rainfrog_config.json5", "rainfrog_config.json", "rainfrog_config.yaml", "rainfrog_config.ini"]
+      .into_iter()
+      .find(|f| dir.join(f).exists());
+    if let Some(other) = other {
+      return Err(eyre::Report::msg(format!("connections can only be saved to {CONFIG_FILE}, but found {other}")));
+    }
+  }
+  Ok(path)
+}
+
+// existing entries are edited in the file that defines them, which may be one pulled in by `include`
+fn entry_file(name: &str) -> Result<PathBuf> {
+  match Config::included_entry_file(name) {
+    Some(path) if path.extension().is_some_and(|ext| ext == "toml") => Ok(path),
+    Some(path) => {
+      Err(eyre::Report::msg(format!("{name} is defined in {}, which can only be edited by hand", path.display())))
+    },
+    None => config_file(),
+  }
+}
+
+// the other entries marked as the default, with the files they're defined in
+fn other_defaults(config: &Config, form: &Form) -> Result<Vec<(String, PathBuf)>> {
+  config
+    .db
+    .iter()
+    .filter(|(name, db)| db.default && Some(name.as_str()) != form.original_name.as_deref())
+    .map(|(name, _)| entry_file(name).map(|path| (name.clone(), path)))
+    .collect()
+}
+
+fn clear_default(path: &PathBuf, name: &str) -> Result<()> {
+  let mut document = read_document(path)?;
+  if let Some(entry) = document.get_mut("db").and_then(|db| db.get_mut(name)).and_then(Item::as_table_like_mut) {
+    entry.remove("default");
+  }
+  write_document(path, &document)
+}
+
+fn read_document(path: &PathBuf) -> Result<DocumentMut> {
+  if path.exists() {
+    Ok(fs::read_to_string(path)?.parse::<DocumentMut>()?)
+  } else {
+    Ok(DocumentMut::new())
+  }
+}
+
+fn write_document(path: &PathBuf, document: &DocumentMut) -> Result<()> {
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir)?;
+  }
+  fs::write(path, document.to_string())?;
+  Ok(())
+}
+
+// edits the entry in place, so keys the form doesn't know about (e.g. tunnel) are kept
+fn save_entry(path: &PathBuf, form: &Form, data_dir: &Path) -> Result<()> {
+  let mut document = read_document(path)?;
+  let db = document.entry("db").or_insert_with(|| Item::Table(Table::new()));
+  let db = db.as_table_like_mut().ok_or_else(|| eyre::Report::msg("[db] in the config file is not a table"))?;
+  let name = form.name.trim();
+  let mut entry = match &form.original_name {
+    Some(original) => db.remove(original).unwrap_or_else(|| Item::Table(Table::new())),
+    None => Item::Table(Table::new()),
+  };
+  let table = entry.as_table_like_mut().ok_or_else(|| eyre::Report::msg(format!("db.{name} is not a table")))?;
+  table.insert("driver", value(format!("{:?}", form.driver).to_lowercase()));
+  let mut previous = HashMap::new();
+  for key in ["connection_string", "host", "port", "database", "username"] {
+    if let Some(raw) = table.remove(key).and_then(|item| item.as_str().map(str::to_string)) {
+      previous.insert(key, raw);
+    }
+  }
+  // values that still match what their `${VAR}` expands to are written back unexpanded
+  let field = |key: &str, new: Item| {
+    let text = new.as_str().map(str::to_string).or_else(|| new.as_integer().map(|i| i.to_string()));
+    match (previous.get(key), text) {
+      (Some(raw), Some(text)) if expand_env(raw).is_ok_and(|expanded| expanded == text) => value(raw.clone()),
+      _ => new,
+    }
+  };
+  let mut usernames = None;
+  if form.is_raw() {
+    table.insert("connection_string", field("connection_string", value(form.connection_string.trim())));
+  } else {
+    let details = form.structured()?;
+    table.insert("host", field("host", value(details.host.clone())));
+    table.insert("port", field("port", value(details.port as i64)));
+    table.insert("database", field("database", value(details.database.clone())));
+    table.insert("username", field("username", value(details.username.clone())));
+    let old_username = previous.get("username").and_then(|raw| expand_env(raw).ok());
+    let old_name = form.original_name.clone().unwrap_or_else(|| name.to_string());
+    usernames = Some((old_name, old_username.unwrap_or_else(|| details.username.clone()), details.username));
+  }
+  if form.default {
+    table.insert("default", value(true));
+    // only one entry can be the default
+    for (_, other) in db.iter_mut() {
+      if let Some(other) = other.as_table_like_mut() {
+        other.remove("default");
+      }
+    }
+  } else {
+    table.remove("default");
+  }
+  db.insert(name, entry);
+  write_document(path, &document)?;
+
+  // the keyring is keyed by name and username, so a saved password follows the entry when either
+  // changes. it's only updated once the entry is written, so it never points at a missing entry
+  if let Some((old_name, old_username, username)) = usernames {
+    let old = (old_name.as_str(), old_username.as_str());
+    if !form.password.is_empty() {
+      save_password(name, &username, &form.password)?;
+      if old != (name, username.as_str()) {
+        delete_password(old.0, old.1)?;
+      }
+    } else {
+      move_password(old, (name, &username))?;
+    }
+  }
+  // pinned tables are kept by connection name as well
+  if let Some(original) = form.original_name.as_deref().filter(|original| *original != name) {
+    let mut pinned = PinnedTables::load(data_dir)?;
+    pinned.rename(original, name);
+    pinned.save(data_dir)?;
+  }
+  Ok(())
+}
+
+fn delete_entry(path: &PathBuf, name: &str) -> Result<()> {
+  let mut document = read_document(path)?;
+  let removed = document.get_mut("db").and_then(|db| db.as_table_like_mut()).and_then(|db| db.remove(name));
+  let Some(removed) = removed else {
+    return Err(eyre::Report::msg(format!("{name} is not defined in {}", path.display())));
+  };
+  write_document(path, &document)?;
+  // otherwise a later entry with the same name and username would pick up the old password
+  if let Some(username) = removed.get("username").and_then(|item| item.as_str()) {
+    delete_password(name, &expand_env(username).unwrap_or_else(|_| username.to_string()))?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_save_entry_keeps_unrelated_keys() {
+    let dir = std::env::temp_dir().join(format!("rainfrog-connection-manager-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join(CONFIG_FILE),
+      "# my connections\n[db.prod]\ndriver = \"postgres\"\nconnection_string = \"postgres://prod\"\ntunnel = { host = \"bastion\", user = \"me\", key_path = \"~/.ssh/id\" }\n",
+    )
+    .unwrap();
+
+    let mut form = Form::new();
+    form.original_name = Some("prod".to_string());
+    form.name = "production".to_string();
+    form.connection_string = "postgres://prod:5433".to_string();
+    let mut pinned = PinnedTables::default();
+    pinned.set("prod", Some("app"), vec![("public".to_string(), "users".to_string())]);
+    pinned.save(&dir).unwrap();
+    save_entry(&dir.join(CONFIG_FILE), &form, &dir).unwrap();
+
+    let document = read_document(&dir.join(CONFIG_FILE)).unwrap();
+    assert!(document.to_string().starts_with("# my connections"));
+    assert!(document["db"].get("prod").is_none());
+    assert_eq!(document["db"]["production"]["connection_string"].as_str(), Some("postgres://prod:5433"));
+    assert_eq!(document["db"]["production"]["tunnel"]["host"].as_str(), Some("bastion"));
+    let pinned = PinnedTables::load(&dir).unwrap();
+    assert!(pinned.get("prod", Some("app")).is_empty());
+    assert_eq!(pinned.get("production", Some("app")), vec![("public".to_string(), "users".to_string())]);
+
+    delete_entry(&dir.join(CONFIG_FILE), "production").unwrap();
+    let document = read_document(&dir.join(CONFIG_FILE)).unwrap();
+    assert!(document.get("db").and_then(|db| db.get("production")).is_none());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_save_entry_keeps_a_single_default() {
+    let dir = std::env::temp_dir().join(format!("rainfrog-connection-manager-default-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join(CONFIG_FILE),
+      "[db.prod]\ndriver = \"postgres\"\nconnection_string = \"postgres://prod\"\ndefault = true\n",
+    )
+    .unwrap();
+
+    let mut form = Form::new();
+    form.name = "dev".to_string();
+    form.connection_string = "postgres://dev".to_string();
+    form.default = true;
+    save_entry(&dir.join(CONFIG_FILE), &form, &dir).unwrap();
+
+    let document = read_document(&dir.join(CONFIG_FILE)).unwrap();
+    assert_eq!(document.to_string().matches("default = true").count(), 1);
+    assert_eq!(document["db"]["dev"]["default"].as_bool(), Some(true));
+    assert!(document["db"]["prod"].get("default").is_none());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_form_connects_without_saving() {
+    let mut manager = ConnectionManager::new(Config::default());
+    let mut form = Form::new();
+    form.name = "scratch".to_string();
+    form.connection_string = "postgres://localhost/scratch".to_string();
+    let outcome = manager.handle_form_key(form, KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL));
+    match outcome {
+      Some(Outcome::Test(request, launch)) => {
+        assert!(launch);
+        assert_eq!(request.name, "scratch");
+        assert_eq!(request.url.as_deref(), Some("postgres://localhost/scratch"));
+      },
+      _ => panic!("expected a connect outcome"),
+    }
+    assert!(matches!(manager.mode, Mode::Form(_)));
+  }
+
+  #[test]
+  fn test_save_entry_keeps_env_variables() {
+    let dir = std::env::temp_dir().join(format!("rainfrog-connection-manager-env-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join(CONFIG_FILE),
+      "[db.dev]\ndriver = \"postgres\"\nhost = \"${RAINFROG_TEST_UNSET_HOST:-localhost}\"\nport = \"${RAINFROG_TEST_UNSET_PORT:-5432}\"\ndatabase = \"app\"\nusername = \"me\"\n",
+    )
+    .unwrap();
+
+    let mut form = Form::new();
+    form.original_name = Some("dev".to_string());
+    form.name = "dev".to_string();
+    form.host = "localhost".to_string();
+    form.port = "5433".to_string();
+    form.database = "app".to_string();
+    form.username = "me".to_string();
+    save_entry(&dir.join(CONFIG_FILE), &form, &dir).unwrap();
+
+    let document = read_document(&dir.join(CONFIG_FILE)).unwrap();
+    assert_eq!(document["db"]["dev"]["host"].as_str(), Some("${RAINFROG_TEST_UNSET_HOST:-localhost}"));
+    assert_eq!(document["db"]["dev"]["port"].as_integer(), Some(5433));
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
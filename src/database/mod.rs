@@ -1,6 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 use futures::stream::{BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
 use sqlparser::{
   ast::Statement,
   dialect::{Dialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect},
@@ -14,8 +15,12 @@ use sqlx::{
   sqlite::{Sqlite, SqliteColumn, SqliteQueryResult, SqliteRow},
   Column, Connection, Database, Either, Error, Executor, Pool, Row, Transaction,
 };
+use strum::{Display, EnumIter};
 
-use crate::cli::Cli;
+use crate::{
+  action::{FilterOperator, RowFilter, RowsPreview, TableAction},
+  cli::Cli,
+};
 
 mod mysql;
 mod postgresql;
@@ -27,16 +32,18 @@ pub struct Header {
   pub type_name: String,
 }
 
+#[derive(Debug, Clone)]
 pub struct Value {
   pub parse_error: bool,
   pub is_null: bool,
   pub string: String,
 }
 
+// rows are kept as strings, except where nulls have to be told apart from text (see `row_to_values`)
 #[derive(Debug, Clone)]
-pub struct Rows {
+pub struct Rows<T = String> {
   pub headers: Headers,
-  pub rows: Vec<Vec<String>>,
+  pub rows: Vec<Vec<T>>,
   pub rows_affected: Option<u64>,
 }
 pub type Headers = Vec<Header>;
@@ -50,17 +57,98 @@ pub enum ExecutionType {
   Normal,
 }
 
+// the kinds of objects listed under each schema in the menu, in the order they're shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Display, EnumIter)]
+pub enum ObjectKind {
+  Table,
+  View,
+  MaterializedView,
+  Function,
+  Sequence,
+  Type,
+  Trigger,
+  Extension,
+}
+
+impl ObjectKind {
+  // parses the kind column returned by `preview_schema_objects_query`
+  pub fn from_query(kind: &str) -> Option<Self> {
+    match kind.to_lowercase().as_str() {
+      "table" => Some(ObjectKind::Table),
+      "view" => Some(ObjectKind::View),
+      "materialized view" => Some(ObjectKind::MaterializedView),
+      "function" | "procedure" => Some(ObjectKind::Function),
+      "sequence" => Some(ObjectKind::Sequence),
+      "type" => Some(ObjectKind::Type),
+      "trigger" => Some(ObjectKind::Trigger),
+      "extension" => Some(ObjectKind::Extension),
+      _ => None,
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      ObjectKind::Table => "tables",
+      ObjectKind::View => "views",
+      ObjectKind::MaterializedView => "materialized views",
+      ObjectKind::Function => "functions",
+      ObjectKind::Sequence => "sequences",
+      ObjectKind::Type => "types",
+      ObjectKind::Trigger => "triggers",
+      ObjectKind::Extension => "extensions",
+    }
+  }
+
+  // whether the object can be queried like a table
+  pub fn has_rows(&self) -> bool {
+    matches!(self, ObjectKind::Table | ObjectKind::View | ObjectKind::MaterializedView)
+  }
+}
+
 pub trait HasRowsAffected {
   fn rows_affected(&self) -> u64;
 }
 
 pub trait DatabaseQueries {
+  // the databases on the server, as (name, current) rows
+  fn preview_databases_query() -> String;
   fn preview_tables_query() -> String;
-  fn preview_rows_query(schema: &str, table: &str) -> String;
+  // (schema, table, estimated rows, size in bytes) rows, where either estimate may be null
+  fn preview_table_sizes_query() -> String;
+  // every object other than tables, as (schema, kind, name) rows, optionally followed by the table
+  // a trigger is on, for databases where trigger names are only unique per table
+  fn preview_schema_objects_query() -> String;
+  // `table` is the table a trigger is on, when `preview_schema_objects_query` returns it
+  fn preview_definition_query(kind: ObjectKind, schema: &str, name: &str, table: Option<&str>) -> String;
+  fn preview_dependencies_query(kind: ObjectKind, schema: &str, name: &str, table: Option<&str>) -> String;
+  // at most `limit` rows, ordered by the primary key when there is one
+  fn preview_rows_query(schema: &str, table: &str, preview: &RowsPreview, limit: usize) -> String;
   fn preview_columns_query(schema: &str, table: &str) -> String;
+  // the columns listed under a table in the menu, as (name, type, nullable, default, keys) rows,
+  // where nullable is `YES` or `NO` and keys is a comma separated list of `PK` and `FK`
+  fn preview_column_details_query(schema: &str, table: &str) -> String;
+  // the columns of every table, as (schema, table, column, type) rows, for searching across schemas
+  fn preview_all_columns_query() -> String;
+  fn preview_distinct_values_query(schema: &str, table: &str, column: &str) -> String;
   fn preview_constraints_query(schema: &str, table: &str) -> String;
   fn preview_indexes_query(schema: &str, table: &str) -> String;
   fn preview_policies_query(schema: &str, table: &str) -> String;
+  fn preview_triggers_query(schema: &str, table: &str) -> String;
+  fn preview_grants_query(schema: &str, table: &str) -> String;
+  fn preview_statistics_query(schema: &str, table: &str) -> String;
+  fn preview_partitions_query(schema: &str, table: &str) -> String;
+  // tables with a foreign key to this one
+  fn preview_referencing_tables_query(schema: &str, table: &str) -> String;
+  // the foreign keys from and to this table, as (constraint, schema, table, column, referenced
+  // schema, referenced table, referenced column) rows, in the order of each key's columns
+  fn preview_foreign_keys_query(schema: &str, table: &str) -> String;
+  // the foreign keys between the tables of a schema, as rows like preview_foreign_keys_query's
+  fn preview_schema_foreign_keys_query(schema: &str) -> String;
+  // the actions listed for a table in the menu
+  fn table_actions() -> Vec<TableAction>;
+  // the statement taking the action, or None when the database doesn't support it or it can't be
+  // taken, like renaming to an empty name
+  fn table_action_query(action: &TableAction, schema: &str, table: &str) -> Option<String>;
 }
 
 pub trait ValueParser: Database {
@@ -69,12 +157,43 @@ pub trait ValueParser: Database {
 
 pub trait BuildConnectionOptions: Database {
   fn build_connection_opts(args: Cli) -> color_eyre::eyre::Result<<Self::Connection as Connection>::Options>;
+  // the host and port the database listens on, as seen from the ssh tunnel's bastion
+  fn tunnel_target(opts: &<Self::Connection as Connection>::Options) -> color_eyre::eyre::Result<(String, u16)>;
+  fn through_tunnel(
+    opts: <Self::Connection as Connection>::Options,
+    local_addr: SocketAddr,
+  ) -> <Self::Connection as Connection>::Options;
+  // the options for another database on the same server, or None when the driver can't switch
+  fn with_database(
+    opts: <Self::Connection as Connection>::Options,
+    database: &str,
+  ) -> Option<<Self::Connection as Connection>::Options>;
+  // the database the options connect to, or None when the driver has no notion of one
+  fn database_name(opts: &<Self::Connection as Connection>::Options) -> Option<String>;
 }
 
 pub async fn init_pool<DB: Database>(opts: <DB::Connection as Connection>::Options) -> Result<Pool<DB>, Error> {
   PoolOptions::new().max_connections(3).connect_with(opts).await
 }
 
+// errors that mean the database could not be reached, as opposed to a bad query
+pub fn is_connection_error(e: &DbError) -> bool {
+  matches!(
+    e,
+    Either::Left(Error::Io(_) | Error::Tls(_) | Error::PoolTimedOut | Error::PoolClosed | Error::WorkerCrashed)
+  )
+}
+
+pub async fn ping<DB>(pool: &Pool<DB>) -> Result<std::time::Duration, Error>
+where
+  DB: Database,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  let start = std::time::Instant::now();
+  sqlx::raw_sql("select 1").execute(pool).await?;
+  Ok(start.elapsed())
+}
+
 // since it's possible for raw_sql to execute multiple queries in a single string,
 // we only execute the first one and then drop the rest.
 pub async fn query<DB>(query: String, dialect: &(dyn Dialect + Sync), pool: &Pool<DB>) -> Result<Rows, DbError>
@@ -95,8 +214,37 @@ where
 
 #[allow(clippy::type_complexity)]
 pub async fn query_stream<DB>(
-  mut stream: BoxStream<'_, Result<Either<DB::QueryResult, DB::Row>, Error>>,
+  stream: BoxStream<'_, Result<Either<DB::QueryResult, DB::Row>, Error>>,
 ) -> Result<Rows, DbError>
+where
+  DB: Database + ValueParser,
+  DB::QueryResult: HasRowsAffected,
+{
+  collect_rows::<DB, _>(stream, row_to_vec::<DB>).await
+}
+
+// like `query`, but keeps nulls apart from text, for results shown in the data pane
+pub async fn query_values<DB>(
+  query: String,
+  dialect: &(dyn Dialect + Sync),
+  pool: &Pool<DB>,
+) -> Result<Rows<Value>, DbError>
+where
+  DB: Database + ValueParser,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  let (first_query, _) = get_first_query(query, dialect)?;
+  let stream = sqlx::raw_sql(&first_query).fetch_many(pool);
+  collect_rows::<DB, _>(stream, row_to_values::<DB>).await
+}
+
+// collects the results of a query, turning each row into values with `to_row`
+#[allow(clippy::type_complexity)]
+pub async fn collect_rows<DB, T>(
+  mut stream: BoxStream<'_, Result<Either<DB::QueryResult, DB::Row>, Error>>,
+  to_row: impl Fn(&DB::Row) -> Vec<T>,
+) -> Result<Rows<T>, DbError>
 where
   DB: Database + ValueParser,
   DB::QueryResult: HasRowsAffected,
@@ -113,7 +261,7 @@ where
       },
       Ok(Either::Right(row)) => {
         // For SELECT queries
-        query_rows.push(row_to_vec::<DB>(&row));
+        query_rows.push(to_row(&row));
         if headers.is_empty() {
           headers = get_headers::<DB>(&row);
         }
@@ -128,7 +276,7 @@ pub async fn query_with_tx<'a, DB>(
   mut tx: Transaction<'static, DB>,
   dialect: &(dyn Dialect + Sync),
   query: String,
-) -> (Result<Either<u64, Rows>, DbError>, Transaction<'static, DB>)
+) -> (Result<Either<u64, Rows<Value>>, DbError>, Transaction<'static, DB>)
 where
   DB: Database + ValueParser,
   DB::QueryResult: HasRowsAffected,
@@ -141,7 +289,7 @@ where
       match statement_type {
         Statement::Explain { .. } => {
           let stream = sqlx::raw_sql(&first_query).fetch_many(&mut *tx);
-          let result = query_stream::<DB>(stream).await;
+          let result = collect_rows::<DB, _>(stream, row_to_values::<DB>).await;
           match result {
             Ok(result) => (Ok(Either::Right(result)), tx),
             Err(e) => (Err(e), tx),
@@ -160,6 +308,142 @@ where
   }
 }
 
+pub fn quote_identifier(quote: char, name: &str) -> String {
+  format!("{quote}{}{quote}", name.replace(quote, &format!("{quote}{quote}")))
+}
+
+pub fn quote_literal(value: &str) -> String {
+  format!("'{}'", value.replace('\'', "''"))
+}
+
+// mysql reads backslashes in string literals as escapes
+pub fn quote_mysql_literal(value: &str) -> String {
+  quote_literal(&value.replace('\\', "\\\\"))
+}
+
+// splits the values of an `in` filter on commas, except for those inside single quotes, which
+// are taken off along with the spaces around each value
+fn split_values(list: &str) -> Vec<String> {
+  let mut values = vec![];
+  let (mut value, mut quoted, mut in_quotes) = (String::new(), false, false);
+  let mut chars = list.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '\'' if in_quotes && chars.peek() == Some(&'\'') => {
+        chars.next();
+        value.push(c);
+      },
+      '\'' if in_quotes => in_quotes = false,
+      '\'' if value.trim().is_empty() => {
+        (value, quoted, in_quotes) = (String::new(), true, true);
+      },
+      ',' if !in_quotes => {
+        values.push(if quoted { std::mem::take(&mut value) } else { std::mem::take(&mut value).trim().to_owned() });
+        quoted = false;
+      },
+      c if quoted && !in_quotes && c.is_whitespace() => {},
+      c => value.push(c),
+    }
+  }
+  values.push(if quoted { value } else { value.trim().to_owned() });
+  values
+}
+
+pub fn filter_condition(quote: char, filter: &RowFilter) -> String {
+  let column = quote_identifier(quote, &filter.column);
+  let literal = |value: &str| if quote == '`' { quote_mysql_literal(value) } else { quote_literal(value) };
+  match filter.operator {
+    FilterOperator::IsNull => format!("{} is null", column),
+    FilterOperator::In => {
+      let values: Vec<String> = split_values(&filter.value).iter().map(|v| literal(v)).collect();
+      format!("{} in ({})", column, values.join(", "))
+    },
+    operator => format!("{} {} {}", column, operator.sql(), literal(&filter.value)),
+  }
+}
+
+// whether values of a column type are shown the way they'd be written as literals, so the next
+// rows can be found by comparing the key with the last row shown. timestamps, floats, uuids and
+// binary values can be shown differently than they compare
+pub fn keyset_type(type_name: &str) -> bool {
+  let type_name = type_name.to_uppercase();
+  matches!(
+    type_name.trim_end_matches(" UNSIGNED"),
+    "INT2"
+      | "INT4"
+      | "INT8"
+      | "TINYINT"
+      | "SMALLINT"
+      | "MEDIUMINT"
+      | "INT"
+      | "INTEGER"
+      | "BIGINT"
+      | "TEXT"
+      | "TINYTEXT"
+      | "MEDIUMTEXT"
+      | "LONGTEXT"
+      | "VARCHAR"
+      | "CHAR"
+      | "BPCHAR"
+      | "NAME"
+      | "CITEXT"
+  )
+}
+
+// the clauses after `select * from <table>` of a rows preview, with identifiers quoted by `quote`.
+// the next rows of a table with a primary key are the ones after the last row shown (keyset
+// paging), and the rest are skipped over (offset paging)
+pub fn preview_rows_clauses(quote: char, preview: &RowsPreview, limit: usize) -> String {
+  let columns: Vec<String> = preview.primary_key.iter().map(|c| quote_identifier(quote, c)).collect();
+  let keyset = !columns.is_empty() && preview.after.len() == columns.len();
+  let mut conditions: Vec<String> = preview.filters.iter().map(|filter| filter_condition(quote, filter)).collect();
+  if keyset {
+    let values: Vec<String> =
+      preview.after.iter().map(|v| if quote == '`' { quote_mysql_literal(v) } else { quote_literal(v) }).collect();
+    conditions.push(format!(
+      "({}) {} ({})",
+      columns.join(", "),
+      if preview.descending { "<" } else { ">" },
+      values.join(", ")
+    ));
+  }
+  let mut clauses = String::new();
+  if !conditions.is_empty() {
+    clauses.push_str(&format!(" where {}", conditions.join(" and ")));
+  }
+  if !columns.is_empty() {
+    let direction = if preview.descending { " desc" } else { "" };
+    let order: Vec<String> = columns.iter().map(|c| format!("{c}{direction}")).collect();
+    clauses.push_str(&format!(" order by {}", order.join(", ")));
+  }
+  clauses.push_str(&format!(" limit {}", limit));
+  if !keyset && preview.offset > 0 {
+    clauses.push_str(&format!(" offset {}", preview.offset));
+  }
+  clauses
+}
+
+// maintenance statements that sqlparser can't parse, like `vacuum`, which are run as written.
+// returns the statement without its trailing semicolon
+pub fn maintenance_statement(query: &str) -> Option<String> {
+  let query = query.trim().trim_end_matches(';').trim_end();
+  let keyword = query.split_whitespace().next()?.to_lowercase();
+  if !matches!(keyword.as_str(), "vacuum" | "reindex" | "optimize") || query.contains(';') {
+    return None;
+  }
+  Some(query.to_owned())
+}
+
+pub async fn maintenance_query<DB>(query: String, pool: &Pool<DB>) -> Result<Rows<Value>, DbError>
+where
+  DB: Database + ValueParser,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  let stream = sqlx::raw_sql(&query).fetch_many(pool);
+  collect_rows::<DB, _>(stream, row_to_values::<DB>).await
+}
+
 pub fn get_first_query(query: String, dialect: &dyn Dialect) -> Result<(String, Statement), DbError> {
   let ast = Parser::parse_sql(dialect, &query);
   match ast {
@@ -183,6 +467,34 @@ pub fn statement_type_string(statement: &Statement) -> String {
     .to_string()
 }
 
+// whether the statement can change what's listed in the menu
+pub fn is_ddl(statement: &Statement) -> bool {
+  matches!(
+    statement,
+    Statement::CreateTable(_)
+      | Statement::CreateView { .. }
+      | Statement::CreateIndex(_)
+      | Statement::CreateSchema { .. }
+      | Statement::CreateDatabase { .. }
+      | Statement::CreateFunction(_)
+      | Statement::CreateProcedure { .. }
+      | Statement::CreateTrigger { .. }
+      | Statement::CreateSequence { .. }
+      | Statement::CreateType { .. }
+      | Statement::CreateExtension { .. }
+      | Statement::CreateVirtualTable { .. }
+      | Statement::Drop { .. }
+      | Statement::DropFunction { .. }
+      | Statement::DropProcedure { .. }
+      | Statement::DropTrigger { .. }
+      | Statement::DropExtension { .. }
+      | Statement::AlterTable { .. }
+      | Statement::AlterView { .. }
+      | Statement::AlterIndex { .. }
+      | Statement::RenameTable(_)
+  )
+}
+
 pub fn get_execution_type(statement: Statement, confirmed: bool) -> ExecutionType {
   if confirmed {
     return ExecutionType::Normal;
@@ -193,6 +505,7 @@ pub fn get_execution_type(statement: Statement, confirmed: bool) -> ExecutionTyp
     | Statement::AlterRole { .. }
     | Statement::AlterTable { .. }
     | Statement::Drop { .. }
+    | Statement::RenameTable(_)
     | Statement::Truncate { .. } => ExecutionType::Confirm,
     Statement::Delete(_) | Statement::Update { .. } => ExecutionType::Transaction,
     Statement::Explain { statement, analyze, .. }
@@ -252,7 +565,143 @@ pub fn vec_to_string<T: std::string::ToString>(vec: Vec<T>) -> String {
 }
 
 pub fn row_to_vec<DB: Database + ValueParser>(row: &DB::Row) -> Vec<String> {
-  row.columns().iter().map(|col| DB::parse_value(row, col).unwrap().string).collect()
+  row_to_values::<DB>(row).into_iter().map(|value| value.string).collect()
+}
+
+pub fn row_to_values<DB: Database + ValueParser>(row: &DB::Row) -> Vec<Value> {
+  row.columns().iter().map(|col| DB::parse_value(row, col).unwrap()).collect()
+}
+
+// the sql returned by `preview_definition_query`. mysql's `show create` statements return it
+// next to other columns, in one named after the kind of object
+pub fn definition_text(rows: &Rows) -> Option<String> {
+  let column = rows
+    .headers
+    .iter()
+    .position(|h| {
+      let name = h.name.to_lowercase();
+      name.ends_with("definition") || name.starts_with("create ") || name == "sql original statement"
+    })
+    .unwrap_or(rows.headers.len().saturating_sub(1));
+  let text = rows.rows.iter().filter_map(|row| row.get(column).cloned()).collect::<Vec<_>>().join("\n");
+  if text.is_empty() {
+    None
+  } else {
+    Some(text)
+  }
+}
+
+// a foreign key from columns of one table to the columns they reference in another
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForeignKey {
+  pub name: String,
+  pub schema: String,
+  pub table: String,
+  pub columns: Vec<String>,
+  pub referenced_schema: String,
+  pub referenced_table: String,
+  pub referenced_columns: Vec<String>,
+}
+
+impl ForeignKey {
+  // groups the rows of `preview_foreign_keys_query` into keys
+  pub fn from_rows(rows: &Rows) -> Vec<Self> {
+    let mut keys: Vec<Self> = vec![];
+    for row in &rows.rows {
+      let [name, schema, table, column, referenced_schema, referenced_table, referenced_column] = row.as_slice() else {
+        continue;
+      };
+      match keys.iter_mut().find(|key| key.name == *name && key.schema == *schema && key.table == *table) {
+        Some(key) => {
+          key.columns.push(column.clone());
+          key.referenced_columns.push(referenced_column.clone());
+        },
+        None => {
+          keys.push(Self {
+            name: name.clone(),
+            schema: schema.clone(),
+            table: table.clone(),
+            columns: vec![column.clone()],
+            referenced_schema: referenced_schema.clone(),
+            referenced_table: referenced_table.clone(),
+            referenced_columns: vec![referenced_column.clone()],
+          })
+        },
+      }
+    }
+    keys
+  }
+
+  // the table on the other side of the key, with the filters on it for the rows related to `row`:
+  // the row it references, or with `reverse` the rows referencing it. none when one of the key's
+  // values is missing from the row or null
+  pub fn related_rows(
+    &self,
+    row: &[(String, Option<String>)],
+    reverse: bool,
+  ) -> Option<(String, String, Vec<RowFilter>)> {
+    let (columns, other_columns, schema, table) = match reverse {
+      true => (&self.referenced_columns, &self.columns, &self.schema, &self.table),
+      false => (&self.columns, &self.referenced_columns, &self.referenced_schema, &self.referenced_table),
+    };
+    let filters = columns
+      .iter()
+      .zip(other_columns)
+      .map(|(column, other_column)| {
+        let value = row.iter().find(|(name, _)| name == column).and_then(|(_, value)| value.clone())?;
+        Some(RowFilter { column: other_column.clone(), operator: FilterOperator::Eq, value })
+      })
+      .collect::<Option<Vec<_>>>()?;
+    Some((schema.clone(), table.clone(), filters))
+  }
+
+  pub fn label(&self) -> String {
+    format!(
+      "{} ({}) -> {} ({})",
+      qualified_name(&self.schema, &self.table),
+      self.columns.join(", "),
+      qualified_name(&self.referenced_schema, &self.referenced_table),
+      self.referenced_columns.join(", ")
+    )
+  }
+}
+
+// `schema.table`, or just the table in sqlite, which has no schemas
+pub fn qualified_name(schema: &str, table: &str) -> String {
+  match schema {
+    "" => table.to_owned(),
+    schema => format!("{}.{}", schema, table),
+  }
+}
+
+// the keys of `schema.table` that can be followed from `column`: the ones from the table, or with
+// `reverse` the ones referencing it. keys without the column are only offered when none have it
+pub fn foreign_keys_to_follow(
+  keys: Vec<ForeignKey>,
+  schema: &str,
+  table: &str,
+  column: &str,
+  reverse: bool,
+) -> Vec<ForeignKey> {
+  let (with_column, others): (Vec<_>, Vec<_>) = keys
+    .into_iter()
+    .filter(|key| {
+      match reverse {
+        true => key.referenced_schema == schema && key.referenced_table == table,
+        false => key.schema == schema && key.table == table,
+      }
+    })
+    .partition(|key| {
+      match reverse {
+        true => key.referenced_columns.iter().any(|c| c == column),
+        false => key.columns.iter().any(|c| c == column),
+      }
+    });
+  if with_column.is_empty() {
+    others
+  } else {
+    with_column
+  }
 }
 
 pub fn header_to_vec(headers: &Headers) -> Vec<String> {
@@ -271,3 +720,184 @@ pub fn get_dialect(db_type: &str) -> Arc<dyn Dialect + Send + Sync> {
     x => panic!("Unsupported database type: {}", x),
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rows(headers: &[&str], rows: &[&[&str]]) -> Rows {
+    Rows {
+      headers: headers.iter().map(|h| Header { name: h.to_string(), type_name: "TEXT".to_owned() }).collect(),
+      rows: rows.iter().map(|row| row.iter().map(|s| s.to_string()).collect()).collect(),
+      rows_affected: None,
+    }
+  }
+
+  #[test]
+  fn test_definition_text() {
+    let postgres = rows(&["definition"], &[&["create view v as\n select 1;"]]);
+    assert_eq!(definition_text(&postgres).as_deref(), Some("create view v as\n select 1;"));
+    let mysql = rows(&["Table", "Create Table"], &[&["t", "CREATE TABLE `t` (\n  `id` int\n)"]]);
+    assert_eq!(definition_text(&mysql).as_deref(), Some("CREATE TABLE `t` (\n  `id` int\n)"));
+    let trigger = rows(&["Trigger", "sql_mode", "SQL Original Statement", "character_set_client"], &[&[
+      "trg",
+      "",
+      "CREATE TRIGGER trg BEFORE INSERT ON t FOR EACH ROW SET NEW.id = 1",
+      "utf8mb4",
+    ]]);
+    assert_eq!(
+      definition_text(&trigger).as_deref(),
+      Some("CREATE TRIGGER trg BEFORE INSERT ON t FOR EACH ROW SET NEW.id = 1")
+    );
+    assert_eq!(definition_text(&rows(&["definition"], &[])), None);
+  }
+
+  #[test]
+  fn test_preview_rows_clauses() {
+    let by_id = RowsPreview { primary_key: vec!["id".to_owned()], ..RowsPreview::default() };
+    assert_eq!(preview_rows_clauses('"', &RowsPreview::default(), 100), " limit 100");
+    assert_eq!(preview_rows_clauses('"', &by_id, 50), " order by \"id\" limit 50");
+    let latest = RowsPreview { descending: true, after: vec!["42".to_owned()], offset: 50, ..by_id.clone() };
+    assert_eq!(preview_rows_clauses('"', &latest, 50), " where (\"id\") < ('42') order by \"id\" desc limit 50");
+    let composite = RowsPreview {
+      primary_key: vec!["tenant".to_owned(), "name".to_owned()],
+      after: vec!["1".to_owned(), "o'brien".to_owned()],
+      ..RowsPreview::default()
+    };
+    assert_eq!(
+      preview_rows_clauses('`', &composite, 10),
+      " where (`tenant`, `name`) > ('1', 'o''brien') order by `tenant`, `name` limit 10"
+    );
+    let offset = RowsPreview { offset: 100, ..RowsPreview::default() };
+    assert_eq!(preview_rows_clauses('"', &offset, 100), " limit 100 offset 100");
+    let filter =
+      |column: &str, operator, value: &str| RowFilter { column: column.to_owned(), operator, value: value.to_owned() };
+    let filtered = RowsPreview {
+      filters: vec![
+        filter("status", FilterOperator::In, "new, o'pen"),
+        filter("deleted_at", FilterOperator::IsNull, ""),
+      ],
+      after: vec!["42".to_owned()],
+      ..by_id.clone()
+    };
+    assert_eq!(
+      preview_rows_clauses('"', &filtered, 50),
+      " where \"status\" in ('new', 'o''pen') and \"deleted_at\" is null and (\"id\") > ('42') order by \"id\" limit 50"
+    );
+    // the previews have to survive being parsed before they're run
+    for dialect in ["PostgreSQL", "MySQL", "SQLite"] {
+      let query = format!("select * from t{}", preview_rows_clauses('"', &composite, 10));
+      assert!(get_first_query(query, get_dialect(dialect).as_ref()).is_ok(), "Failed for {}", dialect);
+    }
+  }
+
+  #[test]
+  fn test_keyset_type() {
+    for type_name in ["INT4", "int8", "BIGINT UNSIGNED", "INTEGER", "TEXT", "VARCHAR", "BPCHAR"] {
+      assert!(keyset_type(type_name), "{}", type_name);
+    }
+    for type_name in
+      ["TIMESTAMPTZ", "DATETIME", "BYTEA", "BLOB", "UUID", "FLOAT8", "DOUBLE", "REAL", "NUMERIC", "BOOLEAN"]
+    {
+      assert!(!keyset_type(type_name), "{}", type_name);
+    }
+  }
+
+  #[test]
+  fn test_filter_condition() {
+    let filter =
+      |column: &str, operator, value: &str| RowFilter { column: column.to_owned(), operator, value: value.to_owned() };
+    assert_eq!(filter_condition('"', &filter("name", FilterOperator::Eq, "o'brien")), "\"name\" = 'o''brien'");
+    assert_eq!(filter_condition('"', &filter("we\"ird", FilterOperator::NotEq, "1")), "\"we\"\"ird\" != '1'");
+    assert_eq!(filter_condition('`', &filter("total", FilterOperator::Gt, "10")), "`total` > '10'");
+    assert_eq!(filter_condition('`', &filter("total", FilterOperator::Lt, "10")), "`total` < '10'");
+    assert_eq!(
+      filter_condition('"', &filter("email", FilterOperator::Like, "%@example.com")),
+      "\"email\" like '%@example.com'"
+    );
+    assert_eq!(filter_condition('"', &filter("id", FilterOperator::In, "1,2, 3")), "\"id\" in ('1', '2', '3')");
+    assert_eq!(filter_condition('"', &filter("email", FilterOperator::IsNull, "ignored")), "\"email\" is null");
+    // only `is null` matches nulls, so text that reads NULL can still be compared
+    assert_eq!(filter_condition('"', &filter("email", FilterOperator::Eq, "NULL")), "\"email\" = 'NULL'");
+    assert_eq!(filter_condition('`', &filter("email", FilterOperator::NotEq, "NULL")), "`email` != 'NULL'");
+    assert_eq!(
+      filter_condition('"', &filter("city", FilterOperator::In, "'Paris, TX', Oslo ,'it''s', ' a '")),
+      "\"city\" in ('Paris, TX', 'Oslo', 'it''s', ' a ')"
+    );
+    assert_eq!(filter_condition('`', &filter("path", FilterOperator::Eq, "c:\\x'")), "`path` = 'c:\\\\x'''");
+  }
+
+  #[test]
+  fn test_foreign_keys() {
+    let keys = ForeignKey::from_rows(&rows(
+      &["constraint_name", "schema", "table", "column", "ref_schema", "ref_table", "ref_column"],
+      &[
+        &["orders_user_fk", "app", "orders", "user_id", "app", "users", "id"],
+        &["items_order_fk", "app", "items", "tenant", "app", "orders", "tenant"],
+        &["items_order_fk", "app", "items", "order_id", "app", "orders", "id"],
+      ],
+    ));
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[1].columns, vec!["tenant", "order_id"]);
+    assert_eq!(keys[1].label(), "app.items (tenant, order_id) -> app.orders (tenant, id)");
+
+    let row = |values: &[(&str, Option<&str>)]| -> Vec<(String, Option<String>)> {
+      values.iter().map(|(c, v)| (c.to_string(), v.map(str::to_string))).collect()
+    };
+    let order = row(&[("id", Some("7")), ("tenant", Some("1")), ("user_id", Some("3"))]);
+    let (schema, table, filters) = keys[0].related_rows(&order, false).unwrap();
+    assert_eq!((schema.as_str(), table.as_str()), ("app", "users"));
+    assert_eq!(filters, vec![RowFilter {
+      column: "id".to_owned(),
+      operator: FilterOperator::Eq,
+      value: "3".to_owned()
+    }]);
+    let (_, table, filters) = keys[1].related_rows(&order, true).unwrap();
+    assert_eq!(table, "items");
+    assert_eq!(filters.iter().map(|f| (f.column.as_str(), f.value.as_str())).collect::<Vec<_>>(), vec![
+      ("tenant", "1"),
+      ("order_id", "7")
+    ]);
+    // a null reference has no row to go to, but text that reads NULL is a value like any other
+    assert_eq!(keys[0].related_rows(&row(&[("id", Some("8")), ("user_id", None)]), false), None);
+    let (.., filters) = keys[0].related_rows(&row(&[("id", Some("9")), ("user_id", Some("NULL"))]), false).unwrap();
+    assert_eq!(filters[0].value, "NULL");
+
+    assert_eq!(foreign_keys_to_follow(keys.clone(), "app", "orders", "user_id", false), vec![keys[0].clone()]);
+    // the id column isn't part of a key from orders, so every key from it is offered
+    assert_eq!(foreign_keys_to_follow(keys.clone(), "app", "orders", "id", false), vec![keys[0].clone()]);
+    assert_eq!(foreign_keys_to_follow(keys.clone(), "app", "orders", "id", true), vec![keys[1].clone()]);
+    assert!(foreign_keys_to_follow(keys, "app", "items", "id", true).is_empty());
+  }
+
+  #[test]
+  fn test_maintenance_statement() {
+    assert_eq!(maintenance_statement("vacuum \"app\".\"orders\";\n"), Some("vacuum \"app\".\"orders\"".to_owned()));
+    assert_eq!(maintenance_statement("  REINDEX \"users\""), Some("REINDEX \"users\"".to_owned()));
+    assert_eq!(
+      maintenance_statement("optimize table `app`.`orders`"),
+      Some("optimize table `app`.`orders`".to_owned())
+    );
+    assert_eq!(maintenance_statement("vacuum; drop table users"), None);
+    assert_eq!(maintenance_statement("select * from vacuum"), None);
+    assert_eq!(maintenance_statement(""), None);
+  }
+
+  #[test]
+  fn test_is_ddl() {
+    let dialect = PostgreSqlDialect {};
+    for (query, expected) in [
+      ("create table t (id int)", true),
+      ("drop table t", true),
+      ("alter table t rename to u", true),
+      ("create index i on t (id)", true),
+      ("create view v as select 1", true),
+      ("select * from t", false),
+      ("insert into t values (1)", false),
+      ("update t set id = 2", false),
+    ] {
+      let (_, statement) = get_first_query(query.to_owned(), &dialect).unwrap();
+      assert_eq!(is_ddl(&statement), expected, "{query}");
+    }
+  }
+}
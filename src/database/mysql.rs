@@ -1,8 +1,4 @@
-use std::{
-  fmt::Write,
-  io::{self, Write as _},
-  str::FromStr,
-};
+use std::{fmt::Write, net::SocketAddr, str::FromStr};
 
 use serde_json;
 use sqlparser::ast::Statement;
@@ -11,7 +7,8 @@ use sqlx::{
   Column, Database, Row, ValueRef,
 };
 
-use super::{vec_to_string, Value};
+use super::{quote_identifier, quote_mysql_literal, vec_to_string, ObjectKind, Value};
+use crate::action::{RowsPreview, TableAction};
 
 impl super::HasRowsAffected for MySqlQueryResult {
   fn rows_affected(&self) -> u64 {
@@ -26,101 +23,213 @@ impl super::BuildConnectionOptions for MySql {
     match args.connection_url {
       Some(url) => Ok(MySqlConnectOptions::from_str(&url)?),
       None => {
+        // anything not given falls back to the driver's defaults
         let mut opts = MySqlConnectOptions::new();
-
-        // Username
         if let Some(user) = args.user {
           opts = opts.username(&user);
-        } else {
-          let mut user = String::new();
-          print!("username: ");
-          io::stdout().flush()?;
-          io::stdin().read_line(&mut user)?;
-          let user = user.trim();
-          if !user.is_empty() {
-            opts = opts.username(user);
-          }
         }
-
-        // Password
         if let Some(password) = args.password {
           opts = opts.password(&password);
-        } else {
-          let password = rpassword::prompt_password(format!("password for user {}: ", opts.get_username())).unwrap();
-          let password = password.trim();
-          if !password.is_empty() {
-            opts = opts.password(password);
-          }
         }
-
-        // Host
         if let Some(host) = args.host {
           opts = opts.host(&host);
-        } else {
-          let mut host = String::new();
-          print!("host (ex. localhost): ");
-          io::stdout().flush()?;
-          io::stdin().read_line(&mut host)?;
-          let host = host.trim();
-          if !host.is_empty() {
-            opts = opts.host(host);
-          }
         }
-
-        // Port
         if let Some(port) = args.port {
           opts = opts.port(port);
-        } else {
-          let mut port = String::new();
-          print!("port (ex. 3306): ");
-          io::stdout().flush()?;
-          io::stdin().read_line(&mut port)?;
-          let port = port.trim();
-          if !port.is_empty() {
-            opts = opts.port(port.parse()?);
-          }
         }
-
-        // Database
         if let Some(database) = args.database {
           opts = opts.database(&database);
-        } else {
-          let mut database = String::new();
-          print!("database (ex. mydb): ");
-          io::stdout().flush()?;
-          io::stdin().read_line(&mut database)?;
-          let database = database.trim();
-          if !database.is_empty() {
-            opts = opts.database(database);
-          }
         }
 
         Ok(opts)
       },
     }
   }
+
+  fn tunnel_target(opts: &MySqlConnectOptions) -> color_eyre::eyre::Result<(String, u16)> {
+    Ok((opts.get_host().to_string(), opts.get_port()))
+  }
+
+  fn through_tunnel(opts: MySqlConnectOptions, local_addr: SocketAddr) -> MySqlConnectOptions {
+    opts.host(&local_addr.ip().to_string()).port(local_addr.port())
+  }
+
+  // every connection of the pool selects the database when it connects, the same as `use`
+  fn with_database(opts: MySqlConnectOptions, database: &str) -> Option<MySqlConnectOptions> {
+    Some(opts.database(database))
+  }
+
+  fn database_name(opts: &MySqlConnectOptions) -> Option<String> {
+    opts.get_database().map(str::to_owned)
+  }
+}
+
+fn foreign_keys_query(condition: &str) -> String {
+  format!(
+    "select constraint_name, table_schema, table_name, column_name,
+      referenced_table_schema, referenced_table_name, referenced_column_name
+      from information_schema.key_column_usage
+      where referenced_table_name is not null
+      and {}
+      order by 1, 2, 3, ordinal_position",
+    condition
+  )
 }
 
 impl super::DatabaseQueries for MySql {
+  fn preview_databases_query() -> String {
+    "select cast(schema_name as char) as database_name, coalesce(schema_name = database(), 0) as current
+      from information_schema.schemata
+      order by schema_name"
+      .to_owned()
+  }
+
   fn preview_tables_query() -> String {
     "select table_schema as table_schema, table_name as table_name
       from information_schema.tables
       where table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      and table_type != 'VIEW'
       order by table_schema, table_name asc"
       .to_owned()
   }
 
-  fn preview_rows_query(schema: &str, table: &str) -> String {
-    format!("select * from `{}`.`{}` limit 100", schema, table)
+  fn preview_table_sizes_query() -> String {
+    "select cast(table_schema as char) as table_schema, cast(table_name as char) as table_name,
+      table_rows as row_estimate, data_length + index_length as size
+      from information_schema.tables
+      where table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      and table_type = 'BASE TABLE'"
+      .to_owned()
+  }
+
+  fn preview_schema_objects_query() -> String {
+    "select cast(table_schema as char) as object_schema, cast('view' as char) as object_kind, cast(table_name as char) as object_name
+      from information_schema.views
+      where table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      union all
+      select cast(routine_schema as char), cast(lower(routine_type) as char), cast(routine_name as char)
+      from information_schema.routines
+      where routine_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      union all
+      select cast(trigger_schema as char), cast('trigger' as char), cast(trigger_name as char)
+      from information_schema.triggers
+      where trigger_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      order by 1, 2, 3"
+      .to_owned()
+  }
+
+  fn preview_definition_query(kind: ObjectKind, schema: &str, name: &str, _table: Option<&str>) -> String {
+    let qualified = format!("{}.{}", quote_identifier('`', schema), quote_identifier('`', name));
+    match kind {
+      ObjectKind::Table => format!("show create table {}", qualified),
+      ObjectKind::View => format!("show create view {}", qualified),
+      ObjectKind::Trigger => format!("show create trigger {}", qualified),
+      // functions and procedures share a node, so the routine type isn't known up front
+      ObjectKind::Function => {
+        format!(
+          "select routine_type, dtd_identifier as returns, routine_definition
+            from information_schema.routines
+            where routine_schema = {} and routine_name = {}",
+          quote_mysql_literal(schema),
+          quote_mysql_literal(name)
+        )
+      },
+      kind => format!("select 'MySQL does not support {}' as message", kind.label()),
+    }
+  }
+
+  fn preview_dependencies_query(kind: ObjectKind, schema: &str, name: &str, _table: Option<&str>) -> String {
+    let (schema, name) = (quote_mysql_literal(schema), quote_mysql_literal(name));
+    match kind {
+      ObjectKind::Table => {
+        format!(
+          "select 'required by' as direction, concat('view ', view_schema, '.', view_name) as object
+            from information_schema.view_table_usage
+            where table_schema = {schema} and table_name = {name}
+            union all
+            select 'required by', concat('trigger ', trigger_schema, '.', trigger_name)
+            from information_schema.triggers
+            where event_object_schema = {schema} and event_object_table = {name}
+            union all
+            select distinct 'required by', concat('foreign key ', constraint_name, ' on ', table_schema, '.', table_name)
+            from information_schema.key_column_usage
+            where referenced_table_schema = {schema} and referenced_table_name = {name}"
+        )
+      },
+      ObjectKind::View => {
+        format!(
+          "select 'depends on' as direction, concat(table_schema, '.', table_name) as object
+            from information_schema.view_table_usage
+            where view_schema = {schema} and view_name = {name}
+            union all
+            select 'required by', concat('view ', view_schema, '.', view_name)
+            from information_schema.view_table_usage
+            where table_schema = {schema} and table_name = {name}"
+        )
+      },
+      ObjectKind::Trigger => {
+        format!(
+          "select 'depends on' as direction, concat('table ', event_object_schema, '.', event_object_table) as object
+            from information_schema.triggers
+            where trigger_schema = {schema} and trigger_name = {name}"
+        )
+      },
+      ObjectKind::Function => "select 'MySQL does not track dependencies of functions' as message".to_owned(),
+      kind => format!("select 'MySQL does not support {}' as message", kind.label()),
+    }
+  }
+
+  fn preview_rows_query(schema: &str, table: &str, preview: &RowsPreview, limit: usize) -> String {
+    let name = format!("{}.{}", quote_identifier('`', schema), quote_identifier('`', table));
+    format!("select * from {}{}", name, super::preview_rows_clauses('`', preview, limit))
   }
 
   fn preview_columns_query(schema: &str, table: &str) -> String {
     format!(
       "select column_name, data_type, is_nullable, column_default, extra, column_comment
         from information_schema.columns
-        where table_schema = '{}' and table_name = '{}'
+        where table_schema = {} and table_name = {}
         order by ordinal_position",
-      schema, table
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
+    )
+  }
+
+  fn preview_column_details_query(schema: &str, table: &str) -> String {
+    format!(
+      "select c.column_name, c.column_type as data_type, c.is_nullable,
+        coalesce(c.column_default, '') as column_default,
+        concat_ws(',',
+          if(c.column_key = 'PRI', 'PK', null),
+          if(exists(
+            select 1 from information_schema.key_column_usage k
+            where k.table_schema = c.table_schema and k.table_name = c.table_name and k.column_name = c.column_name
+            and k.referenced_table_name is not null
+          ), 'FK', null)
+        ) as `keys`
+        from information_schema.columns c
+        where c.table_schema = {} and c.table_name = {}
+        order by c.ordinal_position",
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
+    )
+  }
+
+  fn preview_all_columns_query() -> String {
+    "select cast(table_schema as char) as table_schema, cast(table_name as char) as table_name,
+      cast(column_name as char) as column_name, cast(column_type as char) as column_type
+      from information_schema.columns
+      where table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      order by table_schema, table_name, ordinal_position"
+      .to_owned()
+  }
+
+  fn preview_distinct_values_query(schema: &str, table: &str, column: &str) -> String {
+    format!(
+      "select {}, count(*) as count from {}.{} group by 1 order by 2 desc, 1 limit 100",
+      quote_identifier('`', column),
+      quote_identifier('`', schema),
+      quote_identifier('`', table)
     )
   }
 
@@ -130,10 +239,11 @@ impl super::DatabaseQueries for MySql {
         group_concat(column_name order by ordinal_position) as column_names
         from information_schema.table_constraints
         join information_schema.key_column_usage using (constraint_schema, constraint_name, table_schema, table_name)
-        where table_schema = '{}' and table_name = '{}'
+        where table_schema = {} and table_name = {}
         group by constraint_name, constraint_type, enforced
         order by constraint_type, constraint_name",
-      schema, table
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
     )
   }
 
@@ -141,15 +251,112 @@ impl super::DatabaseQueries for MySql {
     format!(
       "select index_name, column_name, non_unique, seq_in_index, index_type
         from information_schema.statistics
-        where table_schema = '{}' and table_name = '{}'
+        where table_schema = {} and table_name = {}
         order by index_name, seq_in_index",
-      schema, table
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
     )
   }
 
   fn preview_policies_query(_schema: &str, _table: &str) -> String {
     "select 'MySQL does not support row-level security policies' as message".to_owned()
   }
+
+  fn preview_triggers_query(schema: &str, table: &str) -> String {
+    format!(
+      "select trigger_name, action_timing, event_manipulation, action_statement
+        from information_schema.triggers
+        where event_object_schema = {} and event_object_table = {}
+        order by trigger_name",
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
+    )
+  }
+
+  fn preview_grants_query(schema: &str, table: &str) -> String {
+    format!(
+      "select grantee, privilege_type, is_grantable
+        from information_schema.table_privileges
+        where table_schema = {} and table_name = {}
+        order by grantee, privilege_type",
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
+    )
+  }
+
+  // mysql doesn't count scans per table, so this is what information_schema knows about its storage
+  fn preview_statistics_query(schema: &str, table: &str) -> String {
+    format!(
+      "select engine, table_rows, avg_row_length, data_length, index_length, data_free, auto_increment,
+        create_time, update_time, check_time
+        from information_schema.tables
+        where table_schema = {} and table_name = {}",
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
+    )
+  }
+
+  fn preview_partitions_query(schema: &str, table: &str) -> String {
+    format!(
+      "select partition_name, partition_method, partition_expression, partition_description, table_rows
+        from information_schema.partitions
+        where table_schema = {} and table_name = {} and partition_name is not null
+        order by partition_ordinal_position",
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
+    )
+  }
+
+  fn preview_referencing_tables_query(schema: &str, table: &str) -> String {
+    format!(
+      "select concat(table_schema, '.', table_name) as referencing_table, constraint_name,
+        group_concat(column_name order by ordinal_position) as column_names,
+        group_concat(referenced_column_name order by ordinal_position) as referenced_column_names
+        from information_schema.key_column_usage
+        where referenced_table_schema = {} and referenced_table_name = {}
+        group by table_schema, table_name, constraint_name
+        order by 1, 2",
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
+    )
+  }
+
+  fn preview_foreign_keys_query(schema: &str, table: &str) -> String {
+    foreign_keys_query(&format!(
+      "((table_schema = {0} and table_name = {1}) or (referenced_table_schema = {0} and referenced_table_name = {1}))",
+      quote_mysql_literal(schema),
+      quote_mysql_literal(table)
+    ))
+  }
+
+  fn preview_schema_foreign_keys_query(schema: &str) -> String {
+    foreign_keys_query(&format!("table_schema = {}", quote_mysql_literal(schema)))
+  }
+
+  fn table_actions() -> Vec<TableAction> {
+    vec![
+      TableAction::Count,
+      TableAction::Truncate,
+      TableAction::Drop,
+      TableAction::Rename(String::new()),
+      TableAction::Optimize,
+    ]
+  }
+
+  fn table_action_query(action: &TableAction, schema: &str, table: &str) -> Option<String> {
+    let name = format!("{}.{}", quote_identifier('`', schema), quote_identifier('`', table));
+    match action {
+      TableAction::Count => Some(format!("select count(*) from {}", name)),
+      TableAction::Truncate => Some(format!("truncate table {}", name)),
+      TableAction::Drop => Some(format!("drop table {}", name)),
+      TableAction::Rename(new_name) if new_name.trim().is_empty() => None,
+      TableAction::Rename(new_name) => {
+        Some(format!("rename table {} to {}.{}", name, quote_identifier('`', schema), quote_identifier('`', new_name)))
+      },
+      TableAction::Optimize => Some(format!("optimize table {}", name)),
+      TableAction::Vacuum | TableAction::Analyze | TableAction::Reindex => None,
+    }
+  }
 }
 
 impl super::ValueParser for MySql {
@@ -367,9 +574,12 @@ mod tests {
     dialect::MySqlDialect,
     parser::{Parser, ParserError},
   };
+  use strum::IntoEnumIterator;
 
   use super::*;
-  use crate::database::{get_execution_type, get_first_query, DbError, ExecutionType};
+  use crate::database::{
+    get_execution_type, get_first_query, maintenance_statement, DatabaseQueries, DbError, ExecutionType,
+  };
 
   #[test]
   fn test_get_first_query_mysql() {
@@ -469,4 +679,75 @@ mod tests {
       assert_eq!(get_execution_type(statement, false), expected, "Failed for query: {}", query);
     }
   }
+
+  #[test]
+  fn test_foreign_keys_query_mysql() {
+    let query = <MySql as DatabaseQueries>::preview_foreign_keys_query("app", "orders");
+    assert!(get_first_query(query.clone(), &MySqlDialect {}).is_ok(), "Failed for query: {}", query);
+    let query = <MySql as DatabaseQueries>::preview_schema_foreign_keys_query("app");
+    assert!(get_first_query(query.clone(), &MySqlDialect {}).is_ok(), "Failed for query: {}", query);
+  }
+
+  #[test]
+  fn test_quoted_names_mysql() {
+    let dialect = MySqlDialect {};
+    let (schema, name) = ("app", "o'brien\\");
+    for kind in ObjectKind::iter() {
+      for query in [
+        <MySql as DatabaseQueries>::preview_definition_query(kind, schema, name, None),
+        <MySql as DatabaseQueries>::preview_dependencies_query(kind, schema, name, None),
+      ] {
+        assert!(get_first_query(query.clone(), &dialect).is_ok(), "Failed for query: {}", query);
+      }
+    }
+    for query in [
+      <MySql as DatabaseQueries>::preview_rows_query(schema, name, &RowsPreview::default(), 10),
+      <MySql as DatabaseQueries>::preview_columns_query(schema, name),
+      <MySql as DatabaseQueries>::preview_column_details_query(schema, name),
+      <MySql as DatabaseQueries>::preview_distinct_values_query(schema, name, "we\"ird`"),
+      <MySql as DatabaseQueries>::preview_constraints_query(schema, name),
+      <MySql as DatabaseQueries>::preview_indexes_query(schema, name),
+      <MySql as DatabaseQueries>::preview_policies_query(schema, name),
+      <MySql as DatabaseQueries>::preview_triggers_query(schema, name),
+      <MySql as DatabaseQueries>::preview_grants_query(schema, name),
+      <MySql as DatabaseQueries>::preview_statistics_query(schema, name),
+      <MySql as DatabaseQueries>::preview_partitions_query(schema, name),
+      <MySql as DatabaseQueries>::preview_referencing_tables_query(schema, name),
+      <MySql as DatabaseQueries>::preview_foreign_keys_query(schema, name),
+      <MySql as DatabaseQueries>::preview_schema_foreign_keys_query(name),
+    ] {
+      assert!(get_first_query(query.clone(), &dialect).is_ok(), "Failed for query: {}", query);
+    }
+  }
+
+  #[test]
+  fn test_table_action_queries_mysql() {
+    let dialect = MySqlDialect {};
+    let test_cases = vec![
+      (TableAction::Count, Some(ExecutionType::Normal)),
+      (TableAction::Truncate, Some(ExecutionType::Confirm)),
+      (TableAction::Drop, Some(ExecutionType::Confirm)),
+      (TableAction::Rename("archived_orders".to_owned()), Some(ExecutionType::Confirm)),
+      (TableAction::Optimize, None),
+    ];
+
+    for (action, expected) in test_cases {
+      let query = <MySql as DatabaseQueries>::table_action_query(&action, "app", "orders").unwrap();
+      match get_first_query(query.clone(), &dialect) {
+        Ok((_, statement)) => {
+          assert_eq!(Some(get_execution_type(statement, false)), expected, "Failed for query: {}", query)
+        },
+        Err(_) => assert!(expected.is_none() && maintenance_statement(&query).is_some(), "Failed for query: {}", query),
+      }
+    }
+
+    let rename = |new_name: &str| {
+      <MySql as DatabaseQueries>::table_action_query(&TableAction::Rename(new_name.to_owned()), "app", "we`ird")
+    };
+    assert_eq!(
+      rename("x`; drop table y; --"),
+      Some("rename table `app`.`we``ird` to `app`.`x``; drop table y; --`".to_owned())
+    );
+    assert_eq!(rename("  "), None);
+  }
 }
@@ -1,9 +1,4 @@
-use std::{
-  fmt::Write,
-  io::{self, Write as _},
-  str::FromStr,
-  string::String,
-};
+use std::{fmt::Write, net::SocketAddr, str::FromStr, string::String};
 
 use futures::stream::{BoxStream, StreamExt};
 use sqlparser::{
@@ -17,7 +12,8 @@ use sqlx::{
   Column, Database, Either, Row, ValueRef,
 };
 
-use super::{vec_to_string, Value};
+use super::{quote_identifier, quote_literal, vec_to_string, ObjectKind, Value};
+use crate::action::{RowsPreview, TableAction};
 
 impl super::BuildConnectionOptions for sqlx::Postgres {
   fn build_connection_opts(
@@ -26,75 +22,47 @@ impl super::BuildConnectionOptions for sqlx::Postgres {
     match args.connection_url {
       Some(url) => Ok(PgConnectOptions::from_str(&url)?),
       None => {
+        // anything not given falls back to the driver's defaults
         let mut opts = PgConnectOptions::new();
-
         if let Some(user) = args.user {
           opts = opts.username(&user);
-        } else {
-          let mut user: String = String::new();
-          print!("username: ");
-          io::stdout().flush().unwrap();
-          io::stdin().read_line(&mut user).unwrap();
-          user = user.trim().to_string();
-          if !user.is_empty() {
-            opts = opts.username(&user);
-          }
         }
-
         if let Some(password) = args.password {
           opts = opts.password(&password);
-        } else {
-          let mut password =
-            rpassword::prompt_password(format!("password for user {}: ", opts.get_username())).unwrap();
-          password = password.trim().to_string();
-          if !password.is_empty() {
-            opts = opts.password(&password);
-          }
         }
-
         if let Some(host) = args.host {
           opts = opts.host(&host);
-        } else {
-          let mut host: String = String::new();
-          print!("host (ex. localhost): ");
-          io::stdout().flush().unwrap();
-          io::stdin().read_line(&mut host).unwrap();
-          host = host.trim().to_string();
-          if !host.is_empty() {
-            opts = opts.host(&host);
-          }
         }
-
         if let Some(port) = args.port {
           opts = opts.port(port);
-        } else {
-          let mut port: String = String::new();
-          print!("port (ex. 5432): ");
-          io::stdout().flush().unwrap();
-          io::stdin().read_line(&mut port).unwrap();
-          port = port.trim().to_string();
-          if !port.is_empty() {
-            opts = opts.port(port.parse()?);
-          }
         }
-
         if let Some(database) = args.database {
           opts = opts.database(&database);
-        } else {
-          let mut database: String = String::new();
-          print!("database (ex. postgres): ");
-          io::stdout().flush().unwrap();
-          io::stdin().read_line(&mut database).unwrap();
-          database = database.trim().to_string();
-          if !database.is_empty() {
-            opts = opts.database(&database);
-          }
         }
 
         Ok(opts)
       },
     }
   }
+
+  fn tunnel_target(opts: &PgConnectOptions) -> color_eyre::eyre::Result<(String, u16)> {
+    if opts.get_socket().is_some() {
+      return Err(color_eyre::eyre::Report::msg("SSH tunnels are not supported for unix socket connections"));
+    }
+    Ok((opts.get_host().to_string(), opts.get_port()))
+  }
+
+  fn through_tunnel(opts: PgConnectOptions, local_addr: SocketAddr) -> PgConnectOptions {
+    opts.host(&local_addr.ip().to_string()).port(local_addr.port())
+  }
+
+  fn with_database(opts: PgConnectOptions, database: &str) -> Option<PgConnectOptions> {
+    Some(opts.database(database))
+  }
+
+  fn database_name(opts: &PgConnectOptions) -> Option<String> {
+    opts.get_database().map(str::to_owned)
+  }
 }
 
 impl super::HasRowsAffected for PgQueryResult {
@@ -103,41 +71,460 @@ impl super::HasRowsAffected for PgQueryResult {
   }
 }
 
+fn foreign_keys_query(condition: &str) -> String {
+  format!(
+    "select c.conname as constraint_name, ns.nspname as table_schema, t.relname as table_name,
+      a.attname as column_name, rns.nspname as referenced_table_schema, rt.relname as referenced_table_name,
+      ra.attname as referenced_column_name
+      from pg_constraint c
+      cross join unnest(c.conkey, c.confkey) with ordinality as k(attnum, referenced_attnum, position)
+      join pg_class t on t.oid = c.conrelid
+      join pg_namespace ns on ns.oid = t.relnamespace
+      join pg_attribute a on a.attrelid = c.conrelid and a.attnum = k.attnum
+      join pg_class rt on rt.oid = c.confrelid
+      join pg_namespace rns on rns.oid = rt.relnamespace
+      join pg_attribute ra on ra.attrelid = c.confrelid and ra.attnum = k.referenced_attnum
+      where c.contype = 'f'
+      and {}
+      order by 1, 2, 3, k.position",
+    condition
+  )
+}
+
 impl super::DatabaseQueries for Postgres {
+  fn preview_databases_query() -> String {
+    "select datname, datname = current_database() as current
+      from pg_database
+      where not datistemplate and datallowconn
+      order by datname"
+      .to_owned()
+  }
+
   fn preview_tables_query() -> String {
     "select table_schema, table_name
       from information_schema.tables
       where table_schema != 'pg_catalog'
       and table_schema != 'information_schema'
+      and table_type != 'VIEW'
       group by table_schema, table_name
       order by table_schema, table_name asc"
       .to_owned()
   }
 
-  fn preview_rows_query(schema: &str, table: &str) -> String {
-    format!("select * from \"{}\".\"{}\" limit 100", schema, table)
+  // reltuples is -1 for tables that haven't been vacuumed or analyzed yet
+  fn preview_table_sizes_query() -> String {
+    "select n.nspname as table_schema, c.relname as table_name,
+      case when c.reltuples < 0 then null else c.reltuples::bigint end as row_estimate,
+      pg_total_relation_size(c.oid) as size
+      from pg_class c
+      join pg_namespace n on n.oid = c.relnamespace
+      where c.relkind in ('r', 'p', 'f')
+      and n.nspname != 'pg_catalog'
+      and n.nspname != 'information_schema'"
+      .to_owned()
+  }
+
+  fn preview_schema_objects_query() -> String {
+    "select n.nspname as object_schema,
+        case c.relkind when 'v' then 'view' when 'm' then 'materialized view' else 'sequence' end as object_kind,
+        c.relname as object_name, null::name as object_table
+        from pg_class c
+        join pg_namespace n on n.oid = c.relnamespace
+        where c.relkind in ('v', 'm', 'S')
+        and n.nspname not in ('pg_catalog', 'information_schema')
+      union all
+      select n.nspname, case p.prokind when 'p' then 'procedure' else 'function' end,
+        quote_ident(p.proname) || '(' || oidvectortypes(p.proargtypes) || ')', null
+        from pg_proc p
+        join pg_namespace n on n.oid = p.pronamespace
+        where p.prokind in ('f', 'p')
+        and n.nspname not in ('pg_catalog', 'information_schema')
+        and not exists (select 1 from pg_depend d where d.classid = 'pg_proc'::regclass and d.objid = p.oid and d.deptype = 'e')
+      union all
+      select n.nspname, 'type', t.typname, null
+        from pg_type t
+        join pg_namespace n on n.oid = t.typnamespace
+        left join pg_class c on c.oid = t.typrelid
+        where t.typtype in ('c', 'd', 'e', 'r', 'm')
+        and (c.relkind is null or c.relkind = 'c')
+        and n.nspname not in ('pg_catalog', 'information_schema')
+        and not exists (select 1 from pg_depend d where d.classid = 'pg_type'::regclass and d.objid = t.oid and d.deptype = 'e')
+      union all
+      select n.nspname, 'trigger', t.tgname, c.relname
+        from pg_trigger t
+        join pg_class c on c.oid = t.tgrelid
+        join pg_namespace n on n.oid = c.relnamespace
+        where not t.tgisinternal
+        and n.nspname not in ('pg_catalog', 'information_schema')
+      union all
+      select n.nspname, 'extension', e.extname, null
+        from pg_extension e
+        join pg_namespace n on n.oid = e.extnamespace
+        where n.nspname not in ('pg_catalog', 'information_schema')
+      order by 1, 2, 3, 4"
+      .to_owned()
+  }
+
+  fn preview_definition_query(kind: ObjectKind, schema: &str, name: &str, table: Option<&str>) -> String {
+    let table = quote_literal(table.unwrap_or_default());
+    let (schema, name) = (quote_literal(schema), quote_literal(name));
+    match kind {
+      // rebuilt from the catalogs, since postgres has no equivalent of `show create table`
+      ObjectKind::Table => {
+        format!(
+          "with target as (select format('%I.%I', {schema}, {name})::regclass as oid),
+          lines as (
+            select a.attnum as position,
+              quote_ident(a.attname) || ' ' || format_type(a.atttypid, a.atttypmod)
+              || case a.attidentity
+                when 'a' then ' generated always as identity'
+                when 'd' then ' generated by default as identity'
+                else ''
+              end
+              || case
+                when a.attgenerated = 's' then ' generated always as (' || pg_get_expr(d.adbin, d.adrelid) || ') stored'
+                when d.adbin is not null then ' default ' || pg_get_expr(d.adbin, d.adrelid)
+                else ''
+              end
+              || case when a.attnotnull then ' not null' else '' end as line
+            from pg_attribute a
+            join target t on a.attrelid = t.oid
+            left join pg_attrdef d on d.adrelid = a.attrelid and d.adnum = a.attnum
+            where a.attnum > 0 and not a.attisdropped
+            union all
+            select 10000 + row_number() over (
+                order by case c.contype when 'p' then 0 when 'u' then 1 when 'c' then 2 when 'f' then 3 else 4 end, c.conname
+              ),
+              'constraint ' || quote_ident(c.conname) || ' ' || pg_get_constraintdef(c.oid)
+            from pg_constraint c
+            join target t on c.conrelid = t.oid
+            where c.contype in ('p', 'u', 'c', 'f', 'x')
+          )
+          select 'create table ' || (select oid from target)::text || ' (' || chr(10)
+            || (select string_agg('  ' || line, ',' || chr(10) order by position) from lines)
+            || chr(10) || ');'
+            || coalesce((
+              select string_agg(chr(10) || pg_get_indexdef(i.indexrelid) || ';', '' order by i.indexrelid::regclass::text)
+              from pg_index i
+              join target t on i.indrelid = t.oid
+              where not exists (select 1 from pg_constraint c where c.conindid = i.indexrelid and c.conrelid = i.indrelid)
+            ), '')
+            || coalesce(
+              chr(10) || 'comment on table ' || (select oid from target)::text || ' is '
+              || quote_literal(obj_description((select oid from target), 'pg_class')) || ';',
+              ''
+            )
+            || coalesce((
+              select string_agg(
+                chr(10) || 'comment on column ' || t.oid::text || '.' || quote_ident(a.attname) || ' is '
+                || quote_literal(col_description(t.oid, a.attnum)) || ';',
+                '' order by a.attnum
+              )
+              from pg_attribute a
+              join target t on a.attrelid = t.oid
+              where a.attnum > 0 and not a.attisdropped and col_description(t.oid, a.attnum) is not null
+            ), '') as definition"
+        )
+      },
+      ObjectKind::View => {
+        format!(
+          "select 'create or replace view ' || format('%I.%I', {schema}, {name}) || ' as' || chr(10) || pg_get_viewdef(format('%I.%I', {schema}, {name})::regclass, true) as definition"
+        )
+      },
+      ObjectKind::MaterializedView => {
+        format!(
+          "select 'create materialized view ' || format('%I.%I', {schema}, {name}) || ' as' || chr(10) || pg_get_viewdef(format('%I.%I', {schema}, {name})::regclass, true) as definition"
+        )
+      },
+      ObjectKind::Function => {
+        format!("select pg_get_functiondef((format('%I.', {schema}) || {name})::regprocedure) as definition")
+      },
+      ObjectKind::Sequence => {
+        format!(
+          "select format('create sequence %I.%I as %s increment by %s minvalue %s maxvalue %s start with %s cache %s%s;',
+            schemaname, sequencename, data_type, increment_by, min_value, max_value, start_value, cache_size,
+            case when cycle then ' cycle' else '' end) as definition, last_value
+            from pg_sequences
+            where schemaname = {schema} and sequencename = {name}"
+        )
+      },
+      ObjectKind::Type => {
+        format!(
+          "select case t.typtype
+              when 'e' then format('create type %I.%I as enum (%s);', n.nspname, t.typname,
+                (select string_agg(quote_literal(e.enumlabel), ', ' order by e.enumsortorder) from pg_enum e where e.enumtypid = t.oid))
+              when 'c' then format('create type %I.%I as (%s);', n.nspname, t.typname,
+                (select string_agg(format('%I %s', a.attname, format_type(a.atttypid, a.atttypmod)), ', ' order by a.attnum)
+                  from pg_attribute a where a.attrelid = t.typrelid and a.attnum > 0 and not a.attisdropped))
+              when 'd' then format('create domain %I.%I as %s%s%s;', n.nspname, t.typname, format_type(t.typbasetype, t.typtypmod),
+                case when t.typnotnull then ' not null' else '' end,
+                coalesce((select ' ' || string_agg(pg_get_constraintdef(c.oid, true), ' ') from pg_constraint c where c.contypid = t.oid), ''))
+              when 'r' then format('create type %I.%I as range (subtype = %s);', n.nspname, t.typname,
+                (select format_type(r.rngsubtype, null) from pg_range r where r.rngtypid = t.oid))
+              else format('-- %I.%I is a base type', n.nspname, t.typname)
+            end as definition
+            from pg_type t
+            join pg_namespace n on n.oid = t.typnamespace
+            where n.nspname = {schema} and t.typname = {name}"
+        )
+      },
+      ObjectKind::Trigger => {
+        format!(
+          "select pg_get_triggerdef(t.oid, true) as definition
+            from pg_trigger t
+            join pg_class c on c.oid = t.tgrelid
+            join pg_namespace n on n.oid = c.relnamespace
+            where n.nspname = {schema} and t.tgname = {name} and c.relname = {table}"
+        )
+      },
+      ObjectKind::Extension => {
+        format!(
+          "select format('create extension %I with schema %I version %L;', e.extname, n.nspname, e.extversion) as definition
+            from pg_extension e
+            join pg_namespace n on n.oid = e.extnamespace
+            where e.extname = {name}"
+        )
+      },
+    }
+  }
+
+  fn preview_dependencies_query(kind: ObjectKind, schema: &str, name: &str, table: Option<&str>) -> String {
+    let table = quote_literal(table.unwrap_or_default());
+    let (schema, name) = (quote_literal(schema), quote_literal(name));
+    // the catalog and oid that identify the object in pg_depend
+    let target = match kind {
+      ObjectKind::Table | ObjectKind::View | ObjectKind::MaterializedView | ObjectKind::Sequence => {
+        format!(
+          "select 'pg_class'::regclass::oid as classid, format('%I.%I', {schema}, {name})::regclass::oid as objid"
+        )
+      },
+      ObjectKind::Function => {
+        format!(
+          "select 'pg_proc'::regclass::oid as classid, (format('%I.', {schema}) || {name})::regprocedure::oid as objid"
+        )
+      },
+      ObjectKind::Type => {
+        format!("select 'pg_type'::regclass::oid as classid, format('%I.%I', {schema}, {name})::regtype::oid as objid")
+      },
+      ObjectKind::Trigger => {
+        format!(
+          "select 'pg_trigger'::regclass::oid as classid, t.oid as objid
+            from pg_trigger t
+            join pg_class c on c.oid = t.tgrelid
+            join pg_namespace n on n.oid = c.relnamespace
+            where n.nspname = {schema} and t.tgname = {name} and c.relname = {table}"
+        )
+      },
+      ObjectKind::Extension => {
+        format!("select 'pg_extension'::regclass::oid as classid, e.oid as objid from pg_extension e where e.extname = {name}")
+      },
+    };
+    // a view's dependencies are recorded against its rewrite rule rather than the view itself
+    format!(
+      "with target as ({target}),
+        objects as (
+          select classid, objid from target
+          union all
+          select 'pg_rewrite'::regclass::oid, r.oid from pg_rewrite r join target on r.ev_class = target.objid
+            where target.classid = 'pg_class'::regclass::oid
+        )
+        select distinct 'depends on' as direction, pg_describe_object(d.refclassid, d.refobjid, 0) as object
+          from pg_depend d
+          join objects o on d.classid = o.classid and d.objid = o.objid
+          where d.deptype in ('n', 'a', 'e')
+          and d.refclassid != 'pg_namespace'::regclass::oid
+          and not exists (select 1 from objects s where s.classid = d.refclassid and s.objid = d.refobjid)
+        union
+        select distinct 'required by', pg_describe_object(d.classid, d.objid, 0)
+          from pg_depend d
+          join objects o on d.refclassid = o.classid and d.refobjid = o.objid
+          where d.deptype in ('n', 'a', 'e')
+          and not exists (select 1 from objects s where s.classid = d.classid and s.objid = d.objid)
+        order by 1, 2"
+    )
+  }
+
+  fn preview_rows_query(schema: &str, table: &str, preview: &RowsPreview, limit: usize) -> String {
+    let name = format!("{}.{}", quote_identifier('"', schema), quote_identifier('"', table));
+    format!("select * from {}{}", name, super::preview_rows_clauses('"', preview, limit))
   }
 
   fn preview_columns_query(schema: &str, table: &str) -> String {
     format!(
-      "select column_name, * from information_schema.columns where table_schema = '{}' and table_name = '{}'",
-      schema, table
+      "select column_name, * from information_schema.columns where table_schema = {} and table_name = {}",
+      quote_literal(schema),
+      quote_literal(table)
+    )
+  }
+
+  fn preview_column_details_query(schema: &str, table: &str) -> String {
+    format!(
+      "select c.column_name,
+        case when c.data_type in ('USER-DEFINED', 'ARRAY') then c.udt_name else c.data_type end as data_type,
+        c.is_nullable,
+        coalesce(c.column_default, '') as column_default,
+        (
+          select concat_ws(',',
+            case when bool_or(tc.constraint_type = 'PRIMARY KEY') then 'PK' end,
+            case when bool_or(tc.constraint_type = 'FOREIGN KEY') then 'FK' end
+          )
+          from information_schema.key_column_usage k
+          join information_schema.table_constraints tc
+            using (constraint_schema, constraint_name, table_schema, table_name)
+          where k.table_schema = c.table_schema and k.table_name = c.table_name and k.column_name = c.column_name
+        ) as keys
+        from information_schema.columns c
+        where c.table_schema = {} and c.table_name = {}
+        order by c.ordinal_position",
+      quote_literal(schema),
+      quote_literal(table)
+    )
+  }
+
+  fn preview_all_columns_query() -> String {
+    "select table_schema, table_name, column_name, udt_name
+      from information_schema.columns
+      where table_schema != 'pg_catalog'
+      and table_schema != 'information_schema'
+      order by table_schema, table_name, ordinal_position"
+      .to_owned()
+  }
+
+  fn preview_distinct_values_query(schema: &str, table: &str, column: &str) -> String {
+    format!(
+      "select {}, count(*) as count from {}.{} group by 1 order by 2 desc, 1 limit 100",
+      quote_identifier('"', column),
+      quote_identifier('"', schema),
+      quote_identifier('"', table)
     )
   }
 
   fn preview_constraints_query(schema: &str, table: &str) -> String {
     format!(
-      "select constraint_name, * from information_schema.table_constraints where table_schema = '{}' and table_name = '{}'",
-      schema, table
+      "select constraint_name, * from information_schema.table_constraints where table_schema = {} and table_name = {}",
+      quote_literal(schema),
+      quote_literal(table)
     )
   }
 
   fn preview_indexes_query(schema: &str, table: &str) -> String {
-    format!("select indexname, indexdef, * from pg_indexes where schemaname = '{}' and tablename = '{}'", schema, table)
+    format!(
+      "select indexname, indexdef, * from pg_indexes where schemaname = {} and tablename = {}",
+      quote_literal(schema),
+      quote_literal(table)
+    )
   }
 
   fn preview_policies_query(schema: &str, table: &str) -> String {
-    format!("select * from pg_policies where schemaname = '{}' and tablename = '{}'", schema, table)
+    format!(
+      "select * from pg_policies where schemaname = {} and tablename = {}",
+      quote_literal(schema),
+      quote_literal(table)
+    )
+  }
+
+  fn preview_triggers_query(schema: &str, table: &str) -> String {
+    format!(
+      "select t.tgname as trigger_name,
+        case t.tgenabled when 'D' then 'disabled' when 'R' then 'replica' when 'A' then 'always' else 'enabled' end as enabled,
+        pg_get_triggerdef(t.oid, true) as definition
+        from pg_trigger t
+        where t.tgrelid = format('%I.%I', {}, {})::regclass
+        and not t.tgisinternal
+        order by t.tgname",
+      quote_literal(schema),
+      quote_literal(table)
+    )
+  }
+
+  fn preview_grants_query(schema: &str, table: &str) -> String {
+    format!(
+      "select grantee, privilege_type, is_grantable, grantor
+        from information_schema.role_table_grants
+        where table_schema = {} and table_name = {}
+        order by grantee, privilege_type",
+      quote_literal(schema),
+      quote_literal(table)
+    )
+  }
+
+  fn preview_statistics_query(schema: &str, table: &str) -> String {
+    format!(
+      "select seq_scan, seq_tup_read, idx_scan, idx_tup_fetch, n_live_tup, n_dead_tup,
+        n_tup_ins, n_tup_upd, n_tup_del, n_mod_since_analyze,
+        last_vacuum, last_autovacuum, last_analyze, last_autoanalyze,
+        vacuum_count, autovacuum_count, analyze_count, autoanalyze_count
+        from pg_stat_user_tables
+        where schemaname = {} and relname = {}",
+      quote_literal(schema),
+      quote_literal(table)
+    )
+  }
+
+  fn preview_partitions_query(schema: &str, table: &str) -> String {
+    format!(
+      "select c.oid::regclass::text as partition_name,
+        pg_get_expr(c.relpartbound, c.oid) as bound,
+        pg_size_pretty(pg_total_relation_size(c.oid)) as size
+        from pg_inherits i
+        join pg_class c on c.oid = i.inhrelid
+        where i.inhparent = format('%I.%I', {}, {})::regclass
+        order by 1",
+      quote_literal(schema),
+      quote_literal(table)
+    )
+  }
+
+  fn preview_referencing_tables_query(schema: &str, table: &str) -> String {
+    format!(
+      "select c.conrelid::regclass::text as referencing_table, c.conname as constraint_name,
+        pg_get_constraintdef(c.oid) as definition
+        from pg_constraint c
+        where c.contype = 'f'
+        and c.confrelid = format('%I.%I', {}, {})::regclass
+        order by 1, 2",
+      quote_literal(schema),
+      quote_literal(table)
+    )
+  }
+
+  fn preview_foreign_keys_query(schema: &str, table: &str) -> String {
+    foreign_keys_query(&format!(
+      "format('%I.%I', {}, {})::regclass in (c.conrelid, c.confrelid)",
+      quote_literal(schema),
+      quote_literal(table)
+    ))
+  }
+
+  fn preview_schema_foreign_keys_query(schema: &str) -> String {
+    foreign_keys_query(&format!("ns.nspname = {}", quote_literal(schema)))
+  }
+
+  fn table_actions() -> Vec<TableAction> {
+    vec![
+      TableAction::Count,
+      TableAction::Truncate,
+      TableAction::Drop,
+      TableAction::Rename(String::new()),
+      TableAction::Vacuum,
+      TableAction::Analyze,
+    ]
+  }
+
+  fn table_action_query(action: &TableAction, schema: &str, table: &str) -> Option<String> {
+    let name = format!("{}.{}", quote_identifier('"', schema), quote_identifier('"', table));
+    match action {
+      TableAction::Count => Some(format!("select count(*) from {}", name)),
+      TableAction::Truncate => Some(format!("truncate table {}", name)),
+      TableAction::Drop => Some(format!("drop table {}", name)),
+      TableAction::Rename(new_name) if new_name.trim().is_empty() => None,
+      TableAction::Rename(new_name) => {
+        Some(format!("alter table {} rename to {}", name, quote_identifier('"', new_name)))
+      },
+      TableAction::Vacuum => Some(format!("vacuum {}", name)),
+      TableAction::Analyze => Some(format!("analyze {}", name)),
+      TableAction::Optimize | TableAction::Reindex => None,
+    }
   }
 }
 
@@ -464,9 +851,12 @@ mod tests {
   use std::sync::Arc;
 
   use sqlparser::{ast::Statement, dialect::PostgreSqlDialect, parser::Parser};
+  use strum::IntoEnumIterator;
 
   use super::*;
-  use crate::database::{get_execution_type, get_first_query, DbError, ExecutionType};
+  use crate::database::{
+    get_execution_type, get_first_query, maintenance_statement, DatabaseQueries, DbError, ExecutionType,
+  };
 
   #[test]
   fn test_get_first_query() {
@@ -570,4 +960,76 @@ mod tests {
       assert_eq!(get_execution_type(statement, false), expected, "Failed for query: {}", query);
     }
   }
+
+  #[test]
+  fn test_foreign_keys_query_postgres() {
+    let query = <Postgres as DatabaseQueries>::preview_foreign_keys_query("app", "orders");
+    assert!(get_first_query(query.clone(), &PostgreSqlDialect {}).is_ok(), "Failed for query: {}", query);
+    let query = <Postgres as DatabaseQueries>::preview_schema_foreign_keys_query("app");
+    assert!(get_first_query(query.clone(), &PostgreSqlDialect {}).is_ok(), "Failed for query: {}", query);
+  }
+
+  #[test]
+  fn test_quoted_names_postgres() {
+    let dialect = PostgreSqlDialect {};
+    let (schema, name) = ("app", "o'brien\\");
+    for kind in ObjectKind::iter() {
+      for query in [
+        <Postgres as DatabaseQueries>::preview_definition_query(kind, schema, name, Some(name)),
+        <Postgres as DatabaseQueries>::preview_dependencies_query(kind, schema, name, Some(name)),
+      ] {
+        assert!(get_first_query(query.clone(), &dialect).is_ok(), "Failed for query: {}", query);
+      }
+    }
+    for query in [
+      <Postgres as DatabaseQueries>::preview_rows_query(schema, name, &RowsPreview::default(), 10),
+      <Postgres as DatabaseQueries>::preview_columns_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_column_details_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_distinct_values_query(schema, name, "we\"ird`"),
+      <Postgres as DatabaseQueries>::preview_constraints_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_indexes_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_policies_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_triggers_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_grants_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_statistics_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_partitions_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_referencing_tables_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_foreign_keys_query(schema, name),
+      <Postgres as DatabaseQueries>::preview_schema_foreign_keys_query(name),
+    ] {
+      assert!(get_first_query(query.clone(), &dialect).is_ok(), "Failed for query: {}", query);
+    }
+  }
+
+  #[test]
+  fn test_table_action_queries_postgres() {
+    let dialect = PostgreSqlDialect {};
+    let test_cases = vec![
+      (TableAction::Count, Some(ExecutionType::Normal)),
+      (TableAction::Truncate, Some(ExecutionType::Confirm)),
+      (TableAction::Drop, Some(ExecutionType::Confirm)),
+      (TableAction::Rename("archived_orders".to_owned()), Some(ExecutionType::Confirm)),
+      (TableAction::Vacuum, None),
+      (TableAction::Analyze, Some(ExecutionType::Normal)),
+    ];
+
+    for (action, expected) in test_cases {
+      let query = <Postgres as DatabaseQueries>::table_action_query(&action, "app", "orders").unwrap();
+      match get_first_query(query.clone(), &dialect) {
+        Ok((_, statement)) => {
+          assert_eq!(Some(get_execution_type(statement, false)), expected, "Failed for query: {}", query)
+        },
+        Err(_) => assert!(expected.is_none() && maintenance_statement(&query).is_some(), "Failed for query: {}", query),
+      }
+    }
+
+    let rename = |new_name: &str| {
+      <Postgres as DatabaseQueries>::table_action_query(&TableAction::Rename(new_name.to_owned()), "app", "we\"ird")
+    };
+    assert_eq!(
+      rename("x\"; drop table y; --"),
+      Some("alter table \"app\".\"we\"\"ird\" rename to \"x\"\"; drop table y; --\"".to_owned())
+    );
+    assert_eq!(rename("  "), None);
+  }
 }
@@ -1,9 +1,4 @@
-use std::{
-  fmt::Write,
-  io::{self, Write as _},
-  str::FromStr,
-  string::String,
-};
+use std::{fmt::Write, net::SocketAddr, str::FromStr, string::String};
 
 use serde_json;
 use sqlx::{
@@ -16,36 +11,72 @@ use sqlx::{
   Column, Database, Row, ValueRef,
 };
 
-use super::{vec_to_string, Value};
-use crate::cli::Cli;
+use super::{quote_identifier, quote_literal, vec_to_string, ObjectKind, Value};
+use crate::{
+  action::{RowsPreview, TableAction},
+  cli::Cli,
+};
 
 impl super::BuildConnectionOptions for sqlx::Sqlite {
   fn build_connection_opts(args: Cli) -> color_eyre::eyre::Result<<Self::Connection as sqlx::Connection>::Options> {
     match args.connection_url {
       Some(url) => Ok(SqliteConnectOptions::from_str(&url)?),
       None => {
-        let filename = if let Some(database) = args.database {
-          database
-        } else {
-          let mut database = String::new();
-          print!("database file path (or ':memory:'): ");
-          io::stdout().flush()?;
-          io::stdin().read_line(&mut database)?;
-          let database = database.trim().to_string();
-          if database.is_empty() {
-            return Err(color_eyre::eyre::Report::msg("Database file path is required"));
-          }
-          database
-        };
+        let filename = args.database.ok_or_else(|| color_eyre::eyre::Report::msg("Database file path is required"))?;
 
         let opts = SqliteConnectOptions::new().filename(&filename);
         Ok(opts)
       },
     }
   }
+
+  fn tunnel_target(_opts: &SqliteConnectOptions) -> color_eyre::eyre::Result<(String, u16)> {
+    Err(color_eyre::eyre::Report::msg("SSH tunnels are not supported for SQLite"))
+  }
+
+  fn through_tunnel(opts: SqliteConnectOptions, _local_addr: SocketAddr) -> SqliteConnectOptions {
+    opts
+  }
+
+  // attached databases are queried through the same connection, so there's nothing to switch to
+  fn with_database(_opts: SqliteConnectOptions, _database: &str) -> Option<SqliteConnectOptions> {
+    None
+  }
+
+  fn database_name(_opts: &SqliteConnectOptions) -> Option<String> {
+    None
+  }
+}
+
+// lowercases sql and pads every identifier with spaces, so names can be matched as whole words
+fn words(sql: &str) -> String {
+  ["(", ")", ",", ";", ".", "\"", "`", "[", "]", "char(9)", "char(10)", "char(13)"].iter().fold(
+    format!("' ' || lower({sql}) || ' '"),
+    |acc, c| {
+      let c = if c.starts_with("char") { c.to_string() } else { format!("'{c}'") };
+      format!("replace({acc}, {c}, ' ')")
+    },
+  )
+}
+
+fn foreign_keys_query(condition: &str) -> String {
+  format!(
+    "select m.name || '_fk_' || f.id as constraint_name, '' as table_schema, m.name as table_name,
+      f.\"from\" as column_name, '' as referenced_table_schema, f.\"table\" as referenced_table_name,
+      coalesce(f.\"to\", (select p.name from pragma_table_info(f.\"table\") p where p.pk = f.seq + 1))
+      as referenced_column_name
+      from sqlite_master m, pragma_foreign_key_list(m.name) f
+      where m.type = 'table' and {}
+      order by m.name, f.id, f.seq",
+    condition
+  )
 }
 
 impl super::DatabaseQueries for Sqlite {
+  fn preview_databases_query() -> String {
+    "select name, name = 'main' as current from pragma_database_list order by seq".to_owned()
+  }
+
   fn preview_tables_query() -> String {
     "select '' as table_schema, name as table_name
       from sqlite_master
@@ -55,25 +86,220 @@ impl super::DatabaseQueries for Sqlite {
       .to_owned()
   }
 
-  fn preview_rows_query(_schema: &str, table: &str) -> String {
-    format!("select * from \"{}\" limit 100", table)
+  // the leaf cells of a table's b-tree are its rows, so this is an exact count
+  fn preview_table_sizes_query() -> String {
+    "select '' as table_schema, m.name as table_name,
+      sum(case when d.pagetype = 'leaf' then d.ncell else 0 end) as row_estimate,
+      sum(d.pgsize) as size
+      from sqlite_master m
+      join dbstat d on d.name = m.name
+      where m.type = 'table'
+      group by m.name"
+      .to_owned()
+  }
+
+  fn preview_schema_objects_query() -> String {
+    "select '' as object_schema, type as object_kind, name as object_name
+      from sqlite_master
+      where type in ('view', 'trigger')
+      and name not like 'sqlite_%'
+      order by type, name asc"
+      .to_owned()
+  }
+
+  fn preview_definition_query(kind: ObjectKind, _schema: &str, name: &str, _table: Option<&str>) -> String {
+    match kind {
+      // the table along with its indexes, which sqlite keeps as separate entries
+      ObjectKind::Table => {
+        format!(
+          "select group_concat(sql || ';', char(10)) as definition
+            from (
+              select sql from sqlite_master
+              where tbl_name = {} and type in ('table', 'index') and sql is not null
+              order by case type when 'table' then 0 else 1 end, name
+            )",
+          quote_literal(name)
+        )
+      },
+      ObjectKind::View | ObjectKind::Trigger => {
+        format!("select sql as definition from sqlite_master where name = {}", quote_literal(name))
+      },
+      kind => format!("select 'SQLite does not support {}' as message", kind.label()),
+    }
+  }
+
+  // sqlite doesn't record dependencies, so apart from foreign keys they're found by looking for
+  // the name as a whole word in each object's sql
+  fn preview_dependencies_query(kind: ObjectKind, _schema: &str, name: &str, _table: Option<&str>) -> String {
+    let name = quote_literal(name);
+    let mentions = |sql: &str, name: &str| format!("instr({}, ' ' || lower({}) || ' ') > 0", words(sql), name);
+    match kind {
+      ObjectKind::Table | ObjectKind::View => {
+        format!(
+          "select 'depends on' as direction, 'table ' || f.\"table\" as object
+            from pragma_foreign_key_list({name}) f
+            union
+            select 'depends on', m.type || ' ' || m.name
+            from sqlite_master m
+            where m.type in ('table', 'view')
+            and m.name != {name}
+            and {}
+            union
+            select 'required by', 'table ' || m.name
+            from sqlite_master m, pragma_foreign_key_list(m.name) f
+            where m.type = 'table' and f.\"table\" = {name}
+            union
+            select 'required by', m.type || ' ' || m.name
+            from sqlite_master m
+            where m.type in ('view', 'trigger')
+            and m.name != {name}
+            and (m.tbl_name = {name} or {})",
+          mentions(&format!("(select sql from sqlite_master where type = 'view' and name = {name})"), "m.name"),
+          mentions("m.sql", &name),
+        )
+      },
+      ObjectKind::Trigger => {
+        format!(
+          "select 'depends on' as direction, 'table ' || tbl_name as object
+            from sqlite_master
+            where type = 'trigger' and name = {name}"
+        )
+      },
+      kind => format!("select 'SQLite does not support {}' as message", kind.label()),
+    }
+  }
+
+  fn preview_rows_query(_schema: &str, table: &str, preview: &RowsPreview, limit: usize) -> String {
+    format!("select * from {}{}", quote_identifier('"', table), super::preview_rows_clauses('"', preview, limit))
   }
 
   fn preview_columns_query(_schema: &str, table: &str) -> String {
-    format!("pragma table_info(\"{}\")", table)
+    format!("pragma table_info({})", quote_identifier('"', table))
+  }
+
+  fn preview_column_details_query(_schema: &str, table: &str) -> String {
+    let table = quote_literal(table);
+    format!(
+      "select c.name as column_name, c.type as data_type,
+        case when c.\"notnull\" = 1 then 'NO' else 'YES' end as is_nullable,
+        coalesce(c.dflt_value, '') as column_default,
+        ltrim(
+          case when c.pk > 0 then 'PK' else '' end
+          || case when exists(select 1 from pragma_foreign_key_list({table}) f where f.\"from\" = c.name)
+            then ',FK' else '' end,
+          ','
+        ) as keys
+        from pragma_table_info({table}) c
+        order by c.cid"
+    )
+  }
+
+  fn preview_all_columns_query() -> String {
+    "select '' as table_schema, m.name as table_name, c.name as column_name, c.type as column_type
+      from sqlite_master m, pragma_table_info(m.name) c
+      where m.type = 'table'
+      and m.name not like 'sqlite_%'
+      order by m.name, c.cid"
+      .to_owned()
+  }
+
+  fn preview_distinct_values_query(_schema: &str, table: &str, column: &str) -> String {
+    format!(
+      "select {}, count(*) as count from {} group by 1 order by 2 desc, 1 limit 100",
+      quote_identifier('"', column),
+      quote_identifier('"', table)
+    )
   }
 
   fn preview_constraints_query(_schema: &str, table: &str) -> String {
-    format!("pragma foreign_key_list(\"{}\")", table)
+    format!("pragma foreign_key_list({})", quote_identifier('"', table))
   }
 
   fn preview_indexes_query(_schema: &str, table: &str) -> String {
-    format!("pragma index_list(\"{}\")", table)
+    format!("pragma index_list({})", quote_identifier('"', table))
   }
 
   fn preview_policies_query(_schema: &str, _table: &str) -> String {
     "select 'SQLite does not support row-level security policies' as message".to_owned()
   }
+
+  fn preview_triggers_query(_schema: &str, table: &str) -> String {
+    format!(
+      "select name as trigger_name, sql as definition
+        from sqlite_master
+        where type = 'trigger' and tbl_name = {}
+        order by name",
+      quote_literal(table)
+    )
+  }
+
+  fn preview_grants_query(_schema: &str, _table: &str) -> String {
+    "select 'SQLite does not support grants' as message".to_owned()
+  }
+
+  // sqlite doesn't count scans, so this is how the table and its indexes are stored
+  fn preview_statistics_query(_schema: &str, table: &str) -> String {
+    format!(
+      "select d.name, m.type, count(*) as pages, sum(d.ncell) as cells, sum(d.pgsize) as size,
+        sum(d.unused) as unused_bytes
+        from dbstat d
+        join sqlite_master m on m.name = d.name
+        where m.tbl_name = {}
+        group by d.name, m.type
+        order by m.type desc, d.name",
+      quote_literal(table)
+    )
+  }
+
+  fn preview_partitions_query(_schema: &str, _table: &str) -> String {
+    "select 'SQLite does not support partitions' as message".to_owned()
+  }
+
+  fn preview_referencing_tables_query(_schema: &str, table: &str) -> String {
+    format!(
+      "select m.name as referencing_table, f.\"from\" as column_name, f.\"to\" as referenced_column_name
+        from sqlite_master m, pragma_foreign_key_list(m.name) f
+        where m.type = 'table' and f.\"table\" = {}
+        order by 1",
+      quote_literal(table)
+    )
+  }
+
+  // sqlite's foreign keys have no names, and leave out the referenced columns when they're the
+  // primary key
+  fn preview_foreign_keys_query(_schema: &str, table: &str) -> String {
+    foreign_keys_query(&format!("(m.name = {0} or f.\"table\" = {0})", quote_literal(table)))
+  }
+
+  fn preview_schema_foreign_keys_query(_schema: &str) -> String {
+    foreign_keys_query("m.name not like 'sqlite_%'")
+  }
+
+  fn table_actions() -> Vec<TableAction> {
+    vec![
+      TableAction::Count,
+      TableAction::Drop,
+      TableAction::Rename(String::new()),
+      TableAction::Vacuum,
+      TableAction::Reindex,
+    ]
+  }
+
+  // vacuum works on the whole database file
+  fn table_action_query(action: &TableAction, _schema: &str, table: &str) -> Option<String> {
+    let name = quote_identifier('"', table);
+    match action {
+      TableAction::Count => Some(format!("select count(*) from {}", name)),
+      TableAction::Drop => Some(format!("drop table {}", name)),
+      TableAction::Rename(new_name) if new_name.trim().is_empty() => None,
+      TableAction::Rename(new_name) => {
+        Some(format!("alter table {} rename to {}", name, quote_identifier('"', new_name)))
+      },
+      TableAction::Vacuum => Some("vacuum".to_owned()),
+      TableAction::Reindex => Some(format!("reindex {}", name)),
+      TableAction::Truncate | TableAction::Analyze | TableAction::Optimize => None,
+    }
+  }
 }
 
 impl super::HasRowsAffected for SqliteQueryResult {
@@ -224,9 +450,12 @@ mod tests {
     dialect::SQLiteDialect,
     parser::{Parser, ParserError},
   };
+  use strum::IntoEnumIterator;
 
   use super::*;
-  use crate::database::{get_execution_type, get_first_query, DbError, ExecutionType};
+  use crate::database::{
+    get_execution_type, get_first_query, maintenance_statement, DatabaseQueries, DbError, ExecutionType,
+  };
 
   #[test]
   fn test_get_first_query_sqlite() {
@@ -326,4 +555,75 @@ mod tests {
       assert_eq!(get_execution_type(statement, false), expected, "Failed for query: {}", query);
     }
   }
+
+  #[test]
+  fn test_foreign_keys_query_sqlite() {
+    let query = <Sqlite as DatabaseQueries>::preview_foreign_keys_query("app", "orders");
+    assert!(get_first_query(query.clone(), &SQLiteDialect {}).is_ok(), "Failed for query: {}", query);
+    let query = <Sqlite as DatabaseQueries>::preview_schema_foreign_keys_query("");
+    assert!(get_first_query(query.clone(), &SQLiteDialect {}).is_ok(), "Failed for query: {}", query);
+  }
+
+  #[test]
+  fn test_quoted_names_sqlite() {
+    let dialect = SQLiteDialect {};
+    let (schema, name) = ("", "o'brien\\");
+    for kind in ObjectKind::iter() {
+      for query in [
+        <Sqlite as DatabaseQueries>::preview_definition_query(kind, schema, name, None),
+        <Sqlite as DatabaseQueries>::preview_dependencies_query(kind, schema, name, None),
+      ] {
+        assert!(get_first_query(query.clone(), &dialect).is_ok(), "Failed for query: {}", query);
+      }
+    }
+    for query in [
+      <Sqlite as DatabaseQueries>::preview_rows_query(schema, name, &RowsPreview::default(), 10),
+      <Sqlite as DatabaseQueries>::preview_columns_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_column_details_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_distinct_values_query(schema, name, "we\"ird`"),
+      <Sqlite as DatabaseQueries>::preview_constraints_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_indexes_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_policies_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_triggers_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_grants_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_statistics_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_partitions_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_referencing_tables_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_foreign_keys_query(schema, name),
+      <Sqlite as DatabaseQueries>::preview_schema_foreign_keys_query(name),
+    ] {
+      assert!(get_first_query(query.clone(), &dialect).is_ok(), "Failed for query: {}", query);
+    }
+  }
+
+  #[test]
+  fn test_table_action_queries_sqlite() {
+    let dialect = SQLiteDialect {};
+    let test_cases = vec![
+      (TableAction::Count, Some(ExecutionType::Normal)),
+      (TableAction::Drop, Some(ExecutionType::Confirm)),
+      (TableAction::Rename("archived_orders".to_owned()), Some(ExecutionType::Confirm)),
+      (TableAction::Vacuum, None),
+      (TableAction::Reindex, None),
+    ];
+
+    for (action, expected) in test_cases {
+      let query = <Sqlite as DatabaseQueries>::table_action_query(&action, "app", "orders").unwrap();
+      match get_first_query(query.clone(), &dialect) {
+        Ok((_, statement)) => {
+          assert_eq!(Some(get_execution_type(statement, false)), expected, "Failed for query: {}", query)
+        },
+        Err(_) => assert!(expected.is_none() && maintenance_statement(&query).is_some(), "Failed for query: {}", query),
+      }
+    }
+
+    let rename = |new_name: &str| {
+      <Sqlite as DatabaseQueries>::table_action_query(&TableAction::Rename(new_name.to_owned()), "", "we\"ird")
+    };
+    assert_eq!(
+      rename("x\"; drop table y; --"),
+      Some("alter table \"we\"\"ird\" rename to \"x\"\"; drop table y; --\"".to_owned())
+    );
+    assert_eq!(rename("  "), None);
+  }
 }
@@ -0,0 +1,432 @@
+use std::{
+  fs,
+  io::{self, Write},
+  path::Path,
+  time::{Duration, Instant},
+};
+
+use color_eyre::eyre::Result;
+use sqlparser::{
+  dialect::Dialect,
+  tokenizer::{Location, Token, Tokenizer},
+};
+use sqlx::{Connection, Database, Executor, MySql, Postgres, Sqlite};
+
+use crate::{
+  cli::{Cli, Driver, OnError, OutputFormat},
+  database::{
+    self, get_dialect, get_first_query, header_to_vec, row_to_values, BuildConnectionOptions, DbError, HasRowsAffected,
+    Rows, Value, ValueParser,
+  },
+  session::{self, ConnectionRequest},
+};
+
+pub const EXIT_QUERY_ERROR: i32 = 1;
+pub const EXIT_CONNECTION_ERROR: i32 = 2;
+pub const EXIT_FILE_ERROR: i32 = 3;
+
+/// Runs a single query outside of the tui and prints the results to stdout.
+/// Returns the process exit code.
+pub async fn run(args: Cli, request: ConnectionRequest, query: String, format: OutputFormat) -> i32 {
+  match request.driver {
+    Driver::Postgres => run_with::<Postgres>(args, request, query, format).await,
+    Driver::Mysql => run_with::<MySql>(args, request, query, format).await,
+    Driver::Sqlite => run_with::<Sqlite>(args, request, query, format).await,
+  }
+}
+
+async fn run_with<DB>(args: Cli, request: ConnectionRequest, query: String, format: OutputFormat) -> i32
+where
+  DB: Database + BuildConnectionOptions + ValueParser,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  // the tunnel has to outlive the pool
+  let (connection_opts, _tunnel) = match session::connection_options::<DB>(args, &request).await {
+    Ok(opts) => opts,
+    Err(e) => {
+      eprintln!("error: {e}");
+      return EXIT_CONNECTION_ERROR;
+    },
+  };
+  let pool = match database::init_pool::<DB>(connection_opts).await {
+    Ok(pool) => pool,
+    Err(e) => {
+      eprintln!("error: failed to connect to {}: {e}", request.name);
+      return EXIT_CONNECTION_ERROR;
+    },
+  };
+  // the values are kept whole so that json output can tell nulls apart from text
+  let results = match get_first_query(query, get_dialect(DB::NAME).as_ref()) {
+    Ok((first_query, _)) => {
+      database::collect_rows::<DB, _>(sqlx::raw_sql(&first_query).fetch_many(&pool), row_to_values::<DB>).await
+    },
+    Err(e) => Err(e),
+  };
+  pool.close().await;
+  match results {
+    Ok(rows) => {
+      match write_rows(&rows, format, &mut io::stdout().lock()) {
+        Ok(()) => 0,
+        Err(e) => {
+          eprintln!("error: {e}");
+          EXIT_QUERY_ERROR
+        },
+      }
+    },
+    Err(e) => {
+      eprintln!("error: {e}");
+      EXIT_QUERY_ERROR
+    },
+  }
+}
+
+/// Runs each statement of a sql file in order, printing a line per statement.
+/// Returns the process exit code.
+pub async fn run_file(
+  args: Cli,
+  request: ConnectionRequest,
+  file: &Path,
+  single_transaction: bool,
+  on_error: OnError,
+) -> i32 {
+  let sql = match fs::read_to_string(file) {
+    Ok(sql) => sql,
+    Err(e) => {
+      eprintln!("error: failed to read {}: {e}", file.display());
+      return EXIT_FILE_ERROR;
+    },
+  };
+  match request.driver {
+    Driver::Postgres => run_file_with::<Postgres>(args, request, sql, single_transaction, on_error).await,
+    Driver::Mysql => run_file_with::<MySql>(args, request, sql, single_transaction, on_error).await,
+    Driver::Sqlite => run_file_with::<Sqlite>(args, request, sql, single_transaction, on_error).await,
+  }
+}
+
+async fn run_file_with<DB>(
+  args: Cli,
+  request: ConnectionRequest,
+  sql: String,
+  single_transaction: bool,
+  on_error: OnError,
+) -> i32
+where
+  DB: Database + BuildConnectionOptions + ValueParser,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  let statements = match split_statements(&sql, get_dialect(DB::NAME).as_ref()) {
+    Ok(statements) => statements,
+    Err(e) => {
+      eprintln!("error: {e}");
+      return EXIT_QUERY_ERROR;
+    },
+  };
+  let (connection_opts, _tunnel) = match session::connection_options::<DB>(args, &request).await {
+    Ok(opts) => opts,
+    Err(e) => {
+      eprintln!("error: {e}");
+      return EXIT_CONNECTION_ERROR;
+    },
+  };
+  // a single connection, so session state (e.g. `set search_path`) carries over between statements
+  let mut connection = match DB::Connection::connect_with(&connection_opts).await {
+    Ok(connection) => connection,
+    Err(e) => {
+      eprintln!("error: failed to connect to {}: {e}", request.name);
+      return EXIT_CONNECTION_ERROR;
+    },
+  };
+  let total = statements.len();
+  let (succeeded, failed) = if single_transaction {
+    let mut tx = match connection.begin().await {
+      Ok(tx) => tx,
+      Err(e) => {
+        eprintln!("error: failed to start transaction: {e}");
+        return EXIT_QUERY_ERROR;
+      },
+    };
+    // an error aborts the whole transaction, so there's nothing left to continue with
+    let (succeeded, failed) = run_statements::<DB>(&mut tx, &statements, OnError::Stop).await;
+    println!("{total} statements: {succeeded} succeeded, {failed} failed, {} skipped", total - succeeded - failed);
+    let outcome =
+      if failed == 0 { tx.commit().await.map(|_| "committed") } else { tx.rollback().await.map(|_| "rolled back") };
+    match outcome {
+      Ok(outcome) => println!("transaction {outcome}"),
+      Err(e) => {
+        eprintln!("error: failed to end transaction: {e}");
+        return EXIT_QUERY_ERROR;
+      },
+    }
+    (succeeded, failed)
+  } else {
+    let (succeeded, failed) = run_statements::<DB>(&mut connection, &statements, on_error).await;
+    println!("{total} statements: {succeeded} succeeded, {failed} failed, {} skipped", total - succeeded - failed);
+    (succeeded, failed)
+  };
+  let _ = connection.close().await;
+  if failed == 0 {
+    0
+  } else {
+    EXIT_QUERY_ERROR
+  }
+}
+
+// returns how many statements succeeded and failed
+async fn run_statements<DB>(connection: &mut DB::Connection, statements: &[String], on_error: OnError) -> (usize, usize)
+where
+  DB: Database + ValueParser,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  let (mut succeeded, mut failed) = (0, 0);
+  for (i, statement) in statements.iter().enumerate() {
+    let start = Instant::now();
+    let result = database::query_stream::<DB>(sqlx::raw_sql(statement).fetch_many(&mut *connection)).await;
+    println!("{}", summary_line(i + 1, statements.len(), statement, &result, start.elapsed()));
+    match result {
+      Ok(_) => succeeded += 1,
+      Err(_) => {
+        failed += 1;
+        if on_error == OnError::Stop {
+          break;
+        }
+      },
+    }
+  }
+  (succeeded, failed)
+}
+
+// splits on top level semicolons, slicing each statement out of the original text. semicolons
+// inside `begin ... end` blocks, like the bodies of triggers and procedures, don't end a statement
+fn split_statements(sql: &str, dialect: &dyn Dialect) -> Result<Vec<String>> {
+  let tokens = Tokenizer::new(dialect, sql).tokenize_with_location()?;
+  // the tokenizer reports 1-based lines and columns in chars, which are turned into byte offsets
+  let line_starts: Vec<usize> = [0].into_iter().chain(sql.match_indices('\n').map(|(i, _)| i + 1)).collect();
+  let offset = |location: Location| {
+    let line_start = line_starts[(location.line as usize).saturating_sub(1)];
+    let column = (location.column as usize).saturating_sub(1);
+    sql[line_start..].char_indices().nth(column).map_or(sql.len(), |(i, _)| line_start + i)
+  };
+  let words: Vec<Option<String>> = tokens
+    .iter()
+    .map(|token| {
+      match &token.token {
+        Token::Word(word) if word.quote_style.is_none() => Some(word.value.to_uppercase()),
+        _ => None,
+      }
+    })
+    .collect();
+  // the next token that isn't whitespace or a comment
+  let next_code =
+    |i: usize| tokens[i + 1..].iter().position(|t| !matches!(t.token, Token::Whitespace(_))).map(|n| i + 1 + n);
+
+  let mut statements = vec![];
+  let mut start = 0;
+  let mut has_code = false;
+  let mut depth = 0usize;
+  for (i, token) in tokens.iter().enumerate() {
+    match &token.token {
+      Token::SemiColon if depth == 0 => {
+        if has_code {
+          statements.push(sql[start..offset(token.span.start)].trim().to_string());
+        }
+        start = offset(token.span.end);
+        has_code = false;
+      },
+      Token::Whitespace(_) => {},
+      _ => {
+        has_code = true;
+        let next = next_code(i).and_then(|n| words[n].as_deref());
+        match words[i].as_deref() {
+          // `begin;` and `begin transaction` start a transaction rather than a block
+          Some("BEGIN") => {
+            let starts_transaction = next_code(i).map_or(true, |n| tokens[n].token == Token::SemiColon)
+              || matches!(
+                next,
+                Some("TRANSACTION" | "WORK" | "TRAN" | "ISOLATION" | "READ" | "DEFERRED" | "IMMEDIATE" | "EXCLUSIVE")
+              );
+            if !starts_transaction {
+              depth += 1;
+            }
+          },
+          Some("CASE") => depth += 1,
+          // `end if`, `end loop` and the like close blocks that weren't counted
+          Some("END") if !matches!(next, Some("IF" | "LOOP" | "WHILE" | "REPEAT")) => depth = depth.saturating_sub(1),
+          _ => {},
+        }
+      },
+    }
+  }
+  if has_code {
+    statements.push(sql[start..].trim().to_string());
+  }
+  Ok(statements)
+}
+
+fn summary_line(
+  index: usize,
+  total: usize,
+  statement: &str,
+  result: &Result<Rows, DbError>,
+  elapsed: Duration,
+) -> String {
+  let preview: String = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+  let preview =
+    if preview.chars().count() > 60 { format!("{}...", preview.chars().take(57).collect::<String>()) } else { preview };
+  let outcome = match result {
+    Ok(rows) if !rows.headers.is_empty() => format!("ok ({} rows)", rows.rows.len()),
+    Ok(rows) => format!("ok ({} rows affected)", rows.rows_affected.unwrap_or_default()),
+    Err(e) => format!("failed: {e}"),
+  };
+  format!("[{index}/{total}] {preview} ... {outcome} in {}ms", elapsed.as_millis())
+}
+
+fn write_rows(rows: &Rows<Value>, format: OutputFormat, out: &mut impl Write) -> Result<()> {
+  // statements without a result set only report how many rows they touched
+  if rows.headers.is_empty() {
+    if let Some(rows_affected) = rows.rows_affected {
+      eprintln!("{rows_affected} rows affected");
+    }
+    return Ok(());
+  }
+  let headers = header_to_vec(&rows.headers);
+  let strings: Vec<Vec<String>> =
+    rows.rows.iter().map(|row| row.iter().map(|value| value.string.clone()).collect()).collect();
+  match format {
+    OutputFormat::Csv => {
+      let mut writer = csv::Writer::from_writer(out);
+      writer.write_record(&headers)?;
+      for row in strings.iter() {
+        writer.write_record(row)?;
+      }
+      writer.flush()?;
+    },
+    OutputFormat::Json => {
+      // written by hand so the keys keep the column order
+      writeln!(out, "[")?;
+      for (i, row) in rows.rows.iter().enumerate() {
+        let fields = headers
+          .iter()
+          .zip(row.iter())
+          .map(|(header, value)| {
+            let value = if value.is_null { "null".to_owned() } else { serde_json::to_string(&value.string)? };
+            Ok(format!("{}: {}", serde_json::to_string(header)?, value))
+          })
+          .collect::<Result<Vec<_>>>()?;
+        let separator = if i + 1 < rows.rows.len() { "," } else { "" };
+        writeln!(out, "  {{{}}}{separator}", fields.join(", "))?;
+      }
+      writeln!(out, "]")?;
+    },
+    OutputFormat::Table => {
+      let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+      for row in strings.iter() {
+        for (width, value) in widths.iter_mut().zip(row.iter()) {
+          *width = (*width).max(value.chars().count());
+        }
+      }
+      let format_row = |values: &[String]| {
+        values
+          .iter()
+          .zip(widths.iter())
+          .map(|(value, width)| format!("{value:<width$}"))
+          .collect::<Vec<_>>()
+          .join(" | ")
+          .trim_end()
+          .to_string()
+      };
+      writeln!(out, "{}", format_row(&headers))?;
+      writeln!(out, "{}", widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-"))?;
+      for row in strings.iter() {
+        writeln!(out, "{}", format_row(row))?;
+      }
+      writeln!(out, "({} {})", rows.rows.len(), if rows.rows.len() == 1 { "row" } else { "rows" })?;
+    },
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::database::Header;
+
+  fn value(string: &str, is_null: bool) -> Value {
+    Value { parse_error: false, is_null, string: string.to_string() }
+  }
+
+  fn rows() -> Rows<Value> {
+    Rows {
+      headers: vec![Header { name: "id".to_string(), type_name: "INT4".to_string() }, Header {
+        name: "name".to_string(),
+        type_name: "TEXT".to_string(),
+      }],
+      rows: vec![
+        vec![value("1", false), value("frog, green", false)],
+        vec![value("22", false), value("NULL", true)],
+        vec![value("333", false), value("NULL", false)],
+      ],
+      rows_affected: Some(2),
+    }
+  }
+
+  fn output(format: OutputFormat) -> String {
+    let mut out = Vec::new();
+    write_rows(&rows(), format, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn test_split_statements() {
+    let sql = "-- setup\ncreate table t (id int, note text);\ninsert into t values (1, 'a;b');\n\n;  -- trailing\nselect * from t";
+    let statements = split_statements(sql, &sqlparser::dialect::PostgreSqlDialect {}).unwrap();
+    assert_eq!(statements, vec![
+      "-- setup\ncreate table t (id int, note text)",
+      "insert into t values (1, 'a;b')",
+      "-- trailing\nselect * from t",
+    ]);
+  }
+
+  #[test]
+  fn test_split_statements_keeps_escapes() {
+    let sql = "insert into t values ('it''s ✓');\nselect 'a\\'b;c' from t;";
+    let statements = split_statements(sql, &sqlparser::dialect::MySqlDialect {}).unwrap();
+    assert_eq!(statements, vec!["insert into t values ('it''s ✓')", "select 'a\\'b;c' from t"]);
+  }
+
+  #[test]
+  fn test_split_statements_keeps_blocks() {
+    let sql = "begin;\ncreate trigger log_insert after insert on t\nbegin\n  insert into log values (new.id);\n  update t set note = case when new.id > 1 then 'b' else 'a' end where id = new.id;\nend;\ncommit;";
+    let statements = split_statements(sql, &sqlparser::dialect::SQLiteDialect {}).unwrap();
+    assert_eq!(statements, vec![
+      "begin",
+      "create trigger log_insert after insert on t\nbegin\n  insert into log values (new.id);\n  update t set note = case when new.id > 1 then 'b' else 'a' end where id = new.id;\nend",
+      "commit",
+    ]);
+  }
+
+  #[test]
+  fn test_write_rows_csv() {
+    assert_eq!(output(OutputFormat::Csv), "id,name\n1,\"frog, green\"\n22,NULL\n333,NULL\n");
+  }
+
+  #[test]
+  fn test_write_rows_json() {
+    assert_eq!(
+      output(OutputFormat::Json),
+      "[\n  {\"id\": \"1\", \"name\": \"frog, green\"},\n  {\"id\": \"22\", \"name\": null},\n  {\"id\": \"333\", \"name\": \"NULL\"}\n]\n"
+    );
+  }
+
+  #[test]
+  fn test_write_rows_table() {
+    assert_eq!(
+      output(OutputFormat::Table),
+      "id  | name\n----+------------\n1   | frog, green\n22  | NULL\n333 | NULL\n(3 rows)\n"
+    );
+  }
+}
@@ -0,0 +1,126 @@
+// fzf-style fuzzy matching. the characters of the pattern have to show up in the text in order,
+// and a match scores higher when its characters are consecutive or start words.
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CONSECUTIVE: i32 = 4;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTENSION: i32 = 1;
+
+fn is_separator(c: char) -> bool {
+  matches!(c, '_' | '.' | '-' | ' ' | '/' | '$')
+}
+
+// characters that start a word, like the `o` in `user_orders` or `userOrders`
+fn boundary_bonus(chars: &[char], i: usize) -> i32 {
+  match i.checked_sub(1).map(|prev| chars[prev]) {
+    None => BONUS_BOUNDARY,
+    Some(prev) if is_separator(prev) && !is_separator(chars[i]) => BONUS_BOUNDARY,
+    Some(prev) if prev.is_lowercase() && chars[i].is_uppercase() => BONUS_BOUNDARY,
+    _ => 0,
+  }
+}
+
+// returns the score of the best match of `pattern` in `text`, along with the (char) positions of
+// the matched characters, or None when the text doesn't match. matching ignores case
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+  let pattern: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+  let chars: Vec<char> = text.chars().collect();
+  let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+  if pattern.is_empty() {
+    return Some((0, vec![]));
+  }
+  // cheap check before scoring
+  let mut remaining = pattern.iter().peekable();
+  for c in lower.iter() {
+    if remaining.peek() == Some(&c) {
+      remaining.next();
+    }
+  }
+  if remaining.peek().is_some() {
+    return None;
+  }
+
+  // scores[i][j] is the best score of pattern[..=i] with pattern[i] matched at text[j],
+  // previous[i][j] is where pattern[i - 1] was matched for that score, and run_bonus[i][j] is the
+  // bonus of the first character in the run of consecutive matches ending at text[j]
+  let (m, n) = (pattern.len(), lower.len());
+  let mut scores: Vec<Vec<Option<i32>>> = vec![vec![None; n]; m];
+  let mut previous: Vec<Vec<usize>> = vec![vec![0; n]; m];
+  let mut run_bonus: Vec<Vec<i32>> = vec![vec![0; n]; m];
+  for j in 0..n {
+    if lower[j] == pattern[0] {
+      // like fzf, a word boundary counts double on the first character
+      scores[0][j] = Some(SCORE_MATCH + 2 * boundary_bonus(&chars, j));
+      run_bonus[0][j] = boundary_bonus(&chars, j);
+    }
+  }
+  for i in 1..m {
+    // the best match of pattern[i - 1] before j - 1, with the penalty of the gap up to j
+    let mut gapped: Option<(i32, usize)> = None;
+    for j in i..n {
+      if j >= 2 {
+        let extended = gapped.map(|(score, k)| (score - PENALTY_GAP_EXTENSION, k));
+        let started = scores[i - 1][j - 2].map(|score| (score - PENALTY_GAP_START, j - 2));
+        gapped = match (extended, started) {
+          (Some(a), Some(b)) => Some(if b.0 >= a.0 { b } else { a }),
+          (a, b) => a.or(b),
+        };
+      }
+      if lower[j] != pattern[i] {
+        continue;
+      }
+      // like fzf, a run of consecutive matches keeps the bonus of the word it starts
+      let consecutive =
+        scores[i - 1][j - 1].map(|score| (score + BONUS_CONSECUTIVE.max(run_bonus[i - 1][j - 1]), j - 1));
+      let best = match (consecutive, gapped) {
+        (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+        (a, b) => a.or(b),
+      };
+      if let Some((score, k)) = best {
+        scores[i][j] = Some(score + SCORE_MATCH + boundary_bonus(&chars, j));
+        previous[i][j] = k;
+        run_bonus[i][j] = if k == j - 1 { run_bonus[i - 1][k] } else { boundary_bonus(&chars, j) };
+      }
+    }
+  }
+
+  let (score, mut j) = (0..n)
+    .filter_map(|j| scores[m - 1][j].map(|score| (score, j)))
+    .max_by_key(|(score, j)| (*score, usize::MAX - j))?;
+  let mut positions = vec![j; m];
+  for i in (1..m).rev() {
+    j = previous[i][j];
+    positions[i - 1] = j;
+  }
+  Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fuzzy_match_requires_every_character_in_order() {
+    assert!(fuzzy_match("usr", "users").is_some());
+    assert!(fuzzy_match("USR", "users").is_some());
+    assert!(fuzzy_match("rsu", "users").is_none());
+    assert!(fuzzy_match("userz", "users").is_none());
+    assert_eq!(fuzzy_match("", "users"), Some((0, vec![])));
+  }
+
+  #[test]
+  fn test_fuzzy_match_prefers_word_starts() {
+    assert_eq!(fuzzy_match("ur", "user_ranks").unwrap().1, vec![0, 5]);
+    assert_eq!(fuzzy_match("uo", "userOrders").unwrap().1, vec![0, 4]);
+    assert_eq!(fuzzy_match("orders", "tenant_1.orders").unwrap().1, vec![9, 10, 11, 12, 13, 14]);
+  }
+
+  #[test]
+  fn test_fuzzy_match_ranks_tighter_matches_higher() {
+    let score = |pattern, text| fuzzy_match(pattern, text).unwrap().0;
+    assert!(score("ord", "app.orders") > score("ord", "app.long_record_data"));
+    assert!(score("users", "app.users") > score("users", "app.user_settings"));
+    assert!(score("app.ord", "app.orders") > score("app.ord", "apple.wordlist"));
+  }
+}
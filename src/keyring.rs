@@ -1,5 +1,3 @@
-use std::io::{self, Read, Write};
-
 use color_eyre::eyre;
 use keyring::Entry;
 
@@ -13,41 +11,44 @@ impl AsRef<str> for Password {
   }
 }
 
-pub fn get_password(connection_name: &str, username: &str) -> Result<Password> {
-  let entry = Entry::new("rainfrog", &format!("{connection_name}-{username}"))?;
+impl From<String> for Password {
+  fn from(password: String) -> Self {
+    Password(password)
+  }
+}
 
+// non-interactive lookup, for when stdin belongs to the tui
+pub fn get_saved_password(connection_name: &str, username: &str) -> Result<Option<Password>> {
+  let entry = Entry::new("rainfrog", &format!("{connection_name}-{username}"))?;
   match entry.get_password() {
-    Ok(password) => {
-      println!("Password found in keyring");
-      Ok(Password(password))
-    },
-    Err(e) => {
-      match e {
-        keyring::Error::NoEntry => {
-          println!("{}@{}", username, connection_name);
-          let password = rpassword::prompt_password("Password: ")?;
-
-          print!("Save password (Y/n): ");
-          let mut save = String::new();
-          io::stdout().flush()?;
-          io::stdin().read_line(&mut save)?;
-          match save.trim() {
-            "Y" => {
-              entry.set_password(&password)?;
-              println!("Password saved in keyring");
-              Ok(())
-            },
-            "n" => {
-              println!("Password not saved in keyring");
-              Ok(())
-            },
-            _ => Err(eyre::Report::msg("Unrecognized save option")),
-          }?;
-
-          Ok(Password(password))
-        },
-        _ => Err(eyre::Report::msg("Failed to extract password from secret: {e:?}")),
-      }
-    },
+    Ok(password) => Ok(Some(Password(password))),
+    Err(keyring::Error::NoEntry) => Ok(None),
+    Err(e) => Err(eyre::Report::msg(format!("Failed to extract password from secret: {e:?}"))),
+  }
+}
+
+pub fn save_password(connection_name: &str, username: &str, password: &str) -> Result<()> {
+  let entry = Entry::new("rainfrog", &format!("{connection_name}-{username}"))?;
+  entry.set_password(password)?;
+  Ok(())
+}
+
+// keeps a saved password with its connection when the connection is renamed
+pub fn move_password(from: (&str, &str), to: (&str, &str)) -> Result<()> {
+  if from == to {
+    return Ok(());
+  }
+  if let Some(password) = get_saved_password(from.0, from.1)? {
+    save_password(to.0, to.1, password.as_ref())?;
+    delete_password(from.0, from.1)?;
+  }
+  Ok(())
+}
+
+pub fn delete_password(connection_name: &str, username: &str) -> Result<()> {
+  let entry = Entry::new("rainfrog", &format!("{connection_name}-{username}"))?;
+  match entry.delete_credential() {
+    Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+    Err(e) => Err(eyre::Report::msg(format!("Failed to delete password from keyring: {e:?}"))),
   }
 }
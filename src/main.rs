@@ -9,11 +9,16 @@ pub mod app;
 pub mod cli;
 pub mod components;
 pub mod config;
+pub mod connection_manager;
 pub mod database;
+pub mod exec;
 pub mod focus;
+pub mod fuzzy;
 pub mod keyring;
 pub mod popups;
+pub mod session;
 pub mod tui;
+pub mod tunnel;
 pub mod ui;
 pub mod utils;
 pub mod vim;
@@ -25,83 +30,118 @@ use std::{
 };
 
 use clap::Parser;
-use cli::{extract_driver_from_url, prompt_for_database_selection, Cli, Driver};
+use cli::{default_connection, extract_driver_from_url, redact_url, Cli, Command, ConfigCommand, Driver};
 use color_eyre::eyre::{self, Result};
-use config::{Config, ConnectionString};
+use config::{Config, ConnectionString, DatabaseConnection, TunnelConfig};
+use connection_manager::ConnectionManager;
 use database::{BuildConnectionOptions, DatabaseQueries, HasRowsAffected, ValueParser};
 use dotenvy::dotenv;
-use keyring::get_password;
+use keyring::get_saved_password;
 use sqlx::{postgres::PgConnectOptions, Connection, Database, Executor, MySql, Pool, Postgres, Sqlite};
 
 use crate::{
   app::App,
+  session::ConnectionRequest,
   utils::{initialize_logging, initialize_panic_handler, version},
 };
 
-async fn run_app<DB>(mut args: Cli, config: Config) -> Result<()>
-where
-  DB: Database + BuildConnectionOptions + ValueParser + DatabaseQueries,
-  DB::QueryResult: HasRowsAffected,
-  for<'c> <DB as sqlx::Database>::Arguments<'c>: sqlx::IntoArguments<'c, DB>,
-  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
-{
-  let mouse_mode = args.mouse_mode.take();
-  let connection_opts = DB::build_connection_opts(args)?;
-  let mut app = App::<'_, DB>::new(connection_opts, mouse_mode, config)?;
-  app.run().await?;
-  Ok(())
-}
+// resolves the connection without asking the user anything. `None` means there
+// isn't enough to go on, and the connection manager should be shown instead.
+fn resolve_connection(args: &mut Cli, config: &Config) -> Result<Option<ConnectionRequest>> {
+  // clap rejects `--connection` together with `--url`, and a named connection wins over DATABASE_URL
+  if let Some(name) = args.connection.clone() {
+    let conn = config
+      .db
+      .get(&name)
+      .ok_or_else(|| eyre::Report::msg(format!("No connection named {name} in the config file")))?;
+    return config_connection(name, conn.clone());
+  }
 
-fn resolve_driver(args: &mut Cli, config: &Config) -> Result<Driver> {
   let url = args.connection_url.clone().or_else(|| {
     env::var("DATABASE_URL").map_or(None, |url| {
       if url.is_empty() {
         None
       } else {
-        println!("Using DATABASE_URL from environment variable");
+        eprintln!("Using DATABASE_URL from environment variable");
         Some(url)
       }
     })
   });
-  let has_cli_input = args.driver.is_some()
-    || args.user.is_some()
-    || args.password.is_some()
-    || args.host.is_some()
-    || args.port.is_some()
-    || args.database.is_some();
-
-  let (driver, url) = match (url, has_cli_input) {
+
+  match (url, args.has_connection_flags()) {
     (Some(u), _) => {
-      if let Some(driver) = args.driver.take() { Ok(driver) } else { extract_driver_from_url(&u) }.map(|d| (d, Some(u)))
+      let driver = if let Some(driver) = args.driver.take() { driver } else { extract_driver_from_url(&u)? };
+      Ok(Some(ConnectionRequest { name: redact_url(&u), driver, url: Some(u), tunnel: None, color: None }))
     },
     (None, true) => {
-      if let Some(driver) = args.driver.take() {
-        Ok((driver, None))
-      } else {
-        Ok((prompt_for_driver()?, None))
+      let complete = match args.driver {
+        Some(Driver::Sqlite) => args.database.is_some(),
+        Some(_) => args.user.is_some() && args.password.is_some() && args.host.is_some() && args.database.is_some(),
+        None => false,
+      };
+      if !complete {
+        return Ok(None);
       }
+      let driver = args.driver.take().unwrap();
+      let name = match (&args.host, &args.database) {
+        (Some(host), Some(database)) => format!("{host}/{database}"),
+        (Some(name), None) | (None, Some(name)) => name.clone(),
+        (None, None) => format!("{driver:?}").to_lowercase(),
+      };
+      Ok(Some(ConnectionRequest { name, driver, url: None, tunnel: None, color: None }))
     },
     (None, false) => {
-      Ok(match prompt_for_database_selection(config)? {
-        Some((conn, name)) => {
-          let url = match conn.connection {
-            ConnectionString::Raw { connection_string } => Ok(connection_string),
-            ConnectionString::Structured { details } => {
-              let password = get_password(&name, &details.username)?;
-              details.connection_string(conn.driver, password)
-            },
-          }?;
-
-          (conn.driver, Some(url))
-        },
-        None => (prompt_for_driver()?, None),
-      })
+      match default_connection(config)? {
+        Some((conn, name)) => config_connection(name, conn),
+        None => Ok(None),
+      }
     },
-  }?;
+  }
+}
 
-  args.connection_url = url;
+// `None` when the connection's password isn't saved in the keyring
+fn config_connection(name: String, conn: DatabaseConnection) -> Result<Option<ConnectionRequest>> {
+  let url = match &conn.connection {
+    ConnectionString::Raw { connection_string } => Some(connection_string.clone()),
+    ConnectionString::Structured { details } => {
+      get_saved_password(&name, &details.username)?
+        .map(|password| details.connection_string(conn.driver, password))
+        .transpose()?
+    },
+  };
+  Ok(
+    url.map(|url| {
+      ConnectionRequest { name, driver: conn.driver, url: Some(url), tunnel: conn.tunnel, color: conn.color }
+    }),
+  )
+}
 
-  Ok(driver)
+// prints every problem in the config files, returning the process exit code
+fn check_config() -> i32 {
+  let files = Config::files();
+  if files.is_empty() {
+    println!("no config file found in {}", utils::get_config_dir().display());
+    return 0;
+  }
+  match Config::check() {
+    Ok(problems) if problems.is_empty() => {
+      for file in files {
+        println!("{}: ok", file.display());
+      }
+      0
+    },
+    Ok(problems) => {
+      for problem in problems.iter() {
+        eprintln!("{problem}");
+      }
+      eprintln!("{} {} found", problems.len(), if problems.len() == 1 { "problem" } else { "problems" });
+      1
+    },
+    Err(e) => {
+      eprintln!("{e}");
+      1
+    },
+  }
 }
 
 async fn tokio_main() -> Result<()> {
@@ -112,15 +152,61 @@ async fn tokio_main() -> Result<()> {
   let mut args = Cli::parse();
   dotenv().ok();
 
-  let config = Config::new()?;
-
-  let driver = resolve_driver(&mut args, &config)?;
+  if let Some(Command::Config { command: ConfigCommand::Check }) = args.command {
+    std::process::exit(check_config());
+  }
 
-  match driver {
-    Driver::Postgres => run_app::<Postgres>(args, config).await,
-    Driver::Mysql => run_app::<MySql>(args, config).await,
-    Driver::Sqlite => run_app::<Sqlite>(args, config).await,
+  let config = match Config::new() {
+    Ok(config) => config,
+    Err(e) => {
+      eprintln!("{e}");
+      std::process::exit(1);
+    },
+  };
+
+  if let Some(command) = args.command.take() {
+    let code = match resolve_connection(&mut args, &config) {
+      Ok(Some(request)) => {
+        match command {
+          Command::Exec { query, format } => exec::run(args, request, query, format).await,
+          Command::Run { file, single_transaction, on_error } => {
+            exec::run_file(args, request, &file, single_transaction, on_error).await
+          },
+          Command::Config { .. } => unreachable!("config commands don't need a connection"),
+        }
+      },
+      Ok(None) => {
+        eprintln!("error: no connection to use. pass --connection, --url or set DATABASE_URL; saved connections need their password in the keyring");
+        exec::EXIT_CONNECTION_ERROR
+      },
+      Err(e) => {
+        eprintln!("error: {e}");
+        exec::EXIT_CONNECTION_ERROR
+      },
+    };
+    std::process::exit(code);
   }
+
+  let request = match resolve_connection(&mut args, &config)? {
+    Some(request) => request,
+    None => {
+      let manager = if args.has_connection_flags() {
+        ConnectionManager::with_args(config.clone(), &args)
+      } else {
+        ConnectionManager::new(config.clone())
+      };
+      match manager.run().await? {
+        Some(request) => request,
+        None => return Ok(()),
+      }
+    },
+  };
+
+  let mouse_mode = args.mouse_mode.take();
+  let session = session::connect(args, request.clone(), &config).await?;
+  let mut app = App::new(session, request, mouse_mode, config);
+  app.run().await?;
+  Ok(())
 }
 
 #[tokio::main]
@@ -132,11 +218,3 @@ async fn main() -> Result<()> {
     Ok(())
   }
 }
-
-pub fn prompt_for_driver() -> Result<Driver> {
-  let mut driver = String::new();
-  print!("Database driver (postgres, mysql, sqlite): ");
-  io::stdout().flush()?;
-  io::stdin().read_line(&mut driver)?;
-  driver.trim().to_lowercase().parse()
-}
@@ -0,0 +1,112 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use strum::IntoEnumIterator;
+
+use super::{PopUp, PopUpPayload};
+use crate::{
+  action::{FilterOperator, RowFilter},
+  database::filter_condition,
+};
+
+#[derive(Debug)]
+pub struct FilterRows<DB: sqlx::Database> {
+  columns: Vec<String>,
+  column: usize,
+  operators: Vec<FilterOperator>,
+  operator: usize,
+  value: String,
+  filters: Vec<RowFilter>, // already applied to the preview
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> FilterRows<DB> {
+  // a null value starts the filter on `is null`
+  pub fn new(columns: Vec<String>, column: usize, value: Option<String>, filters: Vec<RowFilter>) -> Self {
+    let column = column.min(columns.len().saturating_sub(1));
+    let operators: Vec<FilterOperator> = FilterOperator::iter().collect();
+    let operator = match value {
+      Some(_) => 0,
+      None => operators.iter().position(|operator| *operator == FilterOperator::IsNull).unwrap_or_default(),
+    };
+    Self { columns, column, operators, operator, value: value.unwrap_or_default(), filters, phantom: PhantomData }
+  }
+
+  fn filter(&self) -> Option<RowFilter> {
+    Some(RowFilter {
+      column: self.columns.get(self.column)?.clone(),
+      operator: self.operators[self.operator],
+      value: self.value.clone(),
+    })
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for FilterRows<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter if self.columns.is_empty() => Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter => {
+        let mut filters = self.filters.clone();
+        filters.extend(self.filter());
+        Ok(Some(PopUpPayload::FilterRows(filters)))
+      },
+      KeyCode::Char('d') if key.modifiers == KeyModifiers::CONTROL => Ok(Some(PopUpPayload::FilterRows(vec![]))),
+      KeyCode::Left => {
+        self.column = self.column.checked_sub(1).unwrap_or(self.columns.len().saturating_sub(1));
+        Ok(None)
+      },
+      KeyCode::Right => {
+        self.column = if self.column + 1 < self.columns.len() { self.column + 1 } else { 0 };
+        Ok(None)
+      },
+      KeyCode::Up => {
+        self.operator = self.operator.checked_sub(1).unwrap_or(self.operators.len() - 1);
+        Ok(None)
+      },
+      KeyCode::Down => {
+        self.operator = (self.operator + 1) % self.operators.len();
+        Ok(None)
+      },
+      KeyCode::Backspace => {
+        self.value.pop();
+        Ok(None)
+      },
+      KeyCode::Char(c) if (key.modifiers - KeyModifiers::SHIFT).is_empty() => {
+        self.value.push(c);
+        Ok(None)
+      },
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    let applied = match self.filters.is_empty() {
+      true => String::new(),
+      false => {
+        let conditions: Vec<String> = self.filters.iter().map(|filter| filter_condition('"', filter)).collect();
+        format!("\n\nfiltered by {}", conditions.join(" and "))
+      },
+    };
+    if self.columns.is_empty() {
+      return format!(
+        "The preview has no rows to pick a column from. Press [Ctrl+d] to remove all filters, or [Esc] to cancel.{}",
+        applied
+      );
+    }
+    format!(
+      "Filter the preview: [←|→] column, [↑|↓] operator, then type a value (comma separated for IN, with 'single quotes' around values containing commas). Press [Enter] to apply, [Ctrl+d] to remove all filters, or [Esc] to cancel.{}",
+      applied
+    )
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    self.filter().map(|filter| filter_condition('"', &filter)).unwrap_or_default()
+  }
+}
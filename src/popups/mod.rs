@@ -4,15 +4,20 @@ use crossterm::event::KeyEvent;
 use sqlparser::ast::Statement;
 
 use crate::{
+  action::RowFilter,
   app::AppState,
-  database::{DbError, Rows},
+  database::{DbError, ForeignKey, Rows, Value},
+  session::ConnectionRequest,
 };
 
 pub mod confirm_export;
 pub mod confirm_query;
 pub mod confirm_tx;
 pub mod exporting;
+pub mod filter_rows;
 pub mod name_favorite;
+pub mod pick_foreign_key;
+pub mod select_connection;
 
 // since popups are meant to overlay the entire app and capture
 // all input, we have a payload representing when a popup is exited
@@ -21,10 +26,13 @@ pub mod name_favorite;
 #[allow(clippy::large_enum_variant)]
 pub enum PopUpPayload {
   Cancel, // does nothing and closes the popup
-  SetDataTable(Option<Result<Rows, DbError>>, Option<Statement>),
+  SetDataTable(Option<Result<Rows<Value>, DbError>>, Option<Statement>),
   ConfirmQuery(String),
   ConfirmExport(bool),
   NamedFavorite(String, Vec<String>),
+  SelectConnection(ConnectionRequest),
+  FilterRows(Vec<RowFilter>), // every filter of the preview
+  FollowForeignKey(ForeignKey, Vec<(String, Option<String>)>, bool), // (key, row, to the rows referencing it)
 }
 
 #[async_trait(?Send)]
@@ -0,0 +1,66 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+use crate::database::ForeignKey;
+
+pub struct PickForeignKey<DB: sqlx::Database> {
+  keys: Vec<ForeignKey>,
+  selected: usize,
+  row: Vec<(String, Option<String>)>,
+  reverse: bool,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> PickForeignKey<DB> {
+  pub fn new(keys: Vec<ForeignKey>, row: Vec<(String, Option<String>)>, reverse: bool) -> Self {
+    Self { keys, selected: 0, row, reverse, phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for PickForeignKey<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('j') | KeyCode::Down => {
+        self.selected = (self.selected + 1) % self.keys.len();
+        Ok(None)
+      },
+      KeyCode::Char('k') | KeyCode::Up => {
+        self.selected = (self.selected + self.keys.len() - 1) % self.keys.len();
+        Ok(None)
+      },
+      KeyCode::Enter => {
+        Ok(
+          self
+            .keys
+            .get(self.selected)
+            .map(|key| PopUpPayload::FollowForeignKey(key.clone(), self.row.clone(), self.reverse)),
+        )
+      },
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    let keys = self
+      .keys
+      .iter()
+      .enumerate()
+      .map(|(i, key)| format!("{} {}", if i == self.selected { ">" } else { " " }, key.label()))
+      .collect::<Vec<_>>()
+      .join("\n");
+    format!("{}:\n\n{}", if self.reverse { "Show the rows referencing this one through" } else { "Follow" }, keys)
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[j|↓] down [k|↑] up [Enter] follow [Esc] cancel".to_string()
+  }
+}
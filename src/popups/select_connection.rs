@@ -0,0 +1,187 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+use crate::{
+  config::{ConnectionString, DatabaseConnection, StructuredConnection},
+  keyring::{get_saved_password, Password},
+  session::ConnectionRequest,
+};
+
+#[derive(Clone)]
+pub enum ConnectionEntry {
+  Configured(String, DatabaseConnection),
+  Recent(ConnectionRequest),
+}
+
+impl ConnectionEntry {
+  pub fn name(&self) -> &str {
+    match self {
+      ConnectionEntry::Configured(name, _) => name,
+      ConnectionEntry::Recent(request) => &request.name,
+    }
+  }
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Step {
+  Selecting,
+  // the selected entry has no password in the keyring, so it is typed in here
+  Password(String, DatabaseConnection, StructuredConnection, String),
+}
+
+pub struct SelectConnection<DB: sqlx::Database> {
+  entries: Vec<ConnectionEntry>,
+  selected: usize,
+  step: Step,
+  error: Option<String>,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> SelectConnection<DB> {
+  pub fn new(entries: Vec<ConnectionEntry>, error: Option<String>) -> Self {
+    Self { entries, selected: 0, step: Step::Selecting, error, phantom: PhantomData }
+  }
+
+  fn select(&mut self) -> Option<PopUpPayload> {
+    let entry = self.entries.get(self.selected)?.clone();
+    match entry {
+      ConnectionEntry::Recent(request) => Some(PopUpPayload::SelectConnection(request)),
+      ConnectionEntry::Configured(name, conn) => {
+        match &conn.connection {
+          ConnectionString::Raw { connection_string } => {
+            Some(PopUpPayload::SelectConnection(ConnectionRequest {
+              name,
+              driver: conn.driver,
+              url: Some(connection_string.clone()),
+              tunnel: conn.tunnel.clone(),
+              color: conn.color,
+            }))
+          },
+          ConnectionString::Structured { details } => {
+            match get_saved_password(&name, &details.username) {
+              Ok(Some(password)) => self.finish(name, &conn, details, password),
+              Ok(None) => {
+                self.step = Step::Password(name, conn.clone(), details.clone(), String::new());
+                None
+              },
+              Err(e) => {
+                self.error = Some(e.to_string());
+                None
+              },
+            }
+          },
+        }
+      },
+    }
+  }
+
+  fn finish(
+    &mut self,
+    name: String,
+    conn: &DatabaseConnection,
+    details: &StructuredConnection,
+    password: Password,
+  ) -> Option<PopUpPayload> {
+    match details.connection_string(conn.driver, password) {
+      Ok(url) => {
+        Some(PopUpPayload::SelectConnection(ConnectionRequest {
+          name,
+          driver: conn.driver,
+          url: Some(url),
+          tunnel: conn.tunnel.clone(),
+          color: conn.color,
+        }))
+      },
+      Err(e) => {
+        self.error = Some(e.to_string());
+        self.step = Step::Selecting;
+        None
+      },
+    }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for SelectConnection<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    if let Step::Password(name, conn, details, password) = &mut self.step {
+      return Ok(match key.code {
+        KeyCode::Char(c) => {
+          password.push(c);
+          None
+        },
+        KeyCode::Backspace => {
+          password.pop();
+          None
+        },
+        KeyCode::Enter => {
+          let (name, conn, details, password) = (name.clone(), conn.clone(), details.clone(), password.clone());
+          self.finish(name, &conn, &details, Password::from(password))
+        },
+        KeyCode::Esc => {
+          self.step = Step::Selecting;
+          None
+        },
+        _ => None,
+      });
+    }
+    match key.code {
+      KeyCode::Char('j') | KeyCode::Down => {
+        if !self.entries.is_empty() {
+          self.selected = (self.selected + 1) % self.entries.len();
+        }
+        Ok(None)
+      },
+      KeyCode::Char('k') | KeyCode::Up => {
+        if !self.entries.is_empty() {
+          self.selected = (self.selected + self.entries.len() - 1) % self.entries.len();
+        }
+        Ok(None)
+      },
+      KeyCode::Enter => {
+        self.error = None;
+        Ok(self.select())
+      },
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    let mut text = match &self.step {
+      Step::Password(name, _, details, password) => {
+        format!("Password for {}@{}:\n\n{}", details.username, name, "*".repeat(password.chars().count()))
+      },
+      Step::Selecting if self.entries.is_empty() => {
+        "No connections available. Add [db] entries to your config file.".to_string()
+      },
+      Step::Selecting => {
+        self
+          .entries
+          .iter()
+          .enumerate()
+          .map(|(i, entry)| format!("{} {}", if i == self.selected { ">" } else { " " }, entry.name()))
+          .collect::<Vec<_>>()
+          .join("\n")
+      },
+    };
+    if let Some(error) = &self.error {
+      text.push_str(&format!("\n\nFailed to connect: {error}"));
+    }
+    text
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    match self.step {
+      Step::Password(..) => "[Enter] connect [Esc] back".to_string(),
+      Step::Selecting => "[j|↓] down [k|↑] up [Enter] connect [Esc] cancel".to_string(),
+    }
+  }
+}
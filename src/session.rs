@@ -0,0 +1,1332 @@
+use std::{
+  sync::Arc,
+  time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use futures::FutureExt;
+use ratatui::{
+  layout::{Constraint, Direction, Layout, Position},
+  prelude::Rect,
+  style::{Color, Style, Stylize},
+  text::{Line, Span, Text},
+  widgets::{Block, Borders, Clear, Padding, Paragraph, Tabs, Wrap},
+  Frame,
+};
+use sqlx::{Connection, Database, Either, Executor, MySql, Postgres, Sqlite};
+use strum::IntoEnumIterator;
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use crate::{
+  action::{Action, DefinitionTarget, ExportFormat, MenuPreview, RowsPreview},
+  app::{AppState, Components, DbTask, HistoryEntry, MenuMetadata, MetadataTask, QueryResultsWithMetadata},
+  cli::{Cli, Driver},
+  components::{
+    data::Data,
+    editor::{Editor, EditorLines},
+    er_diagram::ErDiagram,
+    favorites::{FavoriteEntries, Favorites},
+    history::History,
+    menu::{Menu, PinnedTables, SettableTableList},
+    ComponentImpls,
+  },
+  config::{Config, TunnelConfig},
+  database::{
+    self, get_dialect, BuildConnectionOptions, DatabaseQueries, DbError, ExecutionType, ForeignKey, HasRowsAffected,
+    Rows, ValueParser,
+  },
+  focus::Focus,
+  popups::{
+    confirm_export::ConfirmExport,
+    confirm_query::ConfirmQuery,
+    confirm_tx::ConfirmTx,
+    exporting::Exporting,
+    filter_rows::FilterRows,
+    name_favorite::NameFavorite,
+    pick_foreign_key::PickForeignKey,
+    select_connection::{ConnectionEntry, SelectConnection},
+    PopUp, PopUpPayload,
+  },
+  tui,
+  tunnel::{self, Tunnel},
+  ui::center,
+};
+
+// a fully resolved connection, ready to be opened. intentionally not `Debug`,
+// since the url may contain a password.
+#[derive(Clone)]
+pub struct ConnectionRequest {
+  pub name: String,
+  pub driver: Driver,
+  pub url: Option<String>,
+  pub tunnel: Option<TunnelConfig>,
+  pub color: Option<Color>,
+}
+
+// a single live connection with its own pool, components and query state.
+// the app only talks to connections through this trait, which is what lets
+// it swap between drivers at runtime.
+#[async_trait(?Send)]
+pub trait Session {
+  fn name(&self) -> &str;
+  fn color(&self) -> Option<Color>;
+  fn init(&mut self, action_tx: UnboundedSender<Action>, area: Rect) -> Result<()>;
+  fn register_config(&mut self, config: Config) -> Result<()>;
+  async fn poll_tasks(&mut self) -> Result<()>;
+  async fn handle_event(&mut self, event: tui::Event, action_tx: &UnboundedSender<Action>) -> Result<()>;
+  async fn handle_action(&mut self, action: Action, action_tx: &UnboundedSender<Action>) -> Result<()>;
+  fn draw(&mut self, f: &mut Frame, area: Rect) -> Result<()>;
+  fn has_mouse_event(&self) -> bool;
+  fn select_connection(&mut self, entries: Vec<ConnectionEntry>, error: Option<String>);
+  fn take_connection_request(&mut self) -> Option<ConnectionRequest>;
+  fn editor_lines(&self) -> Vec<String>;
+  fn set_editor_lines(&mut self, lines: Vec<String>);
+  async fn close(&mut self);
+}
+
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+// failed checks in a row before the connection is reported as down
+const RECONNECT_ATTEMPTS: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionStatus {
+  // until the first health check comes back, which happens right after connecting
+  Checking,
+  Connected(Duration),
+  Reconnecting(u32),
+  Down,
+}
+
+/// Opens a connection for the given driver and wraps it in a session.
+pub async fn connect(args: Cli, request: ConnectionRequest, config: &Config) -> Result<Box<dyn Session>> {
+  match request.driver {
+    Driver::Postgres => Ok(Box::new(DbSession::<Postgres>::connect(args, request, config).await?)),
+    Driver::Mysql => Ok(Box::new(DbSession::<MySql>::connect(args, request, config).await?)),
+    Driver::Sqlite => Ok(Box::new(DbSession::<Sqlite>::connect(args, request, config).await?)),
+  }
+}
+
+/// Opens a single connection to check that the request works, returning the round trip time.
+pub async fn test_connection(request: ConnectionRequest) -> Result<Duration> {
+  match request.driver {
+    Driver::Postgres => test::<Postgres>(request).await,
+    Driver::Mysql => test::<MySql>(request).await,
+    Driver::Sqlite => test::<Sqlite>(request).await,
+  }
+}
+
+async fn test<DB>(request: ConnectionRequest) -> Result<Duration>
+where
+  DB: Database + BuildConnectionOptions,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  let (connection_opts, _tunnel) = connection_options::<DB>(Cli::default(), &request).await?;
+  let mut connection = DB::Connection::connect_with(&connection_opts).await?;
+  let start = Instant::now();
+  sqlx::raw_sql("select 1").execute(&mut connection).await?;
+  let latency = start.elapsed();
+  connection.close().await?;
+  Ok(latency)
+}
+
+// builds the connect options for a request, opening its ssh tunnel first if it has one
+pub async fn connection_options<DB>(
+  mut args: Cli,
+  request: &ConnectionRequest,
+) -> Result<(<DB::Connection as Connection>::Options, Option<Tunnel>)>
+where
+  DB: Database + BuildConnectionOptions,
+{
+  if request.url.is_some() {
+    args.connection_url = request.url.clone();
+  }
+  let connection_opts = DB::build_connection_opts(args)?;
+  match &request.tunnel {
+    Some(tunnel_config) => {
+      let (remote_host, remote_port) = DB::tunnel_target(&connection_opts)?;
+      let tunnel = tunnel::open_tunnel(tunnel_config, &remote_host, remote_port).await?;
+      Ok((DB::through_tunnel(connection_opts, tunnel.local_addr()), Some(tunnel)))
+    },
+    None => Ok((connection_opts, None)),
+  }
+}
+
+// the tables pinned for a connection's database, logging rather than failing when they can't be read
+fn pinned_tables(config: &Config, connection: &str, database: Option<String>) -> Vec<(String, String)> {
+  match PinnedTables::load(&config.config._data_dir) {
+    Ok(pinned) => pinned.get(connection, database.as_deref()),
+    Err(e) => {
+      log::error!("failed to read pinned tables: {}", e);
+      vec![]
+    },
+  }
+}
+
+pub struct DbSession<'a, DB: sqlx::Database> {
+  pub name: String,
+  pub color: Option<Color>,
+  pub config: Config,
+  pub components: Components<'static, DB>,
+  pub last_tick_key_events: Vec<KeyEvent>,
+  pub last_frame_mouse_event: Option<MouseEvent>,
+  pub pool: Option<database::DbPool<DB>>,
+  pub state: AppState<'a, DB>,
+  last_focused_tab: Focus,
+  last_focused_component: Focus,
+  popup: Option<Box<dyn PopUp<DB>>>,
+  connection_request: Option<ConnectionRequest>,
+  action_tx: Option<UnboundedSender<Action>>,
+  status: ConnectionStatus,
+  health_task: Option<JoinHandle<Result<Duration, sqlx::Error>>>,
+  next_health_check: Instant,
+  failed_health_checks: u32,
+  // the pool for the database being switched to, with its connect options, swapped in once it's open
+  #[allow(clippy::type_complexity)]
+  database_switch:
+    Option<(<DB::Connection as Connection>::Options, JoinHandle<Result<database::DbPool<DB>, sqlx::Error>>)>,
+  // the query currently running outside of a transaction, kept so it can be retried
+  running_query: Option<Vec<String>>,
+  retry_query: Option<Vec<String>>,
+  retrying_query: bool,
+  // the rows preview shown in the data pane, as (schema, table, preview, query), so more of its
+  // rows can be loaded
+  rows_preview: Option<(String, String, RowsPreview, String)>,
+  appending_rows: bool,
+  // the previews left by following foreign keys, to go back to
+  breadcrumbs: Vec<(String, String, RowsPreview, String)>,
+  // a foreign key to follow once the table's keys are loaded, as (column, row, reverse)
+  #[allow(clippy::type_complexity)]
+  following: Option<(String, Vec<(String, Option<String>)>, bool)>,
+  // dropped together with the session, which closes the port-forward
+  tunnel: Option<Tunnel>,
+}
+
+impl<DB> DbSession<'_, DB>
+where
+  DB: Database + BuildConnectionOptions + ValueParser + DatabaseQueries,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> <DB as sqlx::Database>::Arguments<'c>: sqlx::IntoArguments<'c, DB>,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  async fn poll_metadata_tasks(&mut self) -> Result<()> {
+    let (finished, running): (Vec<_>, Vec<_>) =
+      std::mem::take(&mut self.state.metadata_tasks).into_iter().partition(MetadataTask::is_finished);
+    self.state.metadata_tasks = running;
+    for task in finished {
+      match task {
+        MetadataTask::Menu(task) => {
+          let metadata = task.await?;
+          self.components.menu.set_databases(Some(metadata.databases));
+          self.components.menu.set_table_list(Some(metadata.tables));
+          self.components.menu.set_schema_objects(Some(metadata.objects));
+          self.components.menu.set_table_sizes(Some(metadata.sizes));
+        },
+        MetadataTask::Columns(schema, table, task) => {
+          let results = task.await?;
+          self.components.menu.set_columns(&schema, &table, Some(results));
+        },
+        MetadataTask::AllColumns(task) => {
+          let results = task.await?;
+          self.components.menu.set_all_columns(Some(results));
+        },
+        MetadataTask::Definition(kind, schema, name, target, task) => {
+          let Some(action_tx) = self.action_tx.clone() else {
+            continue;
+          };
+          match task.await?.map(|rows| database::definition_text(&rows)) {
+            Ok(Some(definition)) => {
+              match target {
+                DefinitionTarget::Editor => {
+                  action_tx.send(Action::DefinitionToEditor(definition.lines().map(String::from).collect()))?;
+                  action_tx.send(Action::FocusEditor)?;
+                },
+                DefinitionTarget::Clipboard => action_tx.send(Action::CopyData(definition))?,
+              }
+            },
+            Ok(None) => log::warn!("no definition found for {} {}.{}", kind, schema, name),
+            Err(e) => log::error!("{}", e),
+          }
+        },
+        MetadataTask::ForeignKeys(schema, table, task) => {
+          let results = task.await?;
+          // the preview may have changed while the keys were loading
+          let previewing = self.rows_preview.as_ref().is_some_and(|(s, t, ..)| *s == schema && *t == table);
+          match (results, self.following.take()) {
+            (Ok(rows), Some((column, row, reverse))) if previewing => {
+              let keys =
+                database::foreign_keys_to_follow(ForeignKey::from_rows(&rows), &schema, &table, &column, reverse);
+              match keys.as_slice() {
+                [] => {
+                  log::warn!("no foreign keys {} {}", if reverse { "referencing" } else { "from" }, table)
+                },
+                [key] => self.follow_foreign_key(key, &row, reverse)?,
+                _ => self.set_popup(Box::new(PickForeignKey::<DB>::new(keys, row, reverse))),
+              }
+            },
+            (Err(e), _) => log::error!("{}", e),
+            _ => {},
+          }
+        },
+        MetadataTask::ErDiagram(schema, tables, task) => {
+          match task.await? {
+            Ok((columns, keys)) => {
+              self.components.data.set_er_diagram(ErDiagram::new(schema, tables, &columns, &keys));
+              // the diagram replaces the preview, so there is nothing to load more of or go back to
+              self.rows_preview = None;
+              self.breadcrumbs.clear();
+              self.update_breadcrumbs();
+              if let Some(action_tx) = &self.action_tx {
+                action_tx.send(Action::FocusData)?;
+              }
+            },
+            Err(e) => self.components.data.set_data_state(Some(Err(e)), None),
+          }
+        },
+      }
+    }
+    Ok(())
+  }
+
+  pub async fn connect(args: Cli, request: ConnectionRequest, config: &Config) -> Result<Self> {
+    let (connection_opts, tunnel) = connection_options::<DB>(args, &request).await?;
+    let pool = database::init_pool::<DB>(connection_opts.clone()).await?;
+    log::info!("{pool:?}");
+
+    let focus = Focus::Menu;
+    let favorite_entries = FavoriteEntries::new(&config.config._favorites_dir)?;
+    let mut menu = Menu::new();
+    menu.set_table_actions(DB::table_actions());
+    menu.set_pinned_tables(pinned_tables(config, &request.name, DB::database_name(&connection_opts)));
+    Ok(Self {
+      name: request.name.clone(),
+      color: request.color,
+      config: config.clone(),
+      components: Components {
+        menu: Box::new(menu),
+        editor: Box::new(Editor::new()),
+        history: Box::new(History::new()),
+        data: Box::new(Data::new()),
+        favorites: Box::new(Favorites::new()),
+      },
+      last_tick_key_events: Vec::new(),
+      last_frame_mouse_event: None,
+      pool: Some(pool),
+      state: AppState {
+        connection_opts,
+        dialect: get_dialect(DB::NAME),
+        focus,
+        query_task: None,
+        metadata_tasks: vec![],
+        history: vec![],
+        last_query_start: None,
+        last_query_end: None,
+        favorites: favorite_entries,
+      },
+      last_focused_tab: Focus::Editor,
+      last_focused_component: focus,
+      popup: None,
+      connection_request: None,
+      action_tx: None,
+      status: ConnectionStatus::Checking,
+      health_task: None,
+      next_health_check: Instant::now(),
+      failed_health_checks: 0,
+      database_switch: None,
+      running_query: None,
+      retry_query: None,
+      retrying_query: false,
+      rows_preview: None,
+      appending_rows: false,
+      breadcrumbs: vec![],
+      following: None,
+      tunnel,
+    })
+  }
+
+  // shows the query of a rows preview in the editor, and runs it from there like any other preview
+  fn run_rows_preview(&self, query: String) -> Result<()> {
+    if let Some(action_tx) = &self.action_tx {
+      action_tx.send(Action::DefinitionToEditor(vec![query.clone()]))?;
+      action_tx.send(Action::FocusEditor)?;
+      action_tx.send(Action::FocusData)?;
+      action_tx.send(Action::Query(vec![query], false))?;
+    }
+    Ok(())
+  }
+
+  // previews the rows related to `row` through `key`, keeping the preview shown to go back to
+  fn follow_foreign_key(&mut self, key: &ForeignKey, row: &[(String, Option<String>)], reverse: bool) -> Result<()> {
+    let Some((schema, table, filters)) = key.related_rows(row, reverse) else {
+      log::warn!("the row has no values to follow {}", key.label());
+      return Ok(());
+    };
+    // the referenced columns are unique, so they order the rows like a primary key would
+    let primary_key = if reverse { vec![] } else { key.referenced_columns.clone() };
+    let preview = RowsPreview { primary_key, filters, ..RowsPreview::default() };
+    let query = DB::preview_rows_query(&schema, &table, &preview, self.config.settings.preview_limit());
+    if let Some(previous) = self.rows_preview.replace((schema, table, preview, query.clone())) {
+      self.breadcrumbs.push(previous);
+    }
+    self.update_breadcrumbs();
+    self.run_rows_preview(query)
+  }
+
+  fn update_breadcrumbs(&mut self) {
+    let trail = match &self.rows_preview {
+      Some(current) if !self.breadcrumbs.is_empty() => {
+        self
+          .breadcrumbs
+          .iter()
+          .chain([current])
+          .map(|(schema, table, ..)| database::qualified_name(schema, table))
+          .collect()
+      },
+      _ => vec![],
+    };
+    self.components.data.set_breadcrumbs(trail);
+  }
+
+  fn add_to_history(&mut self, query_lines: Vec<String>) {
+    self.state.history.insert(0, HistoryEntry { query_lines, timestamp: chrono::Local::now() });
+    if self.state.history.len() > 50 {
+      self.state.history.pop();
+    }
+  }
+
+  fn clear_history(&mut self) {
+    self.state.history = vec![];
+  }
+
+  fn set_focus(&mut self, focus: Focus) {
+    self.state.focus = focus;
+    if focus != Focus::PopUp {
+      self.popup = None;
+      self.last_focused_component = focus;
+    }
+    if focus == Focus::Editor || focus == Focus::History || focus == Focus::Favorites {
+      self.last_focused_tab = focus;
+    }
+  }
+
+  fn set_popup(&mut self, popup: Box<dyn PopUp<DB>>) {
+    self.popup = Some(popup);
+    self.set_focus(Focus::PopUp);
+  }
+
+  fn last_focused_tab(&mut self) {
+    match self.last_focused_tab {
+      Focus::Editor => self.set_focus(Focus::Editor),
+      Focus::History => self.set_focus(Focus::History),
+      Focus::Favorites => self.set_focus(Focus::Favorites),
+      _ => {},
+    }
+  }
+
+  fn last_focused_component(&mut self) {
+    match self.last_focused_component {
+      Focus::Menu => self.set_focus(Focus::Menu),
+      Focus::Editor => self.set_focus(Focus::Editor),
+      Focus::Data => self.set_focus(Focus::Data),
+      Focus::History => self.set_focus(Focus::History),
+      Focus::Favorites => self.set_focus(Focus::Favorites),
+      Focus::PopUp => {},
+    }
+  }
+
+  fn poll_health_check(&mut self) -> Result<()> {
+    match &mut self.health_task {
+      Some(task) if task.is_finished() => {
+        let result = task.now_or_never().unwrap_or(Ok(Err(sqlx::Error::PoolTimedOut)));
+        self.health_task = None;
+        match result {
+          Ok(Ok(latency)) => {
+            self.status = ConnectionStatus::Connected(latency);
+            self.failed_health_checks = 0;
+            self.next_health_check = Instant::now() + HEALTH_CHECK_INTERVAL;
+            if let (Some(query_lines), Some(action_tx)) = (self.retry_query.take(), &self.action_tx) {
+              log::info!("Connection recovered, retrying query");
+              self.retrying_query = true;
+              action_tx.send(Action::Query(query_lines, false))?;
+            }
+          },
+          Ok(Err(e)) => {
+            log::warn!("Health check failed: {e:?}");
+            self.failed_health_checks += 1;
+            self.status = if self.failed_health_checks > RECONNECT_ATTEMPTS {
+              ConnectionStatus::Down
+            } else {
+              ConnectionStatus::Reconnecting(self.failed_health_checks)
+            };
+            let backoff = Duration::from_secs(1 << self.failed_health_checks.min(5)).min(MAX_RECONNECT_BACKOFF);
+            self.next_health_check = Instant::now() + backoff;
+          },
+          Err(e) => log::error!("{e:?}"),
+        }
+      },
+      None if Instant::now() >= self.next_health_check => {
+        if let Some(pool) = &self.pool {
+          let pool = pool.clone();
+          self.health_task = Some(tokio::spawn(async move {
+            tokio::time::timeout(HEALTH_CHECK_TIMEOUT, database::ping(&pool))
+              .await
+              .unwrap_or(Err(sqlx::Error::PoolTimedOut))
+          }));
+        }
+      },
+      _ => {},
+    }
+    Ok(())
+  }
+
+  async fn poll_database_switch(&mut self) -> Result<()> {
+    if !self.database_switch.as_ref().is_some_and(|(_, task)| task.is_finished()) {
+      return Ok(());
+    }
+    let Some((opts, task)) = self.database_switch.take() else {
+      return Ok(());
+    };
+    match task.await? {
+      Ok(pool) => {
+        for task in self.state.metadata_tasks.drain(..) {
+          task.abort();
+        }
+        if let Some(previous) = self.pool.replace(pool) {
+          tokio::spawn(async move { previous.close().await });
+        }
+        self.state.connection_opts = opts;
+        let pinned = pinned_tables(&self.config, &self.name, DB::database_name(&self.state.connection_opts));
+        self.components.menu.set_pinned_tables(pinned);
+        if let Some(action_tx) = &self.action_tx {
+          action_tx.send(Action::LoadMenu)?;
+        }
+      },
+      Err(e) => self.components.data.set_data_state(Some(Err(Either::Left(e))), None),
+    }
+    Ok(())
+  }
+
+  fn abort_query_task(&mut self) {
+    if let Some(query_task) = self.state.query_task.take() {
+      match query_task {
+        DbTask::Query(task) => {
+          task.abort();
+        },
+        DbTask::TxStart(task) => {
+          task.abort();
+        },
+        DbTask::TxCommit(task) => {
+          task.abort();
+        },
+        DbTask::Maintenance(_, task) => {
+          task.abort();
+        },
+        _ => {},
+      }
+    }
+  }
+
+  fn draw_layout(&mut self, f: &mut Frame, area: Rect) -> Result<()> {
+    let hints_layout = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints(match area.width {
+        x if x < 160 => [Constraint::Fill(1), Constraint::Length(2)],
+        _ => [Constraint::Fill(1), Constraint::Length(1)],
+      })
+      .split(area);
+    let root_layout = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
+      .split(hints_layout[0]);
+    let right_layout = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+      .split(root_layout[1]);
+    let tabs_layout = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Length(1), Constraint::Fill(1)])
+      .split(right_layout[0]);
+
+    if let Some(event) = &self.last_frame_mouse_event {
+      if !matches!(self.state.query_task, Some(DbTask::TxPending(_, _)))
+        && event.kind != MouseEventKind::Moved
+        && !matches!(event.kind, MouseEventKind::Down(_))
+      {
+        let position = Position::new(event.column, event.row);
+        let menu_target = root_layout[0];
+        let tabs_target = tabs_layout[0];
+        let tab_content_target = tabs_layout[1];
+        let data_target = right_layout[1];
+        if menu_target.contains(position) {
+          self.set_focus(Focus::Menu);
+        } else if data_target.contains(position) {
+          self.set_focus(Focus::Data);
+        } else if tab_content_target.contains(position) {
+          self.last_focused_tab();
+        } else if tabs_target.contains(position) {
+          match self.state.focus {
+            Focus::Editor => {
+              if matches!(event.kind, MouseEventKind::Up(_)) {
+                self.set_focus(Focus::History);
+              }
+            },
+            Focus::History => {
+              if matches!(event.kind, MouseEventKind::Up(_)) {
+                self.set_focus(Focus::Favorites);
+              }
+            },
+            Focus::Favorites => {
+              if matches!(event.kind, MouseEventKind::Up(_)) {
+                self.set_focus(Focus::Editor);
+              }
+            },
+            Focus::PopUp => {},
+            _ => {
+              self.state.focus = self.last_focused_tab;
+            },
+          }
+          self.last_frame_mouse_event = None;
+        }
+      }
+    }
+    let tabs = Tabs::new(vec![" 󰤏 query <alt+2>", "   history <alt+4>", "   favorites <alt+5>"])
+      .highlight_style(Style::new().fg(self.state.focus.tab_color()).reversed())
+      .select(self.last_focused_tab.tab_index())
+      .padding(" ", "")
+      .divider(" ");
+
+    let state = &self.state;
+
+    f.render_widget(tabs, tabs_layout[0]);
+    f.render_widget(Clear, tabs_layout[1]);
+
+    match self.last_focused_tab {
+      Focus::Editor => {
+        self.components.editor.draw(f, tabs_layout[1], state).unwrap();
+      },
+      Focus::History => {
+        self.components.history.draw(f, tabs_layout[1], state).unwrap();
+      },
+      Focus::Favorites => {
+        self.components.favorites.draw(f, tabs_layout[1], state).unwrap();
+      },
+      Focus::Menu | Focus::Data | Focus::PopUp => (),
+    };
+
+    self.components.menu.draw(f, root_layout[0], state).unwrap();
+    self.components.data.draw(f, right_layout[1], state).unwrap();
+    self.render_hints(f, hints_layout[1]);
+
+    if let Some(popup) = &self.popup {
+      self.render_popup(f, popup.as_ref());
+    }
+    Ok(())
+  }
+
+  fn render_hints(&self, frame: &mut Frame, area: Rect) {
+    let block = Block::default().style(Style::default().fg(Color::Blue));
+    let help_text = format!(
+        "{}{}",
+        match self.state.query_task {
+            None => "",
+            _ if self.state.focus == Focus::Editor => "[<alt + q>] abort ",
+            _ if self.state.focus != Focus::PopUp => "[q] abort ",
+            _ => ""
+        },
+        match self.state.focus {
+            Focus::Menu  => "[R] refresh [j|↓] down [k|↑] up [l|<enter>] table list [h|󰁮 ] schema list [/] search [F] find [E] er diagram [g] top [G] bottom",
+            Focus::Editor if self.state.query_task.is_none() => "[<alt + enter>|<f5>] execute query [<ctrl + f>|<alt + f>] save query to favorites",
+            Focus::History => "[j|↓] down [k|↑] up [y] copy query [I] edit query [D] clear history",
+            Focus::Favorites => "[j|↓] down [k|↑] up [y] copy query [I] edit query [D] delete entry [/] search [<esc>] clear search",
+            Focus::Data if self.state.query_task.is_none() && !self.breadcrumbs.is_empty() => "[P] export [m] more rows [f] filter [r] referenced row [R] referencing rows [<bs>] back [j|↓] next row [k|↑] prev row [w|e] next col [b] prev col [v] select field [V] select row [y] copy [g] top [G] bottom [0] first col [$] last col",
+            Focus::Data if self.state.query_task.is_none() && self.rows_preview.is_some() => "[P] export [m] more rows [f] filter [r] referenced row [R] referencing rows [j|↓] next row [k|↑] prev row [w|e] next col [b] prev col [v] select field [V] select row [y] copy [g] top [G] bottom [0] first col [$] last col",
+            Focus::Data if self.state.query_task.is_none() => "[P] export [j|↓] next row [k|↑] prev row [w|e] next col [b] prev col [v] select field [V] select row [y] copy [g] top [G] bottom [0] first col [$] last col",
+            Focus::PopUp => "[<esc>] cancel",
+            _ => "",
+        }
+    );
+    let status = match self.status {
+      ConnectionStatus::Checking => Span::styled("◌ checking connection ", Style::default().fg(Color::DarkGray)),
+      ConnectionStatus::Connected(latency) => {
+        Span::styled(format!("● connected ({}ms) ", latency.as_millis()), Style::default().fg(Color::Green))
+      },
+      ConnectionStatus::Reconnecting(attempt) => {
+        Span::styled(format!("◌ reconnecting (attempt {attempt}) "), Style::default().fg(Color::Yellow))
+      },
+      ConnectionStatus::Down => Span::styled("✖ connection down ", Style::default().fg(Color::Red)),
+    };
+    let paragraph =
+      Paragraph::new(Line::from(vec![status, Span::raw(help_text)]).centered()).block(block).wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, area);
+  }
+
+  fn render_popup(&self, frame: &mut Frame, popup: &dyn PopUp<DB>) {
+    let area = center(frame.area(), Constraint::Percentage(50), Constraint::Percentage(50));
+    let block = Block::default()
+      .borders(Borders::ALL)
+      .border_style(Style::default().fg(Color::Yellow))
+      .title(Line::from(" Confirm Action ").centered())
+      .padding(Padding::uniform(1));
+    let layout = Layout::default()
+      .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+      .direction(Direction::Vertical)
+      .split(block.inner(area));
+
+    let popup_cta = Paragraph::new(Text::from(popup.get_cta_text(&self.state)).centered()).wrap(Wrap { trim: false });
+    let popup_actions = Paragraph::new(Line::from(popup.get_actions_text(&self.state)).centered());
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(popup_cta, layout[0]);
+    frame.render_widget(popup_actions, center(layout[1], Constraint::Fill(1), Constraint::Percentage(50)));
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB> Session for DbSession<'_, DB>
+where
+  DB: Database + BuildConnectionOptions + ValueParser + DatabaseQueries,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> <DB as sqlx::Database>::Arguments<'c>: sqlx::IntoArguments<'c, DB>,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn color(&self) -> Option<Color> {
+    self.color
+  }
+
+  fn init(&mut self, action_tx: UnboundedSender<Action>, area: Rect) -> Result<()> {
+    self.action_tx = Some(action_tx.clone());
+    self.components.menu.register_action_handler(action_tx.clone())?;
+    self.components.editor.register_action_handler(action_tx.clone())?;
+    self.components.history.register_action_handler(action_tx.clone())?;
+    self.components.data.register_action_handler(action_tx.clone())?;
+    self.components.favorites.register_action_handler(action_tx.clone())?;
+
+    self.register_config(self.config.clone())?;
+
+    self.components.menu.init(area)?;
+    self.components.editor.init(area)?;
+    self.components.history.init(area)?;
+    self.components.data.init(area)?;
+    self.components.favorites.init(area)?;
+
+    action_tx.send(Action::LoadMenu)?;
+    Ok(())
+  }
+
+  fn register_config(&mut self, config: Config) -> Result<()> {
+    self.components.menu.register_config_handler(config.clone())?;
+    self.components.editor.register_config_handler(config.clone())?;
+    self.components.history.register_config_handler(config.clone())?;
+    self.components.data.register_config_handler(config.clone())?;
+    self.components.favorites.register_config_handler(config.clone())?;
+    self.config = config;
+    Ok(())
+  }
+
+  async fn poll_tasks(&mut self) -> Result<()> {
+    if let Some(popup) = &mut self.popup {
+      self.set_focus(Focus::PopUp);
+    }
+    self.poll_health_check()?;
+    match &mut self.state.query_task {
+      Some(DbTask::Query(task)) => {
+        if task.is_finished() {
+          let results = task.await?;
+          self.state.query_task = None;
+          let query_lines = self.running_query.take();
+          match &results.results {
+            Err(e) if database::is_connection_error(e) => {
+              // check right away instead of waiting for the next interval
+              self.next_health_check = Instant::now();
+              if self.config.settings.retry_failed_query && !self.retrying_query {
+                self.retry_query = query_lines;
+              }
+            },
+            _ => {},
+          }
+          self.retrying_query = false;
+          if results.results.is_ok() && database::is_ddl(&results.statement_type) {
+            if let Some(action_tx) = &self.action_tx {
+              action_tx.send(Action::LoadMenu)?;
+            }
+          }
+          if std::mem::take(&mut self.appending_rows) {
+            self.components.data.append_rows(results.results);
+          } else {
+            self.components.data.set_data_state(Some(results.results), Some(results.statement_type));
+          }
+          self.state.last_query_end = Some(chrono::Utc::now());
+        }
+      },
+      Some(DbTask::TxStart(task)) => {
+        if task.is_finished() {
+          let (results, tx) = task.await?;
+          match results.results {
+            Ok(_) => {
+              self.state.query_task = Some(DbTask::TxPending(tx, results));
+              self.set_popup(Box::new(ConfirmTx::<DB>::new()));
+            },
+            Err(_) => {
+              self.state.query_task = None;
+              self.components.data.set_data_state(Some(results.results), Some(results.statement_type));
+            },
+          }
+          self.state.last_query_end = Some(chrono::Utc::now());
+        }
+      },
+      Some(DbTask::TxCommit(task)) => {},
+      Some(DbTask::Maintenance(statement, task)) if task.is_finished() => {
+        let statement = statement.clone();
+        let results = task.await?;
+        self.state.query_task = None;
+        self.running_query = None;
+        match results {
+          Ok(rows) if !rows.rows.is_empty() => self.components.data.set_data_state(Some(Ok(rows)), None),
+          Ok(_) => self.components.data.set_statement_completed(statement),
+          Err(e) => self.components.data.set_data_state(Some(Err(e)), None),
+        }
+        self.state.last_query_end = Some(chrono::Utc::now());
+      },
+      _ => {},
+    }
+    self.poll_metadata_tasks().await?;
+    self.poll_database_switch().await?;
+    Ok(())
+  }
+
+  async fn handle_event(&mut self, e: tui::Event, action_tx: &UnboundedSender<Action>) -> Result<()> {
+    let mut event_consumed = false;
+    match e {
+      tui::Event::Mouse(event) => self.last_frame_mouse_event = Some(event),
+      tui::Event::Key(key) => {
+        // characters typed into the menu's search are text, not keybindings
+        let typing = self.state.focus == Focus::Menu
+          && self.components.menu.is_typing()
+          && matches!(key.code, KeyCode::Char(_))
+          && (key.modifiers - KeyModifiers::SHIFT).is_empty();
+        if let (Some(keymap), false) = (self.config.keybindings.get(&self.state.focus), typing) {
+          if let Some(action) = keymap.get(&vec![key]) {
+            log::info!("Got action: {action:?}");
+            action_tx.send(action.clone())?;
+            event_consumed = true;
+          } else if let Some(popup) = &mut self.popup {
+            // popup captures all inputs. if it returns a payload, that means
+            // it is finished and should be closed
+            let payload = popup.handle_key_events(key, &mut self.state).await?;
+            match payload {
+              Some(PopUpPayload::SetDataTable(result, statement)) => {
+                self.components.data.set_data_state(result, statement);
+                self.set_focus(Focus::Editor);
+              },
+              Some(PopUpPayload::ConfirmQuery(query)) => {
+                action_tx.send(Action::Query(vec![query], true))?;
+                self.set_focus(Focus::Editor);
+              },
+              Some(PopUpPayload::ConfirmExport(confirmed)) => {
+                if confirmed {
+                  action_tx.send(Action::ExportData(ExportFormat::CSV))?;
+                  self.set_popup(Box::new(Exporting::new()));
+                } else {
+                  self.set_focus(Focus::Data);
+                }
+              },
+              Some(PopUpPayload::Cancel) => {
+                self.last_focused_component();
+              },
+              Some(PopUpPayload::NamedFavorite(name, query_lines)) => {
+                self.state.favorites.add_entry(name, query_lines);
+                self.set_focus(Focus::Editor);
+              },
+              Some(PopUpPayload::SelectConnection(request)) => {
+                self.connection_request = Some(request);
+                self.last_focused_component();
+              },
+              Some(PopUpPayload::FilterRows(filters)) => {
+                self.set_focus(Focus::Data);
+                if let Some((schema, table, preview, query)) = self.rows_preview.as_mut() {
+                  preview.filters = filters;
+                  *query = DB::preview_rows_query(schema, table, preview, self.config.settings.preview_limit());
+                  let query = query.clone();
+                  self.run_rows_preview(query)?;
+                }
+              },
+              Some(PopUpPayload::FollowForeignKey(key, row, reverse)) => {
+                self.set_focus(Focus::Data);
+                self.follow_foreign_key(&key, &row, reverse)?;
+              },
+              None => {},
+            }
+            event_consumed = true;
+          } else {
+            // If the key was not handled as a single key action,
+            // then consider it for multi-key combinations.
+            self.last_tick_key_events.push(key);
+
+            // Check for multi-key combinations
+            if let Some(action) = keymap.get(&self.last_tick_key_events) {
+              log::info!("Got action: {action:?}");
+              action_tx.send(action.clone())?;
+              event_consumed = true;
+            }
+          }
+        }
+      },
+      _ => {},
+    }
+    if !event_consumed {
+      for i in ComponentImpls::iter() {
+        let action = match i {
+          ComponentImpls::Menu => {
+            self.components.menu.handle_events(Some(e.clone()), self.last_tick_key_events.clone(), &self.state)?
+          },
+          ComponentImpls::Editor => {
+            self.components.editor.handle_events(Some(e.clone()), self.last_tick_key_events.clone(), &self.state)?
+          },
+          ComponentImpls::History => {
+            self.components.history.handle_events(Some(e.clone()), self.last_tick_key_events.clone(), &self.state)?
+          },
+          ComponentImpls::Data => {
+            self.components.data.handle_events(Some(e.clone()), self.last_tick_key_events.clone(), &self.state)?
+          },
+          ComponentImpls::Favorites => {
+            self.components.favorites.handle_events(Some(e.clone()), self.last_tick_key_events.clone(), &self.state)?
+          },
+        };
+        if let Some(action) = action {
+          action_tx.send(action)?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  async fn handle_action(&mut self, action: Action, action_tx: &UnboundedSender<Action>) -> Result<()> {
+    let action_consumed = false;
+    match &action {
+      Action::Tick => {
+        self.last_tick_key_events.drain(..);
+      },
+      Action::Render => {
+        self.last_frame_mouse_event = None;
+      },
+      Action::FocusMenu => self.set_focus(Focus::Menu),
+      Action::FocusEditor => self.set_focus(Focus::Editor),
+      Action::FocusData => self.set_focus(Focus::Data),
+      Action::FocusHistory => self.set_focus(Focus::History),
+      Action::FocusFavorites => self.set_focus(Focus::Favorites),
+      Action::CycleFocusForwards => {
+        match self.state.focus {
+          Focus::Menu => self.set_focus(Focus::Editor),
+          Focus::Editor => self.set_focus(Focus::Data),
+          Focus::Data => self.set_focus(Focus::History),
+          Focus::History => self.set_focus(Focus::Favorites),
+          Focus::Favorites => self.set_focus(Focus::Menu),
+          Focus::PopUp => {},
+        }
+      },
+      Action::CycleFocusBackwards => {
+        match self.state.focus {
+          Focus::History => self.set_focus(Focus::Data),
+          Focus::Data => self.set_focus(Focus::Editor),
+          Focus::Editor => self.set_focus(Focus::Menu),
+          Focus::Menu => self.set_focus(Focus::Favorites),
+          Focus::Favorites => self.set_focus(Focus::History),
+          Focus::PopUp => {},
+        }
+      },
+      Action::LoadMenu => {
+        log::info!("LoadMenu");
+        if let Some(pool) = &self.pool {
+          let pool = pool.clone();
+          let dialect = self.state.dialect.clone();
+          // whatever an older load would return is already out of date
+          self.state.metadata_tasks.retain(|task| {
+            let outdated = matches!(task, MetadataTask::Menu(_));
+            if outdated {
+              task.abort();
+            }
+            !outdated
+          });
+          self.state.metadata_tasks.push(MetadataTask::Menu(tokio::spawn(async move {
+            let (databases, tables, objects, sizes) = futures::join!(
+              database::query(DB::preview_databases_query(), dialect.as_ref(), &pool),
+              database::query(DB::preview_tables_query(), dialect.as_ref(), &pool),
+              database::query(DB::preview_schema_objects_query(), dialect.as_ref(), &pool),
+              database::query(DB::preview_table_sizes_query(), dialect.as_ref(), &pool),
+            );
+            MenuMetadata { databases, tables, objects, sizes }
+          })));
+        }
+      },
+      Action::MenuPreview(MenuPreview::Rows(preview), schema, table) if self.state.query_task.is_none() => {
+        let query = DB::preview_rows_query(schema, table, preview, self.config.settings.preview_limit());
+        self.rows_preview = Some((schema.clone(), table.clone(), preview.clone(), query));
+        self.breadcrumbs.clear();
+        self.update_breadcrumbs();
+      },
+      Action::LoadMoreRows(shown, last_row) => {
+        if let (Some((schema, table, preview, _)), None, Some(pool)) =
+          (&self.rows_preview, &self.state.query_task, &self.pool)
+        {
+          // a key that can't be compared with the values shown leaves `after` short, so the rest
+          // are skipped over instead
+          let after = preview
+            .primary_key
+            .iter()
+            .filter_map(|key| last_row.iter().find(|(column, ..)| column == key))
+            .filter(|(_, type_name, _)| database::keyset_type(type_name))
+            .map(|(.., value)| value.clone())
+            .collect();
+          let next = RowsPreview { after, offset: *shown, ..preview.clone() };
+          let query = DB::preview_rows_query(schema, table, &next, self.config.settings.preview_limit());
+          match database::get_first_query(query.clone(), self.state.dialect.as_ref()) {
+            Ok((_, statement_type)) => {
+              let pool = pool.clone();
+              let dialect = self.state.dialect.clone();
+              self.appending_rows = true;
+              self.state.query_task = Some(DbTask::Query(tokio::spawn(async move {
+                let results = database::query_values(query, dialect.as_ref(), &pool).await;
+                if let Err(e) = &results {
+                  log::error!("{e:?}");
+                }
+                QueryResultsWithMetadata { results, statement_type }
+              })));
+              self.state.last_query_start = Some(chrono::Utc::now());
+              self.state.last_query_end = None;
+            },
+            Err(e) => self.components.data.set_data_state(Some(Err(e)), None),
+          }
+        }
+      },
+      Action::RequestFilterRows(columns, column, value) => {
+        if let (Some((.., preview, _)), None) = (&self.rows_preview, &self.state.query_task) {
+          let filters = preview.filters.clone();
+          self.set_popup(Box::new(FilterRows::<DB>::new(columns.clone(), *column, value.clone(), filters)));
+        }
+      },
+      Action::FollowForeignKey(column, row, reverse) => {
+        if let (Some((schema, table, ..)), None, Some(pool)) = (&self.rows_preview, &self.state.query_task, &self.pool)
+        {
+          self.following = Some((column.clone(), row.clone(), *reverse));
+          let query = DB::preview_foreign_keys_query(schema, table);
+          let pool = pool.clone();
+          let dialect = self.state.dialect.clone();
+          self.state.metadata_tasks.push(MetadataTask::ForeignKeys(
+            schema.clone(),
+            table.clone(),
+            tokio::spawn(async move { database::query(query, dialect.as_ref(), &pool).await }),
+          ));
+        }
+      },
+      Action::FollowBack if self.state.query_task.is_none() => {
+        if let Some(previous) = self.breadcrumbs.pop() {
+          let query = previous.3.clone();
+          self.rows_preview = Some(previous);
+          self.update_breadcrumbs();
+          self.run_rows_preview(query)?;
+        }
+      },
+      Action::RequestErDiagram(schema, tables) if self.state.query_task.is_none() => {
+        if let Some(pool) = &self.pool {
+          self.components.data.set_loading();
+          let columns_query = DB::preview_all_columns_query();
+          let keys_query = DB::preview_schema_foreign_keys_query(schema);
+          let pool = pool.clone();
+          let dialect = self.state.dialect.clone();
+          self.state.metadata_tasks.push(MetadataTask::ErDiagram(
+            schema.clone(),
+            tables.clone(),
+            tokio::spawn(async move {
+              let columns = database::query(columns_query, dialect.as_ref(), &pool).await?;
+              let keys = database::query(keys_query, dialect.as_ref(), &pool).await?;
+              Ok((columns, keys))
+            }),
+          ));
+        }
+      },
+      Action::LoadMenuColumns(schema, table) => {
+        let loading = self
+          .state
+          .metadata_tasks
+          .iter()
+          .any(|task| matches!(task, MetadataTask::Columns(s, t, _) if s == schema && t == table));
+        if let (Some(pool), false) = (&self.pool, loading) {
+          let query = DB::preview_column_details_query(schema, table);
+          let pool = pool.clone();
+          let dialect = self.state.dialect.clone();
+          self.state.metadata_tasks.push(MetadataTask::Columns(
+            schema.clone(),
+            table.clone(),
+            tokio::spawn(async move { database::query(query, dialect.as_ref(), &pool).await }),
+          ));
+        }
+      },
+      Action::SwitchDatabase(database) => {
+        if self.state.query_task.is_some() {
+          log::warn!("not switching to {} while a query is running", database);
+        } else if let Some(opts) = DB::with_database(self.state.connection_opts.clone(), database) {
+          log::info!("switching to database {}", database);
+          // opening the pool can take a while, so it's swapped in by `poll_database_switch` once it's ready
+          if let Some((_, previous)) = self.database_switch.take() {
+            previous.abort();
+          }
+          let task = tokio::spawn(database::init_pool::<DB>(opts.clone()));
+          self.database_switch = Some((opts, task));
+        } else {
+          log::warn!("{} can't switch to database {}", DB::NAME, database);
+        }
+      },
+      Action::SavePinnedTables(pinned) => {
+        let data_dir = &self.config.config._data_dir;
+        let saved = PinnedTables::load(data_dir).and_then(|mut pinned_tables| {
+          let database = DB::database_name(&self.state.connection_opts);
+          pinned_tables.set(&self.name, database.as_deref(), pinned.clone());
+          pinned_tables.save(data_dir)
+        });
+        if let Err(e) = saved {
+          log::error!("failed to save pinned tables: {}", e);
+        }
+      },
+      Action::LoadAllColumns => {
+        let loading = self.state.metadata_tasks.iter().any(|task| matches!(task, MetadataTask::AllColumns(_)));
+        if let (Some(pool), false) = (&self.pool, loading) {
+          let pool = pool.clone();
+          let dialect = self.state.dialect.clone();
+          self.state.metadata_tasks.push(MetadataTask::AllColumns(tokio::spawn(async move {
+            database::query(DB::preview_all_columns_query(), dialect.as_ref(), &pool).await
+          })));
+        }
+      },
+      Action::RequestDefinition(kind, schema, name, table, target) => {
+        if let Some(pool) = &self.pool {
+          let query = DB::preview_definition_query(*kind, schema, name, table.as_deref());
+          let pool = pool.clone();
+          let dialect = self.state.dialect.clone();
+          self.state.metadata_tasks.push(MetadataTask::Definition(
+            *kind,
+            schema.clone(),
+            name.clone(),
+            *target,
+            tokio::spawn(async move { database::query(query, dialect.as_ref(), &pool).await }),
+          ));
+        }
+      },
+      Action::Query(query_lines, confirmed) => 'query_action: {
+        let query_string = query_lines.clone().join(" \n");
+        if query_string.is_empty() {
+          break 'query_action;
+        }
+        self.add_to_history(query_lines.clone());
+        // more rows can only be loaded for the preview that's shown
+        if self.rows_preview.as_ref().is_some_and(|(.., query)| *query != query_string) {
+          self.rows_preview = None;
+          self.breadcrumbs.clear();
+          self.update_breadcrumbs();
+        }
+        let first_query = database::get_first_query(query_string.clone(), self.state.dialect.as_ref());
+        let execution_type = first_query.map(|(_, statement_type)| {
+          (database::get_execution_type(statement_type.clone(), *confirmed), statement_type)
+        });
+        let action_tx = action_tx.clone();
+        if let Some(pool) = &self.pool {
+          let pool = pool.clone();
+          let dialect = self.state.dialect.clone();
+          match execution_type {
+            Ok((ExecutionType::Transaction, statement_type)) => {
+              self.components.data.set_loading();
+              let tx = pool.begin().await?;
+              self.state.query_task = Some(DbTask::TxStart(tokio::spawn(async move {
+                let (results, tx) = database::query_with_tx::<DB>(tx, dialect.as_ref(), query_string.clone()).await;
+                match results {
+                  Ok(Either::Left(rows_affected)) => {
+                    log::info!("{:?} rows affected", rows_affected);
+                    (
+                      QueryResultsWithMetadata {
+                        results: Ok(Rows { headers: vec![], rows: vec![], rows_affected: Some(rows_affected) }),
+                        statement_type,
+                      },
+                      tx,
+                    )
+                  },
+                  Ok(Either::Right(rows)) => {
+                    log::info!("{:?} rows affected", rows.rows_affected);
+                    (QueryResultsWithMetadata { results: Ok(rows), statement_type }, tx)
+                  },
+                  Err(e) => {
+                    log::error!("{e:?}");
+                    (QueryResultsWithMetadata { results: Err(e), statement_type }, tx)
+                  },
+                }
+              })));
+              self.state.last_query_start = Some(chrono::Utc::now());
+              self.state.last_query_end = None;
+            },
+            Ok((ExecutionType::Confirm, statement_type)) => {
+              self.set_popup(Box::new(ConfirmQuery::<DB>::new(query_string.clone(), statement_type)));
+            },
+            Ok((ExecutionType::Normal, statement_type)) => {
+              self.components.data.set_loading();
+              self.running_query = Some(query_lines.clone());
+              let dialect = self.state.dialect.clone();
+              self.state.query_task = Some(DbTask::Query(tokio::spawn(async move {
+                let results = database::query_values(query_string.clone(), dialect.as_ref(), &pool).await;
+                match &results {
+                  Ok(rows) => {
+                    log::info!("{:?} rows, {:?} affected", rows.rows.len(), rows.rows_affected);
+                  },
+                  Err(e) => {
+                    log::error!("{e:?}");
+                  },
+                };
+
+                QueryResultsWithMetadata { results, statement_type }
+              })));
+              self.state.last_query_start = Some(chrono::Utc::now());
+              self.state.last_query_end = None;
+            },
+            Err(e) => {
+              match database::maintenance_statement(&query_string) {
+                Some(statement) => {
+                  self.components.data.set_loading();
+                  self.running_query = Some(query_lines.clone());
+                  let statement_type = statement.split_whitespace().next().unwrap_or_default().to_uppercase();
+                  self.state.query_task = Some(DbTask::Maintenance(
+                    statement_type,
+                    tokio::spawn(async move {
+                      let results = database::maintenance_query(statement, &pool).await;
+                      if let Err(e) = &results {
+                        log::error!("{e:?}");
+                      }
+                      results
+                    }),
+                  ));
+                  self.state.last_query_start = Some(chrono::Utc::now());
+                  self.state.last_query_end = None;
+                },
+                None => self.components.data.set_data_state(Some(Err(e)), None),
+              }
+            },
+          }
+        } else {
+          log::error!("No connection pool");
+          self.components.data.set_data_state(Some(Err(DbError::Left(sqlx::Error::PoolTimedOut))), None)
+        }
+      },
+      Action::AbortQuery => {
+        match &self.state.query_task {
+          Some(DbTask::Query(task)) => {
+            task.abort();
+            self.state.query_task = None;
+            // the rows shown stay when loading more of them is cancelled
+            if !std::mem::take(&mut self.appending_rows) {
+              self.components.data.set_cancelled();
+            }
+            self.state.last_query_end = Some(chrono::Utc::now());
+          },
+          Some(DbTask::TxStart(task)) => {
+            task.abort();
+            self.state.query_task = None;
+            self.components.data.set_cancelled();
+            self.state.last_query_end = Some(chrono::Utc::now());
+          },
+          Some(DbTask::Maintenance(_, task)) => {
+            task.abort();
+            self.state.query_task = None;
+            self.components.data.set_cancelled();
+            self.state.last_query_end = Some(chrono::Utc::now());
+          },
+          // without a query to cancel, stop loading the menu instead
+          None => {
+            for task in self.state.metadata_tasks.drain(..) {
+              task.abort();
+            }
+          },
+          _ => {},
+        }
+      },
+      Action::RequestSaveFavorite(query_lines) => {
+        self.set_popup(Box::new(NameFavorite::<DB>::new(
+          self.state.favorites.iter().map(|f| f.get_name().to_string()).collect(),
+          query_lines.clone(),
+        )));
+      },
+      Action::DeleteFavorite(name) => {
+        self.state.favorites.delete_entry(name.clone());
+      },
+      Action::ClearHistory => {
+        self.clear_history();
+      },
+      Action::RequestExportData(row_count) => {
+        self.set_popup(Box::new(ConfirmExport::<DB>::new(*row_count)));
+      },
+      Action::ExportDataFinished => {
+        self.set_focus(Focus::Data);
+      },
+      _ => {},
+    }
+    if !action_consumed {
+      for i in ComponentImpls::iter() {
+        let action = match i {
+          ComponentImpls::Menu => self.components.menu.update(action.clone(), &self.state)?,
+          ComponentImpls::Editor => self.components.editor.update(action.clone(), &self.state)?,
+          ComponentImpls::History => self.components.history.update(action.clone(), &self.state)?,
+          ComponentImpls::Data => self.components.data.update(action.clone(), &self.state)?,
+          ComponentImpls::Favorites => self.components.favorites.update(action.clone(), &self.state)?,
+        };
+        if let Some(action) = action {
+          log::info!("{action:?}");
+          action_tx.send(action)?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn draw(&mut self, f: &mut Frame, area: Rect) -> Result<()> {
+    self.draw_layout(f, area)
+  }
+
+  fn has_mouse_event(&self) -> bool {
+    self.last_frame_mouse_event.is_some()
+  }
+
+  fn select_connection(&mut self, entries: Vec<ConnectionEntry>, error: Option<String>) {
+    // a pending transaction must be committed or rolled back first
+    if matches!(self.state.query_task, Some(DbTask::TxPending(_, _))) {
+      return;
+    }
+    self.set_popup(Box::new(SelectConnection::<DB>::new(entries, error)));
+  }
+
+  fn take_connection_request(&mut self) -> Option<ConnectionRequest> {
+    self.connection_request.take()
+  }
+
+  fn editor_lines(&self) -> Vec<String> {
+    self.components.editor.get_lines()
+  }
+
+  fn set_editor_lines(&mut self, lines: Vec<String>) {
+    self.components.editor.set_lines(lines);
+  }
+
+  async fn close(&mut self) {
+    self.abort_query_task();
+    if let Some(task) = self.health_task.take() {
+      task.abort();
+    }
+    if let Some((_, task)) = self.database_switch.take() {
+      task.abort();
+    }
+    if let Some(pool) = self.pool.take() {
+      pool.close().await;
+    }
+  }
+}
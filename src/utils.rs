@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use color_eyre::eyre::Result;
 use directories::{ProjectDirs, UserDirs};
@@ -125,6 +125,14 @@ pub fn get_export_dir() -> PathBuf {
   directory
 }
 
+// expands a leading `~` to the user's home directory
+pub fn expand_home(path: &Path) -> PathBuf {
+  match (path.strip_prefix("~"), user_directory()) {
+    (Ok(rest), Some(user_dir)) => user_dir.home_dir().join(rest),
+    _ => path.to_path_buf(),
+  }
+}
+
 pub fn initialize_logging() -> Result<()> {
   let directory = get_data_dir();
   std::fs::create_dir_all(directory.clone())?;